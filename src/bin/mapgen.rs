@@ -0,0 +1,6 @@
+use std::env;
+
+fn main() {
+    let args : Vec<String> = env::args().collect();
+    wieclaw_rl::mapgen::run_from_args(&args);
+}
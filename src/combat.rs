@@ -0,0 +1,507 @@
+use specs::prelude::*;
+use rltk::RandomNumberGenerator;
+use crate::{Attributes, Skills, Skill, skill_bonus, Weapon, WeaponAttribute, EquipmentSlot, Equipped, Wearable, NaturalAttackDefense,
+            HungerClock, HungerState, InBackpack, Ammunition, Magazine, Reloading, DamageType, Maneuver, effects::*};
+
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum AttackKind { Melee, Ranged }
+
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum HitResult { Fumble, Miss, Hit, Critical }
+
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum ProcTarget { Attacker, Defender }
+
+// What a fumble does to the weapon in hand, fists and natural attacks only stumble
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum Fumble { DropWeapon { item : Entity }, DamageWeapon { item : Entity } }
+
+// Everything the attacker brings to the swing or the shot
+pub struct Attacker<'a> {
+    pub attributes : &'a Attributes,
+    pub skills : &'a Skills,
+    pub well_fed : bool
+}
+
+pub struct Defender<'a> {
+    pub attributes : &'a Attributes,
+    pub skills : &'a Skills,
+    pub natural_armor : Option<i32>,
+    pub item_armor : f32,
+    pub unaware : bool
+}
+
+// The weapon used for one attack, item is None for fists and natural attacks
+pub struct AttackProfile {
+    pub kind : AttackKind,
+    pub weapon : Weapon,
+    pub item : Option<Entity>
+}
+
+#[derive(Clone, Debug)]
+pub struct DamageBreakdown {
+    pub dice : i32,
+    pub attribute : i32,
+    pub skill : i32,
+    pub weapon : i32,
+    pub sneak : i32,
+    pub total : i32,
+    pub damage_type : DamageType
+}
+
+#[derive(Clone, Debug)]
+pub struct Proc {
+    pub item : Entity,
+    pub target : ProcTarget
+}
+
+#[derive(Clone, Debug)]
+pub struct AttackOutcome {
+    pub result : HitResult,
+    pub natural_roll : i32,
+    pub hit_roll : i32,
+    pub armor_class : i32,
+    pub damage : Option<DamageBreakdown>,
+    pub procs : Vec<Proc>,
+    pub fumble : Option<Fumble>
+}
+
+#[derive(Clone, Debug)]
+pub struct ManeuverOutcome {
+    pub success : bool,
+    pub attack_roll : i32,
+    pub defense_roll : i32
+}
+
+fn unarmed() -> Weapon {
+    Weapon{
+        range: None,
+        attribute: WeaponAttribute::Might,
+        hit_bonus: 0,
+        damage_n_dice: 1,
+        damage_die_type: 4,
+        damage_bonus: 0,
+        proc_chance: None,
+        proc_target: None,
+        damage_type: DamageType::Physical,
+        crit_range: 20,
+        crit_multiplier: 2
+    }
+}
+
+// A wielded weapon fit for the attack wins, otherwise one of the natural attacks, otherwise bare fists
+pub fn choose_weapon(rng : &mut RandomNumberGenerator, attacker : Entity, kind : AttackKind, entities : &Entities,
+    equipped : &ReadStorage<Equipped>, weapons : &ReadStorage<Weapon>, natural : &ReadStorage<NaturalAttackDefense>) -> AttackProfile
+{
+    let mut profile = AttackProfile{ kind, weapon: unarmed(), item: None };
+
+    if let Some(nat) = natural.get(attacker) {
+        if !nat.attacks.is_empty() {
+            let attack = &nat.attacks[if nat.attacks.len()==1 { 0 } else { rng.roll_dice(1, nat.attacks.len() as i32) as usize -1 }];
+            profile.weapon.hit_bonus = attack.hit_bonus;
+            profile.weapon.damage_n_dice = attack.damage_n_dice;
+            profile.weapon.damage_die_type = attack.damage_die_type;
+            profile.weapon.damage_bonus = attack.damage_bonus;
+            profile.weapon.damage_type = attack.damage_type;
+        }
+    }
+
+    for (item, wielded, weapon) in (entities, equipped, weapons).join() {
+        let fits = match kind {
+            AttackKind::Melee => wielded.slot == EquipmentSlot::Melee,
+            AttackKind::Ranged => wielded.slot == EquipmentSlot::Ranged
+        };
+        if wielded.owner == attacker && fits {
+            profile.weapon = weapon.clone();
+            profile.item = Some(item);
+        }
+    }
+
+    profile
+}
+
+// Fills the magazine from matching stacks in the owner's pack and keeps the owner busy for the reload,
+// returning how many shots went in
+pub fn reload(owner : Entity, magazine : &mut Magazine, entities : &Entities, backpack : &ReadStorage<InBackpack>,
+    ammunition : &mut WriteStorage<Ammunition>, reloading : &mut WriteStorage<Reloading>) -> i32
+{
+    let mut loaded = 0;
+    for (stack_entity, carried, stack) in (entities, backpack, &mut *ammunition).join() {
+        if carried.owner != owner || stack.kind != magazine.ammo || magazine.loaded >= magazine.capacity { continue; }
+        let taken = i32::min(stack.count, magazine.capacity - magazine.loaded);
+        stack.count -= taken;
+        magazine.loaded += taken;
+        loaded += taken;
+        if stack.count < 1 {
+            entities.delete(stack_entity).expect("Unable to delete");
+        }
+    }
+    // The turn spent giving the order counts as the first one
+    if loaded > 0 && magazine.reload_turns > 1 {
+        reloading.insert(owner, Reloading{ turns: magazine.reload_turns - 1 }).expect("Unable to insert");
+    }
+    loaded
+}
+
+pub fn has_ammo_for(owner : Entity, magazine : &Magazine, backpack : &ReadStorage<InBackpack>, ammunition : &WriteStorage<Ammunition>) -> bool {
+    (backpack, ammunition).join().any(|(carried, stack)| carried.owner == owner && stack.kind == magazine.ammo && stack.count > 0)
+}
+
+pub fn worn_armor(target : Entity, equipped : &ReadStorage<Equipped>, wearables : &ReadStorage<Wearable>) -> f32 {
+    (equipped, wearables).join()
+        .filter(|(wielded, _)| wielded.owner == target)
+        .map(|(_, armor)| armor.armor_class)
+        .sum()
+}
+
+pub fn is_well_fed(entity : Entity, hunger : &ReadStorage<HungerClock>) -> bool {
+    hunger.get(entity).is_some_and(|hc| hc.state == HungerState::WellFed)
+}
+
+pub fn armor_class(defender : &Defender) -> i32 {
+    defender.natural_armor.unwrap_or(10)
+        + defender.attributes.quickness.bonus
+        + skill_bonus(Skill::Defense, defender.skills)
+        + defender.item_armor as i32
+}
+
+// Rolls one attack without touching the world, so a seeded generator always gives the same outcome
+pub fn resolve_attack(rng : &mut RandomNumberGenerator, attacker : &Attacker, defender : &Defender, profile : &AttackProfile) -> AttackOutcome {
+    let weapon = &profile.weapon;
+    let natural_roll = rng.roll_dice(1, 20);
+    let attribute_hit_bonus = if weapon.attribute == WeaponAttribute::Might
+        { attacker.attributes.might.bonus }
+        else { attacker.attributes.quickness.bonus };
+    let skill = skill_bonus(Skill::Melee, attacker.skills);
+    let status_hit_bonus = if attacker.well_fed { 1 } else { 0 };
+    let hit_roll = natural_roll + attribute_hit_bonus + skill + weapon.hit_bonus + status_hit_bonus;
+    let armor_class = armor_class(defender);
+
+    // A natural 20 always crits, the rest of the weapon's crit range only when it would hit anyway
+    let result = if natural_roll == 1 {
+        HitResult::Fumble
+    } else if natural_roll == 20 || (natural_roll >= weapon.crit_range && hit_roll > armor_class) {
+        HitResult::Critical
+    } else if hit_roll > armor_class {
+        HitResult::Hit
+    } else {
+        HitResult::Miss
+    };
+
+    let mut outcome = AttackOutcome{ result, natural_roll, hit_roll, armor_class, damage: None, procs: Vec::new(), fumble: None };
+    if result == HitResult::Fumble {
+        if let Some(item) = profile.item {
+            outcome.fumble = match rng.roll_dice(1, 6) {
+                1 => Some(Fumble::DropWeapon{ item }),
+                2 => Some(Fumble::DamageWeapon{ item }),
+                _ => None
+            };
+        }
+        return outcome;
+    }
+    if result != HitResult::Hit && result != HitResult::Critical { return outcome; }
+
+    // A critical rolls the weapon's dice once per point of its multiplier
+    let rolls = if result == HitResult::Critical { i32::max(1, weapon.crit_multiplier) } else { 1 };
+    let mut dice = 0;
+    for _ in 0..rolls {
+        dice += rng.roll_dice(weapon.damage_n_dice, weapon.damage_die_type);
+    }
+    // Catching the target unawares rolls the dice once more and rewards the sneaking
+    let sneak = if defender.unaware {
+        rng.roll_dice(weapon.damage_n_dice, weapon.damage_die_type) + i32::max(0, skill_bonus(Skill::Stealth, attacker.skills))
+    } else {
+        0
+    };
+    let attribute = attacker.attributes.might.bonus;
+    // Blows have always counted the Melee skill twice towards damage, shots only once
+    let skill_damage = if profile.kind == AttackKind::Melee { skill * 2 } else { skill };
+    let total = i32::max(0, dice + attribute + skill_damage + weapon.damage_bonus + sneak);
+    outcome.damage = Some(DamageBreakdown{ dice, attribute, skill: skill_damage, weapon: weapon.damage_bonus, sneak, total, damage_type: weapon.damage_type });
+
+    if let (Some(chance), Some(target), Some(item)) = (&weapon.proc_chance, &weapon.proc_target, profile.item) {
+        if rng.roll_dice(1, 100) <= (chance * 100.0) as i32 {
+            let target = if target == "Self" { ProcTarget::Attacker } else { ProcTarget::Defender };
+            outcome.procs.push(Proc{ item, target });
+        }
+    }
+
+    outcome
+}
+
+// How far the sound of a fight carries, gunfire much further than blows
+pub fn attack_noise(kind : AttackKind) -> i32 {
+    match kind {
+        AttackKind::Melee => 6,
+        AttackKind::Ranged => 10
+    }
+}
+
+// Queues the damage, procs and particles of an attack and writes it to the log
+pub fn apply_outcome(attacker : Entity, target : Entity, kind : AttackKind, outcome : &AttackOutcome, attacker_name : &str, target_name : &str,
+    attacker_idx : usize)
+{
+    add_effect(Some(attacker), EffectType::Noise{ volume: attack_noise(kind) }, Targets::Tile{ tile_idx: attacker_idx as i32 });
+    match outcome.result {
+        HitResult::Hit | HitResult::Critical => {
+            let damage = outcome.damage.as_ref().map_or(0, |d| d.total);
+            let damage_type = outcome.damage.as_ref().map_or(DamageType::Physical, |d| d.damage_type);
+            let sneak = outcome.damage.as_ref().is_some_and(|d| d.sneak > 0);
+            add_effect(
+                Some(attacker),
+                EffectType::Damage{ amount: damage, damage_type },
+                Targets::Single{ target }
+            );
+            crate::gamelog::Logger::new()
+                .npc_name(attacker_name)
+                .append(match (outcome.result, sneak) {
+                    (HitResult::Critical, true) => "trafia z ukrycia, krytycznie:",
+                    (HitResult::Critical, false) => "trafia krytycznie:",
+                    (_, true) => "trafia z ukrycia:",
+                    _ => "trafia:"
+                })
+                .npc_name(target_name)
+                .append("za")
+                .damage(damage)
+                .append("HP.")
+                .log();
+
+            for proc in outcome.procs.iter() {
+                let proc_target = match proc.target {
+                    ProcTarget::Attacker => attacker,
+                    ProcTarget::Defender => target
+                };
+                add_effect(
+                    Some(attacker),
+                    EffectType::ItemUse{ item: proc.item },
+                    Targets::Single{ target: proc_target }
+                );
+            }
+        }
+        HitResult::Fumble => {
+            let (tries, fails) = match kind {
+                AttackKind::Melee => ("chce zaatakowac:", "ale potyka sie o wlasne nogi."),
+                AttackKind::Ranged => ("chce strzelic w:", "ale za pózno reaguje.")
+            };
+            crate::gamelog::Logger::new()
+                .npc_name(attacker_name)
+                .append(tries)
+                .npc_name(target_name)
+                .append(fails)
+                .log();
+            match outcome.fumble {
+                Some(Fumble::DropWeapon{ item }) => {
+                    add_effect(Some(attacker), EffectType::DropWeapon{ item }, Targets::Single{ target: attacker });
+                }
+                Some(Fumble::DamageWeapon{ item }) => {
+                    add_effect(Some(attacker), EffectType::DamageWeapon{ item }, Targets::Single{ target: attacker });
+                }
+                None => {}
+            }
+            add_effect(
+                None,
+                EffectType::Particle{ glyph: rltk::to_cp437('‼'), fg: rltk::RGB::named(rltk::BLUE), bg: rltk::RGB::named(rltk::BLACK), lifespan: 200.0 },
+                Targets::Single{ target }
+            );
+        }
+        HitResult::Miss => {
+            crate::gamelog::Logger::new()
+                .npc_name(attacker_name)
+                .append("atakuje:")
+                .npc_name(target_name)
+                .append(match kind {
+                    AttackKind::Melee => "ale bezskutecznie.",
+                    AttackKind::Ranged => "ale nie trafia."
+                })
+                .log();
+            add_effect(
+                None,
+                EffectType::Particle{ glyph: rltk::to_cp437('‼'), fg: rltk::RGB::named(rltk::CYAN), bg: rltk::RGB::named(rltk::BLACK), lifespan: 200.0 },
+                Targets::Single{ target }
+            );
+        }
+    }
+}
+
+// Which attribute each side leans on, the attacker adds Melee and the defender Defense
+fn maneuver_attributes(maneuver : Maneuver) -> (WeaponAttribute, WeaponAttribute) {
+    match maneuver {
+        Maneuver::Shove => (WeaponAttribute::Might, WeaponAttribute::Might),
+        Maneuver::Disarm => (WeaponAttribute::Quickness, WeaponAttribute::Might),
+        Maneuver::Trip => (WeaponAttribute::Quickness, WeaponAttribute::Quickness)
+    }
+}
+
+fn attribute_bonus(attributes : &Attributes, attribute : WeaponAttribute) -> i32 {
+    match attribute {
+        WeaponAttribute::Might => attributes.might.bonus,
+        WeaponAttribute::Quickness => attributes.quickness.bonus
+    }
+}
+
+// An opposed d20 roll, ties go to the defender
+pub fn resolve_maneuver(rng : &mut RandomNumberGenerator, attacker : &Attacker, defender : &Defender, maneuver : Maneuver) -> ManeuverOutcome {
+    let (attack_attribute, defense_attribute) = maneuver_attributes(maneuver);
+    let status_bonus = if attacker.well_fed { 1 } else { 0 };
+    let attack_roll = rng.roll_dice(1, 20)
+        + attribute_bonus(attacker.attributes, attack_attribute)
+        + skill_bonus(Skill::Melee, attacker.skills)
+        + status_bonus;
+    let defense_roll = rng.roll_dice(1, 20)
+        + attribute_bonus(defender.attributes, defense_attribute)
+        + skill_bonus(Skill::Defense, defender.skills);
+    ManeuverOutcome{ success: attack_roll > defense_roll, attack_roll, defense_roll }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use crate::Attribute;
+
+    fn attributes(bonus : i32) -> Attributes {
+        let attribute = Attribute{ base: 11, modifiers: 0, bonus };
+        Attributes{ might: attribute.clone(), fitness: attribute.clone(), quickness: attribute.clone(), intelligence: attribute }
+    }
+
+    fn skills(melee : i32, stealth : i32) -> Skills {
+        let mut skills = HashMap::new();
+        skills.insert(Skill::Melee, melee);
+        skills.insert(Skill::Defense, 0);
+        skills.insert(Skill::Stealth, stealth);
+        Skills{ skills }
+    }
+
+    fn sword() -> Weapon {
+        Weapon{ damage_n_dice: 1, damage_die_type: 8, damage_bonus: 1, ..unarmed() }
+    }
+
+    fn profile(kind : AttackKind, item : Option<Entity>) -> AttackProfile {
+        AttackProfile{ kind, weapon: sword(), item }
+    }
+
+    // Rolls the same attack many times over, the seed keeps every run identical
+    fn roll_many(attacker : &Attacker, defender : &Defender, profile : &AttackProfile) -> Vec<AttackOutcome> {
+        let mut rng = RandomNumberGenerator::seeded(7);
+        (0..500).map(|_| resolve_attack(&mut rng, attacker, defender, profile)).collect()
+    }
+
+    #[test]
+    fn the_same_seed_gives_the_same_attack() {
+        let (attr, skill) = (attributes(1), skills(2, 0));
+        let attacker = Attacker{ attributes: &attr, skills: &skill, well_fed: false };
+        let defender = Defender{ attributes: &attr, skills: &skill, natural_armor: None, item_armor: 0.0, unaware: false };
+        let profile = profile(AttackKind::Melee, None);
+
+        let first : Vec<(HitResult, i32)> = roll_many(&attacker, &defender, &profile).iter().map(|o| (o.result, o.hit_roll)).collect();
+        let second : Vec<(HitResult, i32)> = roll_many(&attacker, &defender, &profile).iter().map(|o| (o.result, o.hit_roll)).collect();
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn natural_rolls_decide_fumbles_misses_hits_and_crits() {
+        let (attr, skill) = (attributes(1), skills(2, 0));
+        let attacker = Attacker{ attributes: &attr, skills: &skill, well_fed: true };
+        let defender = Defender{ attributes: &attr, skills: &skill, natural_armor: Some(14), item_armor: 2.0, unaware: false };
+        let profile = profile(AttackKind::Melee, None);
+        let armor = armor_class(&defender);
+        // natural armor + Quickness + worn armor
+        assert_eq!(armor, 14 + 1 + 2);
+
+        let outcomes = roll_many(&attacker, &defender, &profile);
+        for outcome in outcomes.iter() {
+            assert_eq!(outcome.armor_class, armor);
+            // natural + Might + Melee + well fed
+            assert_eq!(outcome.hit_roll, outcome.natural_roll + 1 + 2 + 1);
+            let expected = match outcome.natural_roll {
+                1 => HitResult::Fumble,
+                20 => HitResult::Critical,
+                _ if outcome.hit_roll > armor => HitResult::Hit,
+                _ => HitResult::Miss
+            };
+            assert_eq!(outcome.result, expected, "natural {}", outcome.natural_roll);
+            let hits = matches!(outcome.result, HitResult::Hit | HitResult::Critical);
+            assert_eq!(outcome.damage.is_some(), hits);
+            // Fists and natural attacks never lose a weapon
+            assert!(outcome.fumble.is_none());
+        }
+        for result in [HitResult::Fumble, HitResult::Miss, HitResult::Hit, HitResult::Critical] {
+            assert!(outcomes.iter().any(|o| o.result == result), "never rolled {:?}", result);
+        }
+    }
+
+    #[test]
+    fn a_wider_crit_range_only_crits_when_it_would_hit() {
+        let (attr, skill) = (attributes(0), skills(0, 0));
+        let attacker = Attacker{ attributes: &attr, skills: &skill, well_fed: false };
+        let defender = Defender{ attributes: &attr, skills: &skill, natural_armor: Some(18), item_armor: 0.0, unaware: false };
+        let mut profile = profile(AttackKind::Melee, None);
+        profile.weapon.crit_range = 17;
+
+        for outcome in roll_many(&attacker, &defender, &profile).iter() {
+            match outcome.natural_roll {
+                17 | 18 => assert_eq!(outcome.result, HitResult::Miss),
+                19 => assert_eq!(outcome.result, HitResult::Critical),
+                _ => {}
+            }
+        }
+    }
+
+    #[test]
+    fn damage_adds_up_and_blows_count_melee_twice() {
+        let (attr, skill) = (attributes(2), skills(3, 0));
+        let attacker = Attacker{ attributes: &attr, skills: &skill, well_fed: false };
+        let defender = Defender{ attributes: &attr, skills: &skill, natural_armor: Some(5), item_armor: 0.0, unaware: false };
+
+        for (kind, skill_damage) in [(AttackKind::Melee, 6), (AttackKind::Ranged, 3)] {
+            let profile = profile(kind, None);
+            for outcome in roll_many(&attacker, &defender, &profile).iter() {
+                let damage = match &outcome.damage { None => continue, Some(damage) => damage };
+                let rolls = if outcome.result == HitResult::Critical { 2 } else { 1 };
+                assert!(damage.dice >= rolls && damage.dice <= 8 * rolls, "dice {} from {} rolls", damage.dice, rolls);
+                assert_eq!(damage.attribute, 2);
+                assert_eq!(damage.skill, skill_damage);
+                assert_eq!(damage.weapon, 1);
+                assert_eq!(damage.sneak, 0);
+                assert_eq!(damage.total, damage.dice + damage.attribute + damage.skill + damage.weapon);
+                assert_eq!(damage.damage_type, DamageType::Physical);
+            }
+        }
+    }
+
+    #[test]
+    fn unaware_targets_take_sneak_damage() {
+        let (attr, skill) = (attributes(0), skills(0, 2));
+        let attacker = Attacker{ attributes: &attr, skills: &skill, well_fed: false };
+        let defender = Defender{ attributes: &attr, skills: &skill, natural_armor: Some(5), item_armor: 0.0, unaware: true };
+        let profile = profile(AttackKind::Melee, None);
+
+        let outcomes = roll_many(&attacker, &defender, &profile);
+        assert!(outcomes.iter().any(|o| o.damage.is_some()));
+        for damage in outcomes.iter().filter_map(|o| o.damage.as_ref()) {
+            // One more roll of the dice plus the Stealth skill
+            assert!((3..=10).contains(&damage.sneak), "sneak {}", damage.sneak);
+            assert_eq!(damage.total, damage.dice + damage.skill + damage.weapon + damage.sneak);
+        }
+    }
+
+    #[test]
+    fn fumbling_with_a_weapon_can_drop_or_damage_it() {
+        let mut world = World::new();
+        let item = world.create_entity().build();
+        let (attr, skill) = (attributes(0), skills(0, 0));
+        let attacker = Attacker{ attributes: &attr, skills: &skill, well_fed: false };
+        let defender = Defender{ attributes: &attr, skills: &skill, natural_armor: None, item_armor: 0.0, unaware: false };
+        let profile = profile(AttackKind::Melee, Some(item));
+
+        let mut rng = RandomNumberGenerator::seeded(3);
+        let fumbles : Vec<AttackOutcome> = (0..5000)
+            .map(|_| resolve_attack(&mut rng, &attacker, &defender, &profile))
+            .filter(|o| o.result == HitResult::Fumble)
+            .collect();
+        assert!(fumbles.iter().all(|o| o.damage.is_none()));
+        assert!(fumbles.iter().any(|o| o.fumble == Some(Fumble::DropWeapon{ item })));
+        assert!(fumbles.iter().any(|o| o.fumble == Some(Fumble::DamageWeapon{ item })));
+        assert!(fumbles.iter().any(|o| o.fumble.is_none()));
+    }
+}
@@ -4,6 +4,7 @@ use specs_derive::*;
 use rltk::{RGB, Point};
 use serde::{Serialize, Deserialize};
 use std::collections::HashMap;
+use crate::map::MapId;
 
 
 #[derive(Component, ConvertSaveload, Clone)]
@@ -26,8 +27,9 @@ pub struct Renderable {
 pub struct Player {}
 
 
-#[derive(Component, ConvertSaveload, Clone)]
+#[derive(Component, Serialize, Deserialize, Clone)]
 pub struct Viewshed {
+    #[serde(with = "crate::map::rle::spans")]
     pub visible_tiles : Vec<rltk::Point>,
     pub range : i32,
     pub dirty : bool
@@ -94,9 +96,22 @@ pub struct Ranged {
     pub range : i32
 }
 
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug, Serialize, Deserialize)]
+pub enum DamageType { Physical, Fire, Cold, Poison, Magic }
+
+#[derive(PartialEq, Copy, Clone, Debug, Serialize, Deserialize)]
+pub enum Resistance { Resistant, Immune, Vulnerable }
+
+// How a creature, or whoever wears the item, takes each type of damage
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Resistances {
+    pub damage : HashMap<DamageType, Resistance>
+}
+
 #[derive(Component, Debug, ConvertSaveload, Clone)]
 pub struct InflictsDamage {
-    pub damage : i32
+    pub damage : i32,
+    pub damage_type : DamageType
 }
 
 #[derive(Component, Debug, ConvertSaveload, Clone)]
@@ -132,7 +147,7 @@ pub struct DMSerializationHelper {
 }
 
 #[derive(PartialEq, Copy, Clone, Serialize, Deserialize)]
-pub enum EquipmentSlot { Melee, Shield, Head, Torso, Legs, Feet, Hands }
+pub enum EquipmentSlot { Melee, Ranged, Shield, Head, Torso, Legs, Feet, Hands }
 
 #[derive(Component, Serialize, Deserialize, Clone)]
 pub struct Equippable {
@@ -157,7 +172,10 @@ pub struct Weapon {
     pub damage_bonus : i32,
     pub hit_bonus : i32,
     pub proc_chance : Option<f32>,
-    pub proc_target : Option<String>
+    pub proc_target : Option<String>,
+    pub damage_type : DamageType,
+    pub crit_range : i32,
+    pub crit_multiplier : i32
 }
 
 #[derive(Component, Serialize, Deserialize, Clone)]
@@ -206,8 +224,12 @@ pub struct Hidden {}
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
 pub struct EntryTrigger {}
 
+// Where the entity stood before the move, so sliding keeps going the same way
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
-pub struct EntityMoved {}
+pub struct EntityMoved {
+    #[serde(default)]
+    pub from : usize
+}
 
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
 pub struct SingleActivation {}
@@ -241,7 +263,7 @@ pub struct Attributes {
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq, Hash)]
-pub enum Skill { Melee, Defense, Magic }
+pub enum Skill { Melee, Defense, Magic, Search, Stealth }
 
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
 pub struct Skills {
@@ -272,7 +294,8 @@ pub struct NaturalAttack {
     pub damage_n_dice: i32,
     pub damage_die_type: i32,
     pub damage_bonus: i32,
-    pub hit_bonus: i32
+    pub hit_bonus: i32,
+    pub damage_type : DamageType
 }
 
 #[derive(Component, Serialize, Deserialize, Clone)]
@@ -290,7 +313,7 @@ pub struct LootTable {
 pub struct OtherLevelPosition {
     pub x: i32,
     pub y: i32,
-    pub depth: i32
+    pub map: MapId
 }
 
 #[derive(Component, Serialize, Deserialize, Clone)]
@@ -354,7 +377,7 @@ pub struct TownPortal {}
 pub struct TeleportTo {
     pub x: i32,
     pub y: i32,
-    pub depth: i32,
+    pub map: MapId,
     pub player_only: bool
 }
 
@@ -367,7 +390,7 @@ pub struct ApplyMove {
 pub struct ApplyTeleport {
     pub dest_x : i32,
     pub dest_y : i32,
-    pub dest_depth : i32
+    pub dest_map : MapId
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq, Hash)]
@@ -458,7 +481,42 @@ pub struct Slow {
 
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
 pub struct DamageOverTime {
-    pub damage : i32
+    pub damage : i32,
+    pub damage_type : DamageType
+}
+
+// Wears down rock: every swing of an equipped tool, or the blast of a used item
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Digs {
+    pub power : i32
+}
+
+// Fills the tiles it is set off on with poison gas of this strength
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct PoisonGas {
+    pub strength : i32
+}
+
+// A stack of shots for every weapon that takes this kind
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Ammunition {
+    pub kind : String,
+    pub count : i32
+}
+
+// What a ranged weapon has loaded and what it takes to load it again
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Magazine {
+    pub ammo : String,
+    pub capacity : i32,
+    pub loaded : i32,
+    pub reload_turns : i32
+}
+
+// Turns the owner still spends reloading instead of acting
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Reloading {
+    pub turns : i32
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -497,4 +555,38 @@ pub struct Target {}
 #[derive(Component, Debug, ConvertSaveload, Clone)]
 pub struct WantsToShoot {
     pub target : Entity
-}
\ No newline at end of file
+}
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Serialize, Deserialize)]
+pub enum Maneuver { Shove, Disarm, Trip }
+
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct WantsToManeuver {
+    pub maneuver : Maneuver,
+    pub target : Entity
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ManeuverAbility {
+    pub maneuver : Maneuver,
+    pub chance : f32
+}
+
+// Maneuvers a mob tries instead of a plain attack when next to its target
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Maneuvers {
+    pub maneuvers : Vec<ManeuverAbility>
+}
+
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Prone {}
+
+#[derive(PartialEq, Eq, Copy, Clone, Serialize, Deserialize, Debug)]
+pub enum AwarenessState { Asleep, Unaware, Suspicious, Alert }
+
+// What a mob knows about the player, mobs without it always know where the player is
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Awareness {
+    pub state : AwarenessState,
+    pub investigate : Option<usize>,
+    pub suspicion : i32
+}
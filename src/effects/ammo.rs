@@ -0,0 +1,33 @@
+use specs::prelude::*;
+use super::*;
+use crate::{map::Map, Position, Ammunition, raws::{RAWS, spawn_named_item, SpawnType}};
+
+// Some shots can be picked up again where they landed, joining any stack already lying there
+pub fn spent_ammo(ecs: &mut World, effect: &EffectSpawner, tile_idx : i32) {
+    if let EffectType::SpentAmmo{ kind } = &effect.effect_type {
+        let raws = RAWS.lock().unwrap();
+        let (name, recovery) = match raws.ammunition_of_kind(kind) {
+            None => return,
+            Some((name, recovery)) => (name.to_string(), recovery)
+        };
+        if crate::rng::roll_dice(1, 100) > (recovery * 100.0) as i32 { return; }
+
+        let (x, y) = {
+            let map = ecs.fetch::<Map>();
+            (tile_idx % map.width, tile_idx / map.width)
+        };
+        {
+            let positions = ecs.read_storage::<Position>();
+            let mut stacks = ecs.write_storage::<Ammunition>();
+            if let Some((_, stack)) = (&positions, &mut stacks).join().find(|(pos, stack)| pos.x == x && pos.y == y && stack.kind == *kind) {
+                stack.count += 1;
+                return;
+            }
+        }
+        if let Some(shot) = spawn_named_item(&raws, ecs, &name, SpawnType::AtPosition{ x, y }) {
+            if let Some(stack) = ecs.write_storage::<Ammunition>().get_mut(shot) {
+                stack.count = 1;
+            }
+        }
+    }
+}
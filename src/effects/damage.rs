@@ -1,7 +1,60 @@
 use specs::{prelude::*, saveload::{SimpleMarker, MarkedBuilder}};
 use super::*;
 use crate::{Pools, Map, Attributes, Player, player_hp_at_level, mana_at_level, Confusion, StatusEffect, Duration,
-            SerializeMe, Name, EquipmentChanged, Slow, DamageOverTime, Skills};
+            SerializeMe, Name, EquipmentChanged, Slow, DamageOverTime, Skills, DamageType, Resistance, Resistances, Equipped,
+            Awareness, AwarenessState};
+
+// Polish name of a damage type, for the log
+pub fn damage_type_name(damage_type : DamageType) -> &'static str {
+    match damage_type {
+        DamageType::Physical => "obrazenia fizyczne",
+        DamageType::Fire => "ogien",
+        DamageType::Cold => "zimno",
+        DamageType::Poison => "trucizne",
+        DamageType::Magic => "magie"
+    }
+}
+
+// The target's own resistances and those of what it wears, immunity beats everything,
+// otherwise resistances and vulnerabilities cancel each other out
+fn resistance_to(ecs: &World, target: Entity, damage_type: DamageType) -> Option<Resistance> {
+    let resistances = ecs.read_storage::<Resistances>();
+    let equipped = ecs.read_storage::<Equipped>();
+    let entries : Vec<Resistance> = resistances.get(target).into_iter()
+        .chain((&equipped, &resistances).join().filter(|(worn, _)| worn.owner == target).map(|(_, r)| r))
+        .filter_map(|r| r.damage.get(&damage_type).copied())
+        .collect();
+    if entries.contains(&Resistance::Immune) {
+        return Some(Resistance::Immune);
+    }
+    let net = entries.iter().fold(0, |net, r| if *r == Resistance::Resistant { net + 1 } else { net - 1 });
+    match net {
+        n if n > 0 => Some(Resistance::Resistant),
+        n if n < 0 => Some(Resistance::Vulnerable),
+        _ => None
+    }
+}
+
+fn adjust_damage(ecs: &World, target: Entity, amount: i32, damage_type: DamageType) -> i32 {
+    let resistance = resistance_to(ecs, target, damage_type);
+    let adjusted = match resistance {
+        Some(Resistance::Immune) => 0,
+        Some(Resistance::Resistant) => amount / 2,
+        Some(Resistance::Vulnerable) => amount * 2,
+        None => amount
+    };
+    if let Some(resistance) = resistance {
+        let names = ecs.read_storage::<Name>();
+        let name = names.get(target).map_or("Cos", |n| n.name.as_str());
+        let logger = crate::gamelog::Logger::new().npc_name(name);
+        match resistance {
+            Resistance::Immune => logger.append(format!("jest odporny na {}.", damage_type_name(damage_type))),
+            Resistance::Resistant => logger.append(format!("opiera sie ({}), obrazenia:", damage_type_name(damage_type))).damage(adjusted).append("HP."),
+            Resistance::Vulnerable => logger.append(format!("jest podatny na {}, obrazenia:", damage_type_name(damage_type))).damage(adjusted).append("HP!")
+        }.log();
+    }
+    adjusted
+}
 
 pub fn inflict_damage(ecs: &mut World, damage: &EffectSpawner, target: Entity) {
     let mut pools = ecs.write_storage::<Pools>();
@@ -13,8 +66,13 @@ pub fn inflict_damage(ecs: &mut World, damage: &EffectSpawner, target: Entity) {
                     return;
                 }
             }
-            if let EffectType::Damage{amount} = damage.effect_type {
+            if let EffectType::Damage{amount, damage_type} = damage.effect_type {
+                let amount = adjust_damage(ecs, target, amount, damage_type);
                 pool.hit_points.current -= amount;
+                // Nothing sleeps through getting hurt
+                if let Some(awareness) = ecs.write_storage::<Awareness>().get_mut(target) {
+                    awareness.state = AwarenessState::Alert;
+                }
                 add_effect(None, EffectType::Bloodstain, Targets::Single{target});
                 add_effect(None,
                     EffectType::Particle{
@@ -220,13 +278,65 @@ pub fn slow(ecs: &mut World, effect: &EffectSpawner, target: Entity) {
 }
 
 pub fn damage_over_time(ecs: &mut World, effect: &EffectSpawner, target: Entity) {
-    if let EffectType::DamageOverTime{damage} = &effect.effect_type {
+    if let EffectType::DamageOverTime{damage, damage_type} = &effect.effect_type {
         ecs.create_entity()
             .with(StatusEffect{ target })
-            .with(DamageOverTime{ damage: *damage })
+            .with(DamageOverTime{ damage: *damage, damage_type: *damage_type })
             .with(Duration{ turns: 5 })
             .with(Name{ name: "Obrazenia".to_string() })
             .marked::<SimpleMarker<SerializeMe>>()
             .build();
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use crate::EquipmentSlot;
+
+    fn resistances(entries : &[(DamageType, Resistance)]) -> Resistances {
+        Resistances{ damage: entries.iter().copied().collect::<HashMap<DamageType, Resistance>>() }
+    }
+
+    // A target with its own resistances, wearing an item with some more
+    fn world_with(own : &[(DamageType, Resistance)], worn : &[(DamageType, Resistance)]) -> (World, Entity) {
+        let mut ecs = World::new();
+        ecs.register::<Resistances>();
+        ecs.register::<Equipped>();
+        ecs.register::<Name>();
+        let target = ecs.create_entity().with(resistances(own)).with(Name{ name: "Gnomon".to_string() }).build();
+        ecs.create_entity().with(resistances(worn)).with(Equipped{ owner: target, slot: EquipmentSlot::Torso }).build();
+        (ecs, target)
+    }
+
+    #[test]
+    fn immunity_beats_vulnerability() {
+        let (ecs, target) = world_with(&[(DamageType::Fire, Resistance::Vulnerable)], &[(DamageType::Fire, Resistance::Immune)]);
+        assert_eq!(resistance_to(&ecs, target, DamageType::Fire), Some(Resistance::Immune));
+        assert_eq!(adjust_damage(&ecs, target, 10, DamageType::Fire), 0);
+    }
+
+    #[test]
+    fn resistance_and_vulnerability_cancel_out() {
+        let (ecs, target) = world_with(&[(DamageType::Cold, Resistance::Resistant)], &[(DamageType::Cold, Resistance::Vulnerable)]);
+        assert_eq!(resistance_to(&ecs, target, DamageType::Cold), None);
+        assert_eq!(adjust_damage(&ecs, target, 10, DamageType::Cold), 10);
+    }
+
+    #[test]
+    fn resistance_halves_and_vulnerability_doubles() {
+        let (ecs, target) = world_with(&[(DamageType::Poison, Resistance::Resistant)], &[(DamageType::Magic, Resistance::Vulnerable)]);
+        assert_eq!(adjust_damage(&ecs, target, 9, DamageType::Poison), 4);
+        assert_eq!(adjust_damage(&ecs, target, 7, DamageType::Magic), 14);
+        assert_eq!(adjust_damage(&ecs, target, 7, DamageType::Physical), 7);
+    }
+
+    #[test]
+    fn only_the_targets_own_gear_counts() {
+        let (mut ecs, target) = world_with(&[], &[]);
+        let other = ecs.create_entity().build();
+        ecs.create_entity().with(resistances(&[(DamageType::Fire, Resistance::Immune)]))
+            .with(Equipped{ owner: other, slot: EquipmentSlot::Torso }).build();
+        assert_eq!(adjust_damage(&ecs, target, 6, DamageType::Fire), 6);
+    }
+}
@@ -0,0 +1,81 @@
+use specs::prelude::*;
+use super::*;
+use crate::{map::{Map, TileType, tile_walkable}, Position, Viewshed, EntityMoved, Name, Equipped, InBackpack, RunState};
+
+pub fn poison_gas(ecs: &mut World, effect: &EffectSpawner, tile_idx : i32) {
+    if let EffectType::PoisonGas{ strength } = effect.effect_type {
+        let mut map = ecs.fetch_mut::<Map>();
+        if !tile_walkable(map.tiles[tile_idx as usize]) { return; }
+        let gas = map.gas.entry(tile_idx as usize).or_insert(0);
+        *gas = i32::max(*gas, strength);
+    }
+}
+
+pub fn fall(ecs: &mut World, target: Entity) {
+    if target == *ecs.fetch::<Entity>() {
+        crate::gamelog::Logger::new()
+            .color(rltk::RED)
+            .append("Wieclaw spada w przepasc!")
+            .log();
+        *ecs.write_resource::<RunState>() = RunState::NextLevel;
+        return;
+    }
+
+    // Whatever falls in is never seen again, along with all it carries
+    if let Some(name) = ecs.read_storage::<Name>().get(target) {
+        crate::gamelog::Logger::new()
+            .npc_name(&name.name)
+            .append("spada w przepasc.")
+            .log();
+    }
+    if let Some(pos) = entity_position(ecs, target) {
+        crate::spatial::remove_entity(target, pos as usize);
+    }
+    let mut lost = vec![target];
+    {
+        let entities = ecs.entities();
+        let equipped = ecs.read_storage::<Equipped>();
+        let carried = ecs.read_storage::<InBackpack>();
+        lost.extend((&entities, &equipped).join().filter(|(_, e)| e.owner == target).map(|(entity, _)| entity));
+        lost.extend((&entities, &carried).join().filter(|(_, b)| b.owner == target).map(|(entity, _)| entity));
+    }
+    ecs.delete_entities(&lost).expect("Unable to delete");
+}
+
+// Keeps sliding the same way until something stops it or the ice runs out
+pub fn slide(ecs: &mut World, effect: &EffectSpawner, target: Entity) {
+    if let EffectType::Slide{ dx, dy } = effect.effect_type {
+        let map = ecs.fetch::<Map>();
+        let mut positions = ecs.write_storage::<Position>();
+        let pos = match positions.get_mut(target) {
+            None => return,
+            Some(pos) => pos
+        };
+        let from = map.xy_idx(pos.x, pos.y);
+        let mut dest = from;
+        loop {
+            let (x, y) = (dest as i32 % map.width + dx, dest as i32 / map.width + dy);
+            if x < 1 || x > map.width-2 || y < 1 || y > map.height-2 { break; }
+            let next = map.xy_idx(x, y);
+            if !tile_walkable(map.tiles[next]) || crate::spatial::is_blocked(next) { break; }
+            dest = next;
+            if map.tiles[dest] != TileType::Ice { break; }
+        }
+        if dest == from { return; }
+
+        crate::spatial::move_entity(target, from, dest);
+        pos.x = dest as i32 % map.width;
+        pos.y = dest as i32 / map.width;
+        if let Some(viewshed) = ecs.write_storage::<Viewshed>().get_mut(target) {
+            viewshed.dirty = true;
+        }
+        if target == *ecs.fetch::<Entity>() {
+            let mut ppos = ecs.write_resource::<rltk::Point>();
+            ppos.x = pos.x;
+            ppos.y = pos.y;
+        }
+        // Moved from the last tile crossed, so a slide stopped on the ice is not logged again
+        let before = map.xy_idx(pos.x - dx, pos.y - dy);
+        ecs.write_storage::<EntityMoved>().insert(target, EntityMoved{ from: before }).expect("Unable to insert");
+    }
+}
@@ -0,0 +1,72 @@
+use specs::{prelude::*, saveload::{SimpleMarker, MarkedBuilder}};
+use super::*;
+use crate::{map::Map, Position, Equipped, EquipmentChanged, Weapon, Name, Prone, StatusEffect, Duration, SerializeMe, CursedItem};
+
+// The weapon lands at its owner's feet, whether fumbled or knocked away.
+// A cursed one won't leave the hand, so it takes the knock instead.
+pub fn drop_weapon(ecs: &mut World, effect: &EffectSpawner, target: Entity) {
+    if let EffectType::DropWeapon{ item } = effect.effect_type {
+        if ecs.read_storage::<Equipped>().get(item).is_none_or(|e| e.owner != target) { return; }
+        if ecs.read_storage::<CursedItem>().get(item).is_some() {
+            crate::gamelog::Logger::new()
+                .item_name(crate::gui::get_item_display_name(ecs, item))
+                .append("przylega do reki jak przyklejony.")
+                .log();
+            chip_weapon(ecs, item);
+            return;
+        }
+        let pos = match entity_position(ecs, target) {
+            None => return,
+            Some(pos) => pos
+        };
+        let width = ecs.fetch::<Map>().width;
+        ecs.write_storage::<Equipped>().remove(item);
+        ecs.write_storage::<Position>().insert(item, Position{ x: pos % width, y: pos / width }).expect("Unable to insert position");
+        ecs.write_storage::<EquipmentChanged>().insert(target, EquipmentChanged{}).expect("Unable to insert");
+
+        let item_name = crate::gui::get_item_display_name(ecs, item);
+        if let Some(name) = ecs.read_storage::<Name>().get(target) {
+            crate::gamelog::Logger::new()
+                .npc_name(&name.name)
+                .append("upuszcza:")
+                .item_name(format!("{}.", item_name))
+                .log();
+        }
+    }
+}
+
+// Each mishap takes a point off the weapon's damage, down to a floor of -3
+pub fn damage_weapon(ecs: &mut World, effect: &EffectSpawner) {
+    if let EffectType::DamageWeapon{ item } = effect.effect_type {
+        chip_weapon(ecs, item);
+    }
+}
+
+fn chip_weapon(ecs: &mut World, item: Entity) {
+    let item_name = crate::gui::get_item_display_name(ecs, item);
+    if let Some(weapon) = ecs.write_storage::<Weapon>().get_mut(item) {
+        if weapon.damage_bonus <= -3 { return; }
+        weapon.damage_bonus -= 1;
+        crate::gamelog::Logger::new()
+            .item_name(item_name)
+            .append("wyszczerbia sie.")
+            .log();
+    }
+}
+
+// The target spends its next turn getting up, the duration only keeps it from lying there forever
+pub fn knock_down(ecs: &mut World, target: Entity) {
+    let already_down = {
+        let prone = ecs.read_storage::<Prone>();
+        let statuses = ecs.read_storage::<StatusEffect>();
+        (&prone, &statuses).join().any(|(_, status)| status.target == target)
+    };
+    if already_down { return; }
+    ecs.create_entity()
+        .with(StatusEffect{ target })
+        .with(Prone{})
+        .with(Duration{ turns : 3 })
+        .with(Name{ name : "Powalenie".to_string() })
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build();
+}
@@ -2,7 +2,7 @@ use std::sync::Mutex;
 use specs::prelude::*;
 use std::collections::{HashSet, VecDeque};
 use rltk::Point;
-use crate::AttributeBonus;
+use crate::{AttributeBonus, MapId, DamageType};
 mod damage;
 mod targeting;
 pub use targeting::*;
@@ -10,6 +10,13 @@ mod particles;
 mod triggers;
 mod hunger;
 mod movement;
+mod secrets;
+mod terrain;
+mod hazards;
+mod ammo;
+mod maneuvers;
+mod noise;
+pub use noise::SUSPICION_TURNS;
 
 lazy_static! {
     pub static ref EFFECT_QUEUE : Mutex<VecDeque<EffectSpawner>> = Mutex::new(VecDeque::new());
@@ -17,7 +24,7 @@ lazy_static! {
 
 #[derive(Debug)]
 pub enum EffectType {
-    Damage { amount: i32 },
+    Damage { amount: i32, damage_type: DamageType },
     Bloodstain,
     Particle { glyph: rltk::FontCharType, fg: rltk::RGB, bg: rltk::RGB, lifespan: f32 },
     ParticleProjectile { glyph: rltk::FontCharType, fg: rltk::RGB, bg: rltk::RGB, lifespan: f32, speed: f32, path: Vec<Point> },
@@ -29,10 +36,20 @@ pub enum EffectType {
     Mana { amount: i32 },
     Confusion { turns : i32 },
     TriggerFire { trigger: Entity },
-    TeleportTo { x:i32, y:i32, depth: i32, player_only: bool },
+    TeleportTo { x:i32, y:i32, map: MapId, player_only: bool },
     AttributeEffect { bonus : AttributeBonus, name : String, duration : i32 },
     Slow { initiative_penalty : f32 },
-    DamageOverTime { damage : i32 }
+    DamageOverTime { damage : i32, damage_type : DamageType },
+    RevealSecretDoor,
+    Dig { power : i32 },
+    PoisonGas { strength : i32 },
+    Fall,
+    Slide { dx : i32, dy : i32 },
+    SpentAmmo { kind : String },
+    DropWeapon { item : Entity },
+    DamageWeapon { item : Entity },
+    KnockDown,
+    Noise { volume : i32 }
 }
 
 #[derive(Clone, Debug)]
@@ -116,6 +133,11 @@ fn affect_tile(ecs: &mut World, effect: &mut EffectSpawner, tile_idx: i32) {
         EffectType::Bloodstain => damage::bloodstain(ecs, tile_idx),
         EffectType::Particle{..} => particles::particle_to_tile(ecs, tile_idx, &effect),
         EffectType::ParticleProjectile{..} => particles::projectile(ecs, tile_idx, &effect),
+        EffectType::RevealSecretDoor => secrets::reveal_secret_door(ecs, tile_idx),
+        EffectType::Dig{..} => terrain::dig(ecs, effect, tile_idx),
+        EffectType::PoisonGas{..} => hazards::poison_gas(ecs, effect, tile_idx),
+        EffectType::SpentAmmo{..} => ammo::spent_ammo(ecs, effect, tile_idx),
+        EffectType::Noise{..} => noise::noise(ecs, effect, tile_idx),
         _ => {}
     }
 }
@@ -138,6 +160,11 @@ fn affect_entity(ecs: &mut World, effect: &mut EffectSpawner, target: Entity) {
         EffectType::AttributeEffect{..} => damage::attribute_effect(ecs, effect, target),
         EffectType::Slow{..} => damage::slow(ecs, effect, target),
         EffectType::DamageOverTime{..} => damage::damage_over_time(ecs, effect, target),
+        EffectType::Fall => hazards::fall(ecs, target),
+        EffectType::Slide{..} => hazards::slide(ecs, effect, target),
+        EffectType::DropWeapon{..} => maneuvers::drop_weapon(ecs, effect, target),
+        EffectType::DamageWeapon{..} => maneuvers::damage_weapon(ecs, effect),
+        EffectType::KnockDown => maneuvers::knock_down(ecs, target),
         _ => {}
     }
 }
\ No newline at end of file
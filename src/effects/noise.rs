@@ -0,0 +1,138 @@
+use specs::prelude::*;
+use super::*;
+use crate::{map::Map, Position, Name, Skills, Skill, skill_bonus, Awareness, AwarenessState};
+
+// How many of its own turns a suspicious mob keeps looking around before it lets it go
+pub const SUSPICION_TURNS : i32 = 10;
+
+// Everything within earshot grows warier and comes to have a look, whatever is close enough is alert at once.
+// A stealthy creator makes less of a racket.
+pub fn noise(ecs: &mut World, effect: &EffectSpawner, tile_idx : i32) {
+    if let EffectType::Noise{ volume } = effect.effect_type {
+        let stealth = effect.creator
+            .and_then(|creator| ecs.read_storage::<Skills>().get(creator).map(|skills| skill_bonus(Skill::Stealth, skills)))
+            .unwrap_or(0);
+        let volume = (volume - i32::max(0, stealth)) as f32;
+        if volume < 1.0 { return; }
+
+        let map = ecs.fetch::<Map>();
+        let source = Point::new(tile_idx % map.width, tile_idx / map.width);
+        let entities = ecs.entities();
+        let positions = ecs.read_storage::<Position>();
+        let names = ecs.read_storage::<Name>();
+        let mut awareness = ecs.write_storage::<Awareness>();
+        for (entity, pos, awareness) in (&entities, &positions, &mut awareness).join() {
+            if Some(entity) == effect.creator { continue; }
+            let distance = rltk::DistanceAlg::Pythagoras.distance2d(source, Point::new(pos.x, pos.y));
+            if distance > volume { continue; }
+
+            let woken = awareness.state == AwarenessState::Asleep;
+            if distance <= volume / 2.0 {
+                awareness.state = AwarenessState::Alert;
+            } else if awareness.state != AwarenessState::Alert {
+                awareness.state = AwarenessState::Suspicious;
+            }
+            awareness.investigate = Some(tile_idx as usize);
+            awareness.suspicion = SUSPICION_TURNS;
+
+            if woken && map.visible_tiles[map.xy_idx(pos.x, pos.y)] {
+                if let Some(name) = names.get(entity) {
+                    crate::gamelog::Logger::new()
+                        .npc_name(&name.name)
+                        .append("budzi sie.")
+                        .log();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::{HashMap, HashSet};
+
+    fn world() -> World {
+        let mut ecs = World::new();
+        ecs.register::<Position>();
+        ecs.register::<Name>();
+        ecs.register::<Skills>();
+        ecs.register::<Awareness>();
+        ecs.insert(Map::new(1, 40, 20, "Test"));
+        ecs
+    }
+
+    fn listener(ecs : &mut World, x : i32) -> Entity {
+        ecs.create_entity()
+            .with(Position{ x, y: 10 })
+            .with(Awareness{ state: AwarenessState::Unaware, investigate: None, suspicion: 0 })
+            .build()
+    }
+
+    fn state(ecs : &World, entity : Entity) -> AwarenessState {
+        ecs.read_storage::<Awareness>().get(entity).unwrap().state
+    }
+
+    // A noise of the given volume from (10, 10), made by someone with the given Stealth
+    fn make_noise(ecs : &mut World, volume : i32, stealth : i32) {
+        let skills = Skills{ skills: [(Skill::Stealth, stealth)].into_iter().collect::<HashMap<Skill, i32>>() };
+        let creator = ecs.create_entity().with(Position{ x: 10, y: 10 }).with(skills).build();
+        let tile_idx = ecs.fetch::<Map>().xy_idx(10, 10) as i32;
+        let effect = EffectSpawner{ creator: Some(creator), effect_type: EffectType::Noise{ volume }, targets: Targets::Tile{ tile_idx },
+            dedupe: HashSet::new() };
+        noise(ecs, &effect, tile_idx);
+    }
+
+    #[test]
+    fn close_listeners_are_alert_and_farther_ones_suspicious() {
+        let _lock = crate::headless::TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut ecs = world();
+        let near = listener(&mut ecs, 14);
+        let edge = listener(&mut ecs, 15);
+        let far = listener(&mut ecs, 18);
+        let out_of_earshot = listener(&mut ecs, 21);
+
+        make_noise(&mut ecs, 10, 0);
+
+        assert_eq!(state(&ecs, near), AwarenessState::Alert);
+        assert_eq!(state(&ecs, edge), AwarenessState::Alert);
+        assert_eq!(state(&ecs, far), AwarenessState::Suspicious);
+        assert_eq!(state(&ecs, out_of_earshot), AwarenessState::Unaware);
+        let awareness = ecs.read_storage::<Awareness>();
+        let far = awareness.get(far).unwrap();
+        assert_eq!(far.investigate, Some(ecs.fetch::<Map>().xy_idx(10, 10)));
+        assert_eq!(far.suspicion, SUSPICION_TURNS);
+        assert_eq!(awareness.get(out_of_earshot).unwrap().investigate, None);
+    }
+
+    #[test]
+    fn stealth_muffles_the_noise() {
+        let _lock = crate::headless::TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut ecs = world();
+        let near = listener(&mut ecs, 13);
+        let far = listener(&mut ecs, 17);
+
+        // Stealth 4 takes a volume of 10 down to 6
+        make_noise(&mut ecs, 10, 4);
+
+        assert_eq!(state(&ecs, near), AwarenessState::Alert);
+        assert_eq!(state(&ecs, far), AwarenessState::Unaware);
+
+        // Enough Stealth makes no sound at all
+        let next = listener(&mut ecs, 11);
+        make_noise(&mut ecs, 3, 3);
+        assert_eq!(state(&ecs, next), AwarenessState::Unaware);
+    }
+
+    #[test]
+    fn an_alert_listener_does_not_calm_down() {
+        let _lock = crate::headless::TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut ecs = world();
+        let far = listener(&mut ecs, 18);
+        ecs.write_storage::<Awareness>().get_mut(far).unwrap().state = AwarenessState::Alert;
+
+        make_noise(&mut ecs, 10, 0);
+
+        assert_eq!(state(&ecs, far), AwarenessState::Alert);
+    }
+}
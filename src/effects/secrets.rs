@@ -0,0 +1,30 @@
+use specs::prelude::*;
+use super::terrain::set_tile;
+use crate::{map::Map, TileType, BlocksTile, raws::{RAWS, SpawnType, spawn_named_entity}};
+
+pub fn reveal_secret_door(ecs: &mut World, tile_idx : i32) {
+    let (x, y, occupied) = {
+        let mut map = ecs.fetch_mut::<Map>();
+        if map.tiles[tile_idx as usize] != TileType::SecretDoor { return; }
+        map.revealed_tiles[tile_idx as usize] = true;
+
+        let blockers = ecs.read_storage::<BlocksTile>();
+        let mut occupied = false;
+        crate::spatial::for_each_tile_content(tile_idx as usize, |entity| {
+            if blockers.get(entity).is_some() { occupied = true; }
+        });
+        (tile_idx % map.width, tile_idx / map.width, occupied)
+    };
+
+    set_tile(ecs, tile_idx as usize, TileType::Floor);
+    // Whoever stands in the doorway keeps it open
+    if !occupied {
+        spawn_named_entity(&RAWS.lock().unwrap(), ecs, "Drzwi", SpawnType::AtPosition{ x, y });
+    }
+
+    crate::gamelog::Logger::new()
+        .append("Wieclaw odkryl")
+        .color(rltk::CYAN)
+        .append("ukryte przejscie!")
+        .log();
+}
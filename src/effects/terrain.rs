@@ -0,0 +1,47 @@
+use specs::prelude::*;
+use super::*;
+use crate::{map::{Map, tile_hardness}, TileType, Viewshed};
+
+// Everything cached from the old tile follows it: movement blocking now, sight and light with the next field of view
+pub fn set_tile(ecs: &mut World, tile_idx : usize, tile : TileType) {
+    ecs.fetch_mut::<Map>().tiles[tile_idx] = tile;
+    crate::spatial::set_tile_blocked(tile_idx, tile);
+    for viewshed in (&mut ecs.write_storage::<Viewshed>()).join() {
+        viewshed.dirty = true;
+    }
+}
+
+pub fn dig(ecs: &mut World, effect: &EffectSpawner, tile_idx : i32) {
+    if let EffectType::Dig{ power } = effect.effect_type {
+        let idx = tile_idx as usize;
+        let collapsed = {
+            let mut map = ecs.fetch_mut::<Map>();
+            let x = tile_idx % map.width;
+            let y = tile_idx / map.width;
+            if x < 1 || x > map.width-2 || y < 1 || y > map.height-2 { return; }
+            let hardness = match tile_hardness(map.tiles[idx]) {
+                None => return,
+                Some(hardness) => hardness
+            };
+            let damage = map.tile_damage.entry(idx).or_insert(0);
+            *damage += power;
+            if *damage >= hardness {
+                map.tile_damage.remove(&idx);
+                true
+            } else {
+                false
+            }
+        };
+
+        // Only digging by hand is worth a log line, a blast speaks for itself
+        let by_hand = effect.creator == Some(*ecs.fetch::<Entity>()) && matches!(effect.targets, Targets::Tile{..});
+        if collapsed {
+            set_tile(ecs, idx, TileType::Rubble);
+            if by_hand {
+                crate::gamelog::Logger::new().append("Skala kruszy sie w gruz.").log();
+            }
+        } else if by_hand {
+            crate::gamelog::Logger::new().append("Wieclaw kuje w skale.").log();
+        }
+    }
+}
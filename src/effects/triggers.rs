@@ -1,9 +1,9 @@
 use specs::prelude::*;
 use super::{Targets, add_effect, EffectType, entity_position, targeting};
-use crate::{Consumable, ProvidesFood, Name, RunState, MagicMapper, Map, TownPortal, ProvidesHealing, ProvidesIdentification,
+use crate::{Consumable, ProvidesFood, Name, RunState, MagicMapper, Map, MapId, TownPortal, ProvidesHealing, ProvidesIdentification,
             InflictsDamage, Confusion, Hidden, SingleActivation, TeleportTo, SpawnParticleLine, SpawnParticleBurst, ProvidesRemoveCurse, Duration,
             AttributeBonus, SpellTemplate, Pools, ProvidesMana, TeachesSpell, KnownSpells, KnownSpell, Slow, DamageOverTime, AreaOfEffect,
-            AlwaysTargetsSelf, Position, effects::aoe_tiles};
+            AlwaysTargetsSelf, Position, Digs, PoisonGas, effects::aoe_tiles};
 
 pub fn item_trigger(creator: Option<Entity>, item: Entity, targets: &Targets, ecs: &mut World) {
     if let Some(c) = ecs.write_storage::<Consumable>().get_mut(item) {
@@ -130,7 +130,7 @@ fn event_trigger(creator: Option<Entity>, entity: Entity, targets: &Targets, ecs
 
     if ecs.read_storage::<TownPortal>().get(entity).is_some() {
         let map = ecs.fetch::<Map>();
-        if map.depth == 1 {
+        if map.id() == MapId::TOWN {
             crate::gamelog::Logger::new().append("Wieclaw juz jest w miescie, wiec zwój nie dziala.").log();
         } else {
             crate::gamelog::Logger::new().append("Wieclaw teleportuje sie do miasta!").log();
@@ -161,7 +161,7 @@ fn event_trigger(creator: Option<Entity>, entity: Entity, targets: &Targets, ecs
     }
 
     if let Some(damage) = ecs.read_storage::<InflictsDamage>().get(entity) {
-        add_effect(creator, EffectType::Damage{amount: damage.damage}, targets.clone());
+        add_effect(creator, EffectType::Damage{amount: damage.damage, damage_type: damage.damage_type}, targets.clone());
         did_something = true;
     }
 
@@ -179,7 +179,7 @@ fn event_trigger(creator: Option<Entity>, entity: Entity, targets: &Targets, ecs
             EffectType::TeleportTo{
                 x: teleport.x,
                 y: teleport.y,
-                depth: teleport.depth,
+                map: teleport.map,
                 player_only: teleport.player_only
             },
             targets.clone()
@@ -222,7 +222,17 @@ fn event_trigger(creator: Option<Entity>, entity: Entity, targets: &Targets, ecs
     }
 
     if let Some(damage) = ecs.read_storage::<DamageOverTime>().get(entity) {
-        add_effect(creator, EffectType::DamageOverTime{ damage: damage.damage }, targets.clone());
+        add_effect(creator, EffectType::DamageOverTime{ damage: damage.damage, damage_type: damage.damage_type }, targets.clone());
+        did_something = true;
+    }
+
+    if let Some(digs) = ecs.read_storage::<Digs>().get(entity) {
+        add_effect(creator, EffectType::Dig{ power: digs.power }, targets.clone());
+        did_something = true;
+    }
+
+    if let Some(gas) = ecs.read_storage::<PoisonGas>().get(entity) {
+        add_effect(creator, EffectType::PoisonGas{ strength: gas.strength }, targets.clone());
         did_something = true;
     }
 
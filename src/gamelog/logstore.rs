@@ -33,6 +33,12 @@ pub fn log_display() -> TextBuilder {
     buf
 }
 
+pub fn log_lines() -> Vec<String> {
+    LOG.lock().unwrap().iter().map(|log| {
+        log.iter().map(|frag| frag.text.clone()).collect::<Vec<String>>().join(" ")
+    }).collect()
+}
+
 pub fn clone_log() -> Vec<Vec<crate::gamelog::LogFragment>> {
     LOG.lock().unwrap().clone()
 }
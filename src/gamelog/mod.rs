@@ -1,7 +1,7 @@
 use rltk::RGB;
 mod logstore;
 use logstore::*;
-pub use logstore::{clear_log, log_display, clone_log, restore_log};
+pub use logstore::{clear_log, log_display, clone_log, restore_log, log_lines};
 mod builder;
 pub use builder::*;
 mod events;
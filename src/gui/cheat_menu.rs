@@ -3,11 +3,11 @@ use crate::State;
 use super::{menu_box, menu_option};
 
 #[derive(PartialEq, Copy, Clone)]
-pub enum CheatMenuResult { NoResponse, Cancel, TeleportToExit, Heal, Revive, GodMode }
+pub enum CheatMenuResult { NoResponse, Cancel, TeleportToExit, Heal, Revive, GodMode, ReloadRaws }
 
 pub fn show_cheat_mode(_gs : &mut State, ctx : &mut Rltk) -> CheatMenuResult {
     let mut draw_batch = DrawBatch::new();
-    let count = 4;
+    let count = 5;
     let mut y = (25 - (count / 2)) as i32;
     menu_box(&mut draw_batch, 15, y, (count+3) as i32, "Nieladnie");
     draw_batch.print_color(
@@ -23,6 +23,8 @@ pub fn show_cheat_mode(_gs : &mut State, ctx : &mut Rltk) -> CheatMenuResult {
     menu_option(&mut draw_batch, 17, y, rltk::to_cp437('R'), "Odsloniecie mapy");
     y += 1;
     menu_option(&mut draw_batch, 17, y, rltk::to_cp437('G'), "Niesmiertelnosc");
+    y += 1;
+    menu_option(&mut draw_batch, 17, y, rltk::to_cp437('D'), "Przeladowanie danych gry");
 
     draw_batch.submit(6000).expect("Unable to submit");
 
@@ -34,6 +36,7 @@ pub fn show_cheat_mode(_gs : &mut State, ctx : &mut Rltk) -> CheatMenuResult {
                 VirtualKeyCode::H => CheatMenuResult::Heal,
                 VirtualKeyCode::R => CheatMenuResult::Revive,
                 VirtualKeyCode::G => CheatMenuResult::GodMode,
+                VirtualKeyCode::D => CheatMenuResult::ReloadRaws,
                 VirtualKeyCode::Escape => CheatMenuResult::Cancel,
                 _ => CheatMenuResult::NoResponse
             }
@@ -0,0 +1,39 @@
+use rltk::prelude::*;
+use crate::{State, Maneuver};
+use super::{menu_box, menu_option};
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum CombatMenuResult { NoResponse, Cancel, Selected { maneuver : Maneuver } }
+
+pub fn show_combat_menu(_gs : &mut State, ctx : &mut Rltk) -> CombatMenuResult {
+    let mut draw_batch = DrawBatch::new();
+    let count = 3;
+    let mut y = 25 - (count / 2);
+    menu_box(&mut draw_batch, 15, y, count+3, "Manewry");
+    draw_batch.print_color(
+        Point::new(18, y+count+1),
+        "ESCAPE - wyjscie",
+        ColorPair::new(RGB::named(rltk::YELLOW), RGB::named(rltk::BLACK))
+    );
+
+    menu_option(&mut draw_batch, 17, y, rltk::to_cp437('P'), "Pchniecie");
+    y += 1;
+    menu_option(&mut draw_batch, 17, y, rltk::to_cp437('R'), "Rozbrojenie");
+    y += 1;
+    menu_option(&mut draw_batch, 17, y, rltk::to_cp437('W'), "Wywrocenie");
+
+    draw_batch.submit(6000).expect("Unable to submit");
+
+    match ctx.key {
+        None => CombatMenuResult::NoResponse,
+        Some(key) => {
+            match key {
+                VirtualKeyCode::P => CombatMenuResult::Selected{ maneuver: Maneuver::Shove },
+                VirtualKeyCode::R => CombatMenuResult::Selected{ maneuver: Maneuver::Disarm },
+                VirtualKeyCode::W => CombatMenuResult::Selected{ maneuver: Maneuver::Trip },
+                VirtualKeyCode::Escape => CombatMenuResult::Cancel,
+                _ => CombatMenuResult::NoResponse
+            }
+        }
+    }
+}
@@ -0,0 +1,24 @@
+use rltk::prelude::*;
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum ConfirmResult { NoResponse, Yes, No }
+
+pub fn confirm_menu(ctx : &mut Rltk, question : &str) -> ConfirmResult {
+    let mut draw_batch = DrawBatch::new();
+
+    let width = question.len() as i32 + 4;
+    draw_batch.draw_box(
+        Rect::with_size(40 - width / 2, 22, width, 4),
+        ColorPair::new(RGB::named(rltk::WHITE), RGB::named(rltk::BLACK))
+    );
+    draw_batch.print_color_centered(23, question, ColorPair::new(RGB::named(rltk::YELLOW), RGB::named(rltk::BLACK)));
+    draw_batch.print_color_centered(25, "(T)ak / (N)ie", ColorPair::new(RGB::named(rltk::WHITE), RGB::named(rltk::BLACK)));
+
+    draw_batch.submit(6000).expect("Unable to submit");
+
+    match ctx.key {
+        Some(VirtualKeyCode::T) | Some(VirtualKeyCode::Y) => ConfirmResult::Yes,
+        Some(VirtualKeyCode::N) | Some(VirtualKeyCode::Escape) => ConfirmResult::No,
+        _ => ConfirmResult::NoResponse
+    }
+}
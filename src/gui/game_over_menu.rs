@@ -1,9 +1,10 @@
 use rltk::prelude::*;
+use crate::{State, MasterDungeonMap};
 
 #[derive(PartialEq, Copy, Clone)]
 pub enum GameOverResult { NoSelection, QuitToMenu }
 
-pub fn game_over(ctx : &mut Rltk) -> GameOverResult {
+pub fn game_over(gs : &mut State, ctx : &mut Rltk) -> GameOverResult {
     let mut draw_batch = DrawBatch::new();
     draw_batch.print_color_centered(
         15,
@@ -36,6 +37,11 @@ pub fn game_over(ctx : &mut Rltk) -> GameOverResult {
         &format!("Zadane obrazenia: {}.", crate::gamelog::get_event_count("Damage Inflicted")),
         ColorPair::new(RGB::named(rltk::RED), RGB::named(rltk::BLACK))
     );
+    draw_batch.print_color_centered(
+        22,
+        format!("Ziarno swiata: {}", gs.ecs.fetch::<MasterDungeonMap>().run_seed),
+        ColorPair::new(RGB::named(rltk::GRAY), RGB::named(rltk::BLACK))
+    );
 
     draw_batch.print_color_centered(
         23,
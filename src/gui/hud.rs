@@ -1,7 +1,7 @@
 use specs::prelude::*;
 use rltk::prelude::*;
-use crate::{Map, Pools, Attributes, Equipped, Weapon, Consumable, InBackpack, HungerClock, HungerState, Duration, KnownSpells, Name, StatusEffect,
-    Attribute, gamelog};
+use crate::{Map, MasterDungeonMap, Pools, Attributes, Equipped, Weapon, Consumable, InBackpack, HungerClock, HungerState, Duration, KnownSpells, Name, StatusEffect,
+    Attribute, Magazine, Ammunition, gamelog};
 use super::{draw_tooltips, get_item_display_name, get_item_color};
 
 fn draw_attribute(name : &str, attribute : &Attribute, y : i32, draw_batch: &mut DrawBatch) {
@@ -119,6 +119,11 @@ fn initiative_weight(ecs: &World, draw_batch: &mut DrawBatch, player_entity: &En
         &format!("Zwiedrki: {:.1}", player_pools.gold),
         ColorPair::new(rltk::RGB::named(rltk::GOLD), black)
     );
+    draw_batch.print_color(
+        Point::new(50, 12),
+        format!("Ziarno: {}", ecs.fetch::<MasterDungeonMap>().run_seed),
+        ColorPair::new(rltk::RGB::named(rltk::GRAY), black)
+    );
 }
 
 fn equipped(ecs: &World, draw_batch: &mut DrawBatch, player_entity: &Entity) -> i32 {
@@ -128,6 +133,9 @@ fn equipped(ecs: &World, draw_batch: &mut DrawBatch, player_entity: &Entity) ->
     let entities = ecs.entities();
     let equipped = ecs.read_storage::<Equipped>();
     let weapon = ecs.read_storage::<Weapon>();
+    let magazines = ecs.read_storage::<Magazine>();
+    let backpack = ecs.read_storage::<InBackpack>();
+    let ammunition = ecs.read_storage::<Ammunition>();
     for (entity, equipped_by) in (&entities, &equipped).join() {
         if equipped_by.owner == *player_entity {
             let name = get_item_display_name(ecs, entity);
@@ -137,6 +145,19 @@ fn equipped(ecs: &World, draw_batch: &mut DrawBatch, player_entity: &Entity) ->
                 ColorPair::new(get_item_color(ecs, entity), black));
             y += 1;
 
+            if let Some(magazine) = magazines.get(entity) {
+                let spare : i32 = (&backpack, &ammunition).join()
+                    .filter(|(carried, stack)| carried.owner == *player_entity && stack.kind == magazine.ammo)
+                    .map(|(_, stack)| stack.count)
+                    .sum();
+                let color = if magazine.loaded > 0 { RGB::named(rltk::WHITE) } else { RGB::named(rltk::RED) };
+                draw_batch.print_color(
+                    Point::new(51, y),
+                    &format!("Amunicja: {}/{} (zapas: {})", magazine.loaded, magazine.capacity, spare),
+                    ColorPair::new(color, black));
+                y += 1;
+            }
+
             if let Some(weapon) = weapon.get(entity) {
                 let mut weapon_info = if weapon.damage_bonus < 0 {
                     format!("┤ {} ({}d{}{})", &name, weapon.damage_n_dice, weapon.damage_die_type, weapon.damage_bonus)
@@ -146,12 +167,22 @@ fn equipped(ecs: &World, draw_batch: &mut DrawBatch, player_entity: &Entity) ->
                     format!("┤ {} ({}d{}+{}", &name, weapon.damage_n_dice, weapon.damage_die_type, weapon.damage_bonus)
                 };
 
+                if weapon.crit_range < 20 || weapon.crit_multiplier != 2 {
+                    let crit_rolls = if weapon.crit_range < 20 { format!("{}-20", weapon.crit_range) } else { "20".to_string() };
+                    weapon_info += &format!(" kryt. {} x{}", crit_rolls, weapon.crit_multiplier);
+                }
+
+                // The ranged weapon goes on the bottom edge so it does not cover the melee one
+                let mut row = 45;
                 if let Some(range) = weapon.range {
-                    weapon_info += &format!(" (zasieg: {}, F - strzal, V - zmiana celu)", range);
+                    weapon_info += &format!(" (zasieg: {}, F - strzal, V - zmiana celu, P - przeladuj)", range);
+                    row = 59;
+                } else {
+                    weapon_info += " (M - manewry)";
                 }
                 weapon_info += " ├";
                 draw_batch.print_color(
-                    Point::new(3, 45),
+                    Point::new(3, row),
                     &weapon_info,
                     ColorPair::new(yellow, black));
             }
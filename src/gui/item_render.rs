@@ -1,6 +1,6 @@
 use specs::prelude::*;
 use rltk::prelude::*;
-use crate::{Name, CursedItem, MagicItem, MagicItemClass, Consumable, ObfuscatedName};
+use crate::{Name, CursedItem, MagicItem, MagicItemClass, Consumable, ObfuscatedName, Ammunition};
 
 
 pub fn get_item_color(ecs : &World, item : Entity) -> RGB {
@@ -40,6 +40,8 @@ pub fn get_item_display_name(ecs: &World, item : Entity) -> String {
             } else {
                 "Nierozpoznany przedmiot".to_string()
             }
+        } else if let Some(stack) = ecs.read_storage::<Ammunition>().get(item) {
+            format!("{} ({})", name.name, stack.count)
         } else {
             name.name.clone()
         }
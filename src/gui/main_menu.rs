@@ -2,16 +2,42 @@ use rltk::prelude::*;
 use crate::{State, RunState, rex_assets::RexAssets };
 
 #[derive(PartialEq, Copy, Clone)]
-pub enum MainMenuSelection { NewGame, LoadGame, Credits, Quit }
+pub enum MainMenuSelection { NewGame, LoadGame, RestoreAutosave, Credits, Quit }
 
 #[derive(PartialEq, Copy, Clone)]
 pub enum MainMenuResult { NoSelection{ selected : MainMenuSelection }, Selected{ selected: MainMenuSelection } }
 
+fn next_selection(selection : MainMenuSelection) -> MainMenuSelection {
+    match selection {
+        MainMenuSelection::NewGame => MainMenuSelection::LoadGame,
+        MainMenuSelection::LoadGame => MainMenuSelection::RestoreAutosave,
+        MainMenuSelection::RestoreAutosave => MainMenuSelection::Credits,
+        MainMenuSelection::Credits => MainMenuSelection::Quit,
+        MainMenuSelection::Quit => MainMenuSelection::NewGame
+    }
+}
+
+fn previous_selection(selection : MainMenuSelection) -> MainMenuSelection {
+    match selection {
+        MainMenuSelection::NewGame => MainMenuSelection::Quit,
+        MainMenuSelection::LoadGame => MainMenuSelection::NewGame,
+        MainMenuSelection::RestoreAutosave => MainMenuSelection::LoadGame,
+        MainMenuSelection::Credits => MainMenuSelection::RestoreAutosave,
+        MainMenuSelection::Quit => MainMenuSelection::Credits
+    }
+}
+
 pub fn main_menu(gs : &mut State, ctx : &mut Rltk) -> MainMenuResult {
     let mut draw_batch = DrawBatch::new();
     let assets = gs.ecs.fetch::<RexAssets>();
     ctx.render_xp_sprite(&assets.menu, 0, 0);
     let save_exists = crate::saveload_system::does_save_exist();
+    let autosave_exists = crate::saveload_system::does_autosave_exist();
+    let is_available = |selection : MainMenuSelection| match selection {
+        MainMenuSelection::LoadGame => save_exists,
+        MainMenuSelection::RestoreAutosave => autosave_exists,
+        _ => true
+    };
     let runstate = gs.ecs.fetch::<RunState>();
 
     draw_batch.print(Point::new(1, 51), "v0.1.0");
@@ -20,6 +46,11 @@ pub fn main_menu(gs : &mut State, ctx : &mut Rltk) -> MainMenuResult {
     draw_batch.print_color_centered(21, "Zebyr Zyjgames", ColorPair::new(RGB::named(rltk::CYAN), RGB::named(rltk::BLACK)));
     draw_batch.print_color_centered(22, "(strzalki góra/dól, Enter)", ColorPair::new(RGB::named(rltk::GRAY), RGB::named(rltk::BLACK)));
 
+    if let Some(error) = &gs.load_error {
+        draw_batch.print_color_centered(31, "Nie udalo sie wczytac gry:", ColorPair::new(RGB::named(rltk::RED), RGB::named(rltk::BLACK)));
+        draw_batch.print_color_centered(32, error, ColorPair::new(RGB::named(rltk::RED), RGB::named(rltk::BLACK)));
+    }
+
     let mut y = 24;
         if let RunState::MainMenu{ menu_selection : selection } = *runstate {
             if selection == MainMenuSelection::NewGame {
@@ -38,6 +69,15 @@ pub fn main_menu(gs : &mut State, ctx : &mut Rltk) -> MainMenuResult {
                 y += 1;
             }
 
+            if autosave_exists {
+                if selection == MainMenuSelection::RestoreAutosave {
+                    draw_batch.print_color_centered(y, "Przywroc autozapis", ColorPair::new(RGB::named(rltk::MAGENTA), RGB::named(rltk::BLACK)));
+                } else {
+                    draw_batch.print_color_centered(y, "Przywroc autozapis", ColorPair::new(RGB::named(rltk::WHITE), RGB::named(rltk::BLACK)));
+                }
+                y += 1;
+            }
+
             if selection == MainMenuSelection::Credits {
                 draw_batch.print_color_centered(y, "Autorzy", ColorPair::new(RGB::named(rltk::MAGENTA), RGB::named(rltk::BLACK)));
             } else {
@@ -59,28 +99,16 @@ pub fn main_menu(gs : &mut State, ctx : &mut Rltk) -> MainMenuResult {
                     match key {
                         VirtualKeyCode::Escape => { return MainMenuResult::NoSelection { selected: MainMenuSelection::Quit }}
                         VirtualKeyCode::Up => {
-                            let mut newselection;
-                            match selection {
-                                MainMenuSelection::NewGame => newselection = MainMenuSelection::Quit,
-                                MainMenuSelection::LoadGame => newselection = MainMenuSelection::NewGame,
-                                MainMenuSelection::Credits => newselection = MainMenuSelection::LoadGame,
-                                MainMenuSelection::Quit => newselection = MainMenuSelection::Credits
-                            }
-                            if newselection == MainMenuSelection::LoadGame && !save_exists {
-                                newselection = MainMenuSelection::NewGame;
+                            let mut newselection = previous_selection(selection);
+                            while !is_available(newselection) {
+                                newselection = previous_selection(newselection);
                             }
                             return MainMenuResult::NoSelection { selected: newselection }
                         }
                         VirtualKeyCode::Down => {
-                            let mut newselection;
-                            match selection {
-                                MainMenuSelection::NewGame => newselection = MainMenuSelection::LoadGame,
-                                MainMenuSelection::LoadGame => newselection = MainMenuSelection::Credits,
-                                MainMenuSelection::Credits => newselection = MainMenuSelection::Quit,
-                                MainMenuSelection::Quit => newselection = MainMenuSelection::NewGame
-                            }
-                            if newselection == MainMenuSelection::LoadGame && !save_exists {
-                                newselection = MainMenuSelection::Credits;
+                            let mut newselection = next_selection(selection);
+                            while !is_available(newselection) {
+                                newselection = next_selection(newselection);
                             }
                             return MainMenuResult::NoSelection { selected: newselection }
                         }
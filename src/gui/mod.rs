@@ -24,7 +24,13 @@ mod credits_menu;
 pub use credits_menu::*;
 mod cheat_menu;
 pub use cheat_menu::*;
+mod combat_menu;
+pub use combat_menu::*;
 mod vendor_menu;
 pub use vendor_menu::*;
+mod save_slot_menu;
+pub use save_slot_menu::*;
+mod confirm_menu;
+pub use confirm_menu::*;
 mod menus;
 pub use menus::*;
\ No newline at end of file
@@ -4,6 +4,15 @@ use crate::{State, camera, Viewshed};
 use super::ItemMenuResult;
 
 pub fn ranged_target(gs : &mut State, ctx : &mut Rltk, range : i32) -> (ItemMenuResult, Option<Point>) {
+    pick_target(gs, ctx, range as f32)
+}
+
+// Only the tiles right next to the player, diagonals included
+pub fn melee_target(gs : &mut State, ctx : &mut Rltk) -> (ItemMenuResult, Option<Point>) {
+    pick_target(gs, ctx, 1.5)
+}
+
+fn pick_target(gs : &mut State, ctx : &mut Rltk, range : f32) -> (ItemMenuResult, Option<Point>) {
     let (min_x, max_x, min_y, max_y) = camera::get_screen_bounds(&gs.ecs, ctx);
     let player_entity = gs.ecs.fetch::<Entity>();
     let player_pos = gs.ecs.fetch::<Point>();
@@ -22,7 +31,7 @@ pub fn ranged_target(gs : &mut State, ctx : &mut Rltk, range : i32) -> (ItemMenu
     if let Some(visible) = visible {
         for idx in visible.visible_tiles.iter() {
             let distance = rltk::DistanceAlg::Pythagoras.distance2d(*player_pos, *idx);
-            if distance <= range as f32 {
+            if distance <= range {
                 let screen_x = idx.x - min_x;
                 let screen_y = idx.y - min_y;
                 if screen_x > 1 && screen_x < (max_x - min_x)-1 && screen_y > 1 && screen_y < (max_y - min_y)-1 {
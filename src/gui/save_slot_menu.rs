@@ -0,0 +1,85 @@
+use rltk::prelude::*;
+use crate::{State, rex_assets::RexAssets, saveload_system::SaveMetadata};
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum SlotMenuResult { NoResponse, Cancel, Selected, Delete }
+
+fn format_timestamp(timestamp : u64) -> String {
+    let days = (timestamp / 86400) as i64;
+    let seconds = timestamp % 86400;
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02} {:02}:{:02}", year, month, day, seconds / 3600, (seconds % 3600) / 60)
+}
+
+fn describe_slot(slot : &Option<SaveMetadata>) -> String {
+    match slot {
+        None => "(pusty)".to_string(),
+        Some(meta) if meta.timestamp == 0 => "(zapis bez opisu)".to_string(),
+        Some(meta) => format!("Poz. {}, {} ({}), {} tur, {}",
+            meta.level, meta.map_name, meta.depth, meta.turns, format_timestamp(meta.timestamp))
+    }
+}
+
+pub fn save_slot_menu(gs : &mut State, ctx : &mut Rltk, saving : bool) -> (SlotMenuResult, Option<i32>) {
+    let mut draw_batch = DrawBatch::new();
+    if !saving {
+        let assets = gs.ecs.fetch::<RexAssets>();
+        ctx.render_xp_sprite(&assets.menu, 0, 0);
+    }
+
+    let count = gs.save_slots.len() as i32;
+    let y = 25 - (count / 2);
+    draw_batch.draw_box(
+        Rect::with_size(8, y-2, 64, count+4),
+        ColorPair::new(RGB::named(rltk::WHITE), RGB::named(rltk::BLACK))
+    );
+    draw_batch.print_color(
+        Point::new(11, y-2),
+        if saving { "Gdzie zapisac gre?" } else { "Ktora gre wczytac?" },
+        ColorPair::new(RGB::named(rltk::YELLOW), RGB::named(rltk::BLACK))
+    );
+    draw_batch.print_color(
+        Point::new(11, y+count+1),
+        if saving { "ESCAPE - powrót do gry" } else { "SHIFT+litera - usun zapis, ESCAPE - wyjscie" },
+        ColorPair::new(RGB::named(rltk::YELLOW), RGB::named(rltk::BLACK))
+    );
+
+    if let Some(error) = &gs.slot_error {
+        draw_batch.print_color(Point::new(11, y+count+3), error, ColorPair::new(RGB::named(rltk::RED), RGB::named(rltk::BLACK)));
+    }
+
+    for (j, slot) in gs.save_slots.iter().enumerate() {
+        super::menu_option(&mut draw_batch, 10, y + j as i32, 97+j as rltk::FontCharType, describe_slot(slot));
+    }
+
+    draw_batch.submit(6000).expect("Unable to submit");
+
+    match ctx.key {
+        None => (SlotMenuResult::NoResponse, None),
+        Some(VirtualKeyCode::Escape) => (SlotMenuResult::Cancel, None),
+        Some(key) => {
+            let selection = rltk::letter_to_option(key);
+            if selection < 0 || selection >= count {
+                return (SlotMenuResult::NoResponse, None);
+            }
+            let occupied = gs.save_slots[selection as usize].is_some();
+            if saving {
+                (SlotMenuResult::Selected, Some(selection))
+            } else if occupied && ctx.shift {
+                (SlotMenuResult::Delete, Some(selection))
+            } else if occupied {
+                (SlotMenuResult::Selected, Some(selection))
+            } else {
+                (SlotMenuResult::NoResponse, None)
+            }
+        }
+    }
+}
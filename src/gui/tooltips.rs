@@ -1,6 +1,6 @@
 use rltk::prelude::*;
 use specs::prelude::*;
-use crate::{Pools, Map, Name, Hidden, camera, Attributes, StatusEffect, Duration};
+use crate::{Pools, Map, Name, Hidden, camera, Attributes, StatusEffect, Duration, Awareness, AwarenessState, raws::{RAWS, get_level_name}};
 use super::get_item_display_name;
 
 struct Tooltip {
@@ -93,6 +93,15 @@ pub fn draw_tooltips(ecs: &World, ctx : &mut Rltk) {
             tip.add(format!("Poziom: {}", stat.level));
         }
 
+        if let Some(awareness) = ecs.read_storage::<Awareness>().get(entity) {
+            match awareness.state {
+                AwarenessState::Asleep => tip.add("Spi."),
+                AwarenessState::Unaware => tip.add("Niczego nie podejrzewa."),
+                AwarenessState::Suspicious => tip.add("Czujny."),
+                AwarenessState::Alert => {}
+            }
+        }
+
         let statuses = ecs.read_storage::<StatusEffect>();
         let durations = ecs.read_storage::<Duration>();
         let names = ecs.read_storage::<Name>();
@@ -105,6 +114,13 @@ pub fn draw_tooltips(ecs: &World, ctx : &mut Rltk) {
         tip_boxes.push(tip);
     });
 
+    if let Some(destination) = map.connections.get(&mouse_idx) {
+        let mut tip = Tooltip::new();
+        tip.add("Przejscie");
+        tip.add(format!("Prowadzi do: {}", get_level_name(&RAWS.lock().unwrap(), *destination)));
+        tip_boxes.push(tip);
+    }
+
     if tip_boxes.is_empty() { return; }
 
     let box_gray : RGB = RGB::from_hex("#999999").expect("Oops");
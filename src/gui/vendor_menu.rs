@@ -7,7 +7,7 @@ use super::{get_item_display_name, get_item_color, menu_box};
 pub enum VendorResult { NoResponse, Cancel, Sell, BuyMode, SellMode, Buy }
 
 
-fn vendor_sell_menu(gs : &mut State, ctx : &mut Rltk, _vendor : Entity, _mode : VendorMode) -> (VendorResult, Option<Entity>, Option<String>, Option<f32>) {
+fn vendor_sell_menu(gs : &mut State, ctx : &mut Rltk, _vendor : Entity, _mode : VendorMode) -> (VendorResult, Option<Entity>, Option<i32>) {
     let mut draw_batch = DrawBatch::new();
     let player_entity = gs.ecs.fetch::<Entity>();
     let names = gs.ecs.read_storage::<Name>();
@@ -47,24 +47,24 @@ fn vendor_sell_menu(gs : &mut State, ctx : &mut Rltk, _vendor : Entity, _mode :
     draw_batch.submit(6000).expect("Unable to submit");
 
     match ctx.key {
-        None => (VendorResult::NoResponse, None, None, None),
+        None => (VendorResult::NoResponse, None, None),
         Some(key) => {
             match key {
-                VirtualKeyCode::Space => { (VendorResult::BuyMode, None, None, None) }
-                VirtualKeyCode::Escape => { (VendorResult::Cancel, None, None, None) }
+                VirtualKeyCode::Space => { (VendorResult::BuyMode, None, None) }
+                VirtualKeyCode::Escape => { (VendorResult::Cancel, None, None) }
                 _ => {
                     let selection = rltk::letter_to_option(key);
                     if selection > -1 && selection < count as i32 {
-                        return (VendorResult::Sell, Some(equippable[selection as usize]), None, None);
+                        return (VendorResult::Sell, Some(equippable[selection as usize]), None);
                     }
-                    (VendorResult::NoResponse, None, None, None)
+                    (VendorResult::NoResponse, None, None)
                 }
             }
         }
     }
 }
 
-fn vendor_buy_menu(gs : &mut State, ctx : &mut Rltk, vendor : Entity, _mode : VendorMode) -> (VendorResult, Option<Entity>, Option<String>, Option<f32>) {
+fn vendor_buy_menu(gs : &mut State, ctx : &mut Rltk, vendor : Entity, _mode : VendorMode) -> (VendorResult, Option<Entity>, Option<i32>) {
     use crate::raws::*;
     let mut draw_batch = DrawBatch::new();
 
@@ -94,24 +94,24 @@ fn vendor_buy_menu(gs : &mut State, ctx : &mut Rltk, vendor : Entity, _mode : Ve
     draw_batch.submit(6000).expect("Unable to submit");
 
     match ctx.key {
-        None => (VendorResult::NoResponse, None, None, None),
+        None => (VendorResult::NoResponse, None, None),
         Some(key) => {
             match key {
-                VirtualKeyCode::Space => { (VendorResult::SellMode, None, None, None) }
-                VirtualKeyCode::Escape => { (VendorResult::Cancel, None, None, None) }
+                VirtualKeyCode::Space => { (VendorResult::SellMode, None, None) }
+                VirtualKeyCode::Escape => { (VendorResult::Cancel, None, None) }
                 _ => {
                     let selection = rltk::letter_to_option(key);
                     if selection > -1 && selection < count as i32 {
-                        return (VendorResult::Buy, None, Some(inventory[selection as usize].0.clone()), Some(inventory[selection as usize].1));
+                        return (VendorResult::Buy, None, Some(selection));
                     }
-                    (VendorResult::NoResponse, None, None, None)
+                    (VendorResult::NoResponse, None, None)
                 }
             }
         }
     }
 }
 
-pub fn show_vendor_menu(gs : &mut State, ctx : &mut Rltk, vendor : Entity, mode : VendorMode) -> (VendorResult, Option<Entity>, Option<String>, Option<f32>) {
+pub fn show_vendor_menu(gs : &mut State, ctx : &mut Rltk, vendor : Entity, mode : VendorMode) -> (VendorResult, Option<Entity>, Option<i32>) {
     match mode {
         VendorMode::Buy => vendor_buy_menu(gs, ctx, vendor, mode),
         VendorMode::Sell => vendor_sell_menu(gs, ctx, vendor, mode)
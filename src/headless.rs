@@ -0,0 +1,179 @@
+use specs::prelude::*;
+use rltk::Point;
+use crate::{State, RunState, Map, Pools, PlayerCommand, MasterDungeonMap, execute_command, damage_system, gamelog, replay, saveload_system,
+    seed_from_args, record_path_from_args};
+
+const HEADLESS_FRAME_MS : f32 = 1000.0;
+
+// The spatial index, effect queue and generator are globals, so tests that touch them take turns
+#[cfg(test)]
+pub static TEST_LOCK : std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+pub struct Simulation {
+    pub state : State
+}
+
+impl Simulation {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Simulation {
+        Simulation::with_seed(None)
+    }
+
+    pub fn with_seed(seed : Option<u64>) -> Simulation {
+        Simulation::with_recording(seed, None)
+    }
+
+    pub fn with_recording(seed : Option<u64>, record_path : Option<String>) -> Simulation {
+        let mut state = State::new();
+        state.requested_seed = seed;
+        state.record_path = record_path;
+        state.autosave_enabled = false;
+        state.game_over_cleanup();
+        let mut sim = Simulation{ state };
+        sim.settle(RunState::PreRun);
+        sim
+    }
+
+    pub fn ecs(&self) -> &World {
+        &self.state.ecs
+    }
+
+    pub fn ecs_mut(&mut self) -> &mut World {
+        &mut self.state.ecs
+    }
+
+    pub fn run_seed(&self) -> u64 {
+        self.state.ecs.fetch::<MasterDungeonMap>().run_seed
+    }
+
+    pub fn run_state(&self) -> RunState {
+        *self.state.ecs.fetch::<RunState>()
+    }
+
+    pub fn is_game_over(&self) -> bool {
+        self.run_state() == RunState::GameOver
+    }
+
+    pub fn step(&mut self, command : PlayerCommand) -> RunState {
+        let runstate = self.run_state();
+        if !is_waiting_for_player(runstate) {
+            return runstate;
+        }
+        let newrunstate = execute_command(&mut self.state, command);
+        if runstate == RunState::AwaitingInput && newrunstate != RunState::AwaitingInput {
+            gamelog::record_event("Turn", 1);
+        }
+        self.settle(newrunstate)
+    }
+
+    fn settle(&mut self, runstate : RunState) -> RunState {
+        let mut newrunstate = runstate;
+        loop {
+            self.commit(newrunstate);
+            newrunstate = self.run_state();
+            match newrunstate {
+                RunState::GameOver => break,
+                _ if is_waiting_for_player(newrunstate) => break,
+                RunState::MapGeneration => {
+                    newrunstate = self.state.mapgen_next_state.unwrap_or(RunState::PreRun);
+                }
+                RunState::PreRun |
+                RunState::Ticking |
+                RunState::NextLevel |
+                RunState::PreviousLevel |
+                RunState::MagicMapReveal{ .. } |
+                RunState::TownPortal |
+                RunState::TeleportingToOtherLevel{ .. } => {
+                    newrunstate = self.state.advance_state(newrunstate);
+                }
+                _ => newrunstate = RunState::AwaitingInput
+            }
+        }
+        newrunstate
+    }
+
+    fn commit(&mut self, runstate : RunState) {
+        {
+            let mut runwriter = self.state.ecs.write_resource::<RunState>();
+            *runwriter = runstate;
+        }
+        damage_system::delete_the_dead(&mut self.state.ecs);
+        crate::systems::particle_system::update_particles(&mut self.state.ecs, HEADLESS_FRAME_MS);
+    }
+}
+
+fn is_waiting_for_player(runstate : RunState) -> bool {
+    matches!(runstate,
+        RunState::AwaitingInput |
+        RunState::ShowTargeting{ .. } |
+        RunState::ShowVendor{ .. } |
+        RunState::ShowRemoveCurse |
+        RunState::ShowIdentify
+    )
+}
+
+fn print_status(sim : &Simulation, turn : i32) {
+    let depth = sim.ecs().fetch::<Map>().depth;
+    let player_entity = *sim.ecs().fetch::<Entity>();
+    let pools = sim.ecs().read_storage::<Pools>();
+    let player_pos = *sim.ecs().fetch::<Point>();
+    match pools.get(player_entity) {
+        Some(player_pools) => println!("turn {}: depth {} at ({}, {}), HP {}/{}", turn, depth, player_pos.x, player_pos.y,
+            player_pools.hit_points.current, player_pools.hit_points.max),
+        None => println!("turn {}: depth {} at ({}, {}), no player", turn, depth, player_pos.x, player_pos.y)
+    }
+}
+
+pub fn run_from_args(args : &[String]) {
+    let mut script : Option<String> = None;
+    let mut turns = 100;
+    let verify_saves = args.iter().any(|a| a == "--verify-saves");
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--script" | "--replay" if i + 1 < args.len() => { script = Some(args[i+1].clone()); i += 1; }
+            "--turns" if i + 1 < args.len() => { turns = args[i+1].parse::<i32>().unwrap_or(turns); i += 1; }
+            _ => {}
+        }
+        i += 1;
+    }
+
+    let mut seed = seed_from_args(args);
+    let commands = if let Some(script) = script {
+        match replay::load_replay(&script) {
+            Ok(replay) => {
+                seed = seed.or(replay.seed);
+                replay.commands
+            }
+            Err(e) => { println!("Script error: {}", e); std::process::exit(1); }
+        }
+    } else {
+        vec![PlayerCommand::Wait; turns as usize]
+    };
+
+    let mut sim = Simulation::with_recording(seed, record_path_from_args(args));
+    println!("seed {}", sim.run_seed());
+    for (turn, command) in commands.iter().enumerate() {
+        sim.step(*command);
+        print_status(&sim, turn as i32 + 1);
+        if sim.is_game_over() {
+            println!("Game over.");
+            break;
+        }
+    }
+
+    for line in gamelog::log_lines() {
+        println!("{}", line);
+    }
+
+    if verify_saves {
+        match saveload_system::verify_round_trip(sim.ecs_mut()) {
+            Ok(sizes) => {
+                for (encoding, size) in sizes {
+                    println!("save round trip {:?}: OK, {} bytes", encoding, size);
+                }
+            }
+            Err(e) => { println!("Save round trip failed: {}", e); std::process::exit(1); }
+        }
+    }
+}
@@ -0,0 +1,752 @@
+use rltk::{GameState, Rltk, Point};
+use specs::{prelude::*, saveload::{SimpleMarker, SimpleMarkerAllocator}};
+extern crate serde;
+mod components;
+pub use components::*;
+mod systems;
+mod map;
+pub use map::*;
+use dungeon::*;
+mod player;
+pub use player::*;
+mod rect;
+pub use rect::Rect;
+mod damage_system;
+mod gui;
+mod gamelog;
+mod spawner;
+pub mod saveload_system;
+pub mod random_table;
+mod rex_assets;
+pub mod map_builders;
+pub mod raws;
+mod gamesystem;
+pub use gamesystem::*;
+pub mod spatial;
+pub mod effects;
+pub mod rng;
+pub mod headless;
+pub mod mapgen;
+pub mod replay;
+pub mod combat;
+#[macro_use]
+extern crate lazy_static;
+
+
+const SHOW_MAPGEN_VISUALIZER : bool = false;
+const SHOW_FPS : bool = false;
+const CHEATS_ALLOWED : bool = false;
+
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum VendorMode { Buy, Sell }
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum SlotAction { Overwrite, Delete }
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum RunState { AwaitingInput, 
+    PreRun, 
+    Ticking,
+    ShowInventory, 
+    ShowDropItem,
+    ShowTargeting { range : i32, item : Entity},
+    MainMenu { menu_selection : gui::MainMenuSelection },
+    SaveGame,
+    LoadGame,
+    ConfirmSlot { slot: i32, action: SlotAction },
+    NextLevel,
+    PreviousLevel,
+    TownPortal,
+    ShowRemoveItem,
+    GameOver,
+    MagicMapReveal { row : i32 },
+    MapGeneration,
+    ShowCheatMenu,
+    ShowCombatMenu,
+    ShowManeuverTargeting { maneuver : Maneuver },
+    ShowVendor { vendor: Entity, mode: VendorMode },
+    TeleportingToOtherLevel { x: i32, y: i32, map: MapId },
+    ShowRemoveCurse,
+    ShowIdentify,
+    Credits
+}
+
+pub struct State {
+    pub ecs: World,
+    mapgen_next_state : Option<RunState>,
+    mapgen_history : Vec<Map>,
+    mapgen_index : usize,
+    mapgen_timer : f32,
+    dispatcher : Box<dyn systems::UnifiedDispatcher + 'static>,
+    pub requested_seed : Option<u64>,
+    pub record_path : Option<String>,
+    recorder : Option<replay::Recorder>,
+    pub load_error : Option<String>,
+    pub slot_error : Option<String>,
+    pub save_slots : Vec<Option<saveload_system::SaveMetadata>>,
+    pub save_encoding : saveload_system::SaveEncoding,
+    pub autosave_enabled : bool,
+    autosave_pending : bool,
+    pub raws_watcher : Option<raws::RawsWatcher>
+}
+
+fn arg_value<'a>(args : &'a [String], flag : &str) -> Option<&'a String> {
+    args.iter()
+        .position(|a| a == flag)
+        .and_then(|i| args.get(i+1))
+}
+
+pub fn seed_from_args(args : &[String]) -> Option<u64> {
+    arg_value(args, "--seed").and_then(|s| s.parse::<u64>().ok())
+}
+
+pub fn record_path_from_args(args : &[String]) -> Option<String> {
+    arg_value(args, "--record").cloned()
+}
+
+pub fn raws_watcher_from_args(args : &[String]) -> Option<raws::RawsWatcher> {
+    if args.iter().any(|a| a == "--watch-raws") { Some(raws::RawsWatcher::new()) } else { None }
+}
+
+pub fn save_encoding_from_args(args : &[String]) -> saveload_system::SaveEncoding {
+    match arg_value(args, "--save-format").map(|s| s.as_str()) {
+        Some("json") => saveload_system::SaveEncoding::Json,
+        _ => saveload_system::SaveEncoding::Binary
+    }
+}
+
+impl State {
+    fn run_systems(&mut self) {
+        self.dispatcher.run_now(&mut self.ecs);
+        self.ecs.maintain();
+    }
+
+    fn advance_state(&mut self, runstate : RunState) -> RunState {
+        let mut newrunstate = runstate;
+        match runstate {
+            RunState::PreRun => {
+                self.run_systems();
+                self.ecs.maintain();
+                if self.autosave_pending {
+                    self.autosave_pending = false;
+                    if let Err(e) = saveload_system::autosave(&mut self.ecs, self.save_encoding) {
+                        rltk::console::log(format!("Autosave failed: {}", e));
+                    }
+                }
+                newrunstate = RunState::AwaitingInput;
+            }
+            RunState::Ticking => {
+                let mut should_change_target = false;
+                while newrunstate == RunState::Ticking {
+                    self.run_systems();
+                    self.ecs.maintain();
+                    match *self.ecs.fetch::<RunState>() {
+                        RunState::AwaitingInput => { newrunstate = RunState::AwaitingInput; should_change_target = true; }
+                        RunState::MagicMapReveal{ .. } => newrunstate = RunState::MagicMapReveal{ row: 0 },
+                        RunState::TownPortal => newrunstate = RunState::TownPortal,
+                        RunState::NextLevel => newrunstate = RunState::NextLevel,
+                        RunState::TeleportingToOtherLevel{ x, y, map } => newrunstate = RunState::TeleportingToOtherLevel{ x, y, map },
+                        RunState::ShowRemoveCurse => newrunstate = RunState::ShowRemoveCurse,
+                        RunState::ShowIdentify => newrunstate = RunState::ShowIdentify,
+                        _ => newrunstate = RunState::Ticking
+                    }
+                }
+                if should_change_target {
+                    player::end_turn_targeting(&mut self.ecs);
+                }
+            }
+            RunState::NextLevel | RunState::PreviousLevel => {
+                let destination = {
+                    let map = self.ecs.fetch::<Map>();
+                    let player_pos = self.ecs.fetch::<Point>();
+                    let player_idx = map.xy_idx(player_pos.x, player_pos.y);
+                    let fallback = if runstate == RunState::NextLevel { map.id().below() } else { map.id().above() };
+                    map.stairs_destination(player_idx).unwrap_or(fallback)
+                };
+                self.goto_map(destination);
+                self.mapgen_next_state = Some(RunState::PreRun);
+                newrunstate = RunState::MapGeneration;
+            }
+            RunState::MagicMapReveal{row} => {
+                let mut map = self.ecs.fetch_mut::<Map>();
+                for x in 0..map.width {
+                    let idx = map.xy_idx(x as i32,row);
+                    map.revealed_tiles[idx] = true;
+                }
+                if row == map.height-1 {
+                    newrunstate = RunState::Ticking;
+                } else {
+                    newrunstate = RunState::MagicMapReveal{ row: row+1 };
+                }
+            }
+            RunState::TownPortal => {
+                spawner::spawn_town_portal(&mut self.ecs);
+
+                self.goto_map(MapId::TOWN);
+                self.mapgen_next_state = Some(RunState::PreRun);
+                newrunstate = RunState::MapGeneration;
+            }
+            RunState::TeleportingToOtherLevel{x, y, map} => {
+                self.goto_map(map);
+                let player_entity = self.ecs.fetch::<Entity>();
+                if let Some(pos) = self.ecs.write_storage::<Position>().get_mut(*player_entity) {
+                    pos.x = x;
+                    pos.y = y;
+                }
+                let mut ppos = self.ecs.fetch_mut::<rltk::Point>();
+                ppos.x = x;
+                ppos.y = y;
+                self.mapgen_next_state = Some(RunState::PreRun);
+                newrunstate = RunState::MapGeneration;
+            }
+            _ => {}
+        }
+        newrunstate
+    }
+}
+
+
+
+impl GameState for State {
+    fn tick(&mut self, ctx : &mut Rltk) {
+        let mut newrunstate;
+        {
+            let runstate = self.ecs.fetch::<RunState>();
+            newrunstate = *runstate;
+        }
+
+        ctx.cls();
+        systems::particle_system::update_particles(&mut self.ecs, ctx.frame_time_ms);
+
+        if newrunstate == RunState::AwaitingInput {
+            let raws_changed = self.raws_watcher.as_mut().is_some_and(|watcher| watcher.has_changed(ctx.frame_time_ms));
+            if raws_changed {
+                self.reload_game_data();
+            }
+        }
+
+        match newrunstate {
+            RunState::MainMenu {..} => {}
+            RunState::Credits {..} => {}
+            RunState::GameOver {..} => {}
+            RunState::LoadGame => {}
+            RunState::ConfirmSlot{ action: SlotAction::Delete, .. } => {}
+            _ => {
+                camera::render_camera(&self.ecs, ctx);
+                gui::draw_ui(&self.ecs, ctx);
+            }
+        }
+
+        match newrunstate {
+            RunState::MapGeneration => {
+                if !SHOW_MAPGEN_VISUALIZER {
+                    newrunstate = self.mapgen_next_state.unwrap();
+                }
+                ctx.cls();
+                if self.mapgen_index < self.mapgen_history.len() { camera::render_debug_map(&self.mapgen_history[self.mapgen_index], ctx); }
+
+                self.mapgen_timer += ctx.frame_time_ms;
+                if self.mapgen_timer > 50.0 {
+                    self.mapgen_timer = 0.0;
+                    self.mapgen_index += 1;
+                    if self.mapgen_index >= self.mapgen_history.len() {
+                        newrunstate = self.mapgen_next_state.unwrap();
+                    }
+                }
+            }
+            RunState::PreRun |
+            RunState::Ticking |
+            RunState::NextLevel |
+            RunState::PreviousLevel |
+            RunState::MagicMapReveal{ .. } |
+            RunState::TownPortal |
+            RunState::TeleportingToOtherLevel{ .. } => {
+                newrunstate = self.advance_state(newrunstate);
+            }
+            RunState::AwaitingInput => {
+                newrunstate = player_input(self, ctx);
+                if newrunstate != RunState::AwaitingInput {
+                    crate::gamelog::record_event("Turn", 1);
+                }
+            }
+            RunState::ShowInventory => {
+                let result = gui::show_inventory(self, ctx);
+                match result.0 {
+                    gui::ItemMenuResult::Cancel => newrunstate = RunState::AwaitingInput,
+                    gui::ItemMenuResult::NoResponse => {}
+                    gui::ItemMenuResult::Selected => {
+                        newrunstate = execute_command(self, PlayerCommand::UseItem{ item: result.1.unwrap().id() });
+                    }
+                }
+            }
+            RunState::ShowDropItem => {
+                let result = gui::drop_item_menu(self, ctx);
+                match result.0 {
+                    gui::ItemMenuResult::Cancel => newrunstate = RunState::AwaitingInput,
+                    gui::ItemMenuResult::NoResponse => {}
+                    gui::ItemMenuResult::Selected => {
+                        newrunstate = execute_command(self, PlayerCommand::DropItem{ item: result.1.unwrap().id() });
+                    }
+                }
+            }
+            RunState::ShowTargeting {range, item: _} => {
+                let result = gui::ranged_target(self, ctx, range);
+                match result.0 {
+                    gui::ItemMenuResult::Cancel => newrunstate = execute_command(self, PlayerCommand::Cancel),
+                    gui::ItemMenuResult::NoResponse => {}
+                    gui::ItemMenuResult::Selected => {
+                        newrunstate = execute_command(self, PlayerCommand::SelectTarget{ target: result.1.unwrap() });
+                    }
+                }
+            }
+            RunState::MainMenu { .. } => {
+                let result = gui::main_menu(self, ctx);
+                match result {
+                    gui::MainMenuResult::NoSelection{ selected } => newrunstate = RunState::MainMenu{ menu_selection: selected },
+                    gui::MainMenuResult::Selected{ selected } => {
+                        match selected {
+                            gui::MainMenuSelection::NewGame => {
+                                self.load_error = None;
+                                self.game_over_cleanup();
+                                newrunstate = RunState::PreRun;
+                            }
+                            gui::MainMenuSelection::LoadGame => {
+                                self.slot_error = None;
+                                newrunstate = RunState::LoadGame;
+                            }
+                            gui::MainMenuSelection::RestoreAutosave => {
+                                let result = saveload_system::load_autosave(&mut self.ecs);
+                                newrunstate = self.finish_loading(result);
+                            }
+                            gui::MainMenuSelection::Credits => { newrunstate = RunState::Credits; }
+                            gui::MainMenuSelection::Quit => { ::std::process::exit(0); }
+                        }
+                    }
+                }
+            }
+            RunState::GameOver => {
+                let result = gui::game_over(self, ctx);
+                match result {
+                    gui::GameOverResult::NoSelection => {}
+                    gui::GameOverResult::QuitToMenu => {
+                        self.game_over_cleanup();
+                        newrunstate = RunState::MainMenu{ menu_selection: gui::MainMenuSelection::NewGame };
+                    }
+                }
+            }
+            RunState::Credits => {
+                let result = gui::credits(self, ctx);
+                match result {
+                    gui::CreditsResult::NoSelection => {}
+                    gui::CreditsResult::QuitToMenu => {
+                        newrunstate = RunState::MainMenu { menu_selection: gui::MainMenuSelection::Credits };
+                    }
+                }
+            }
+            RunState::SaveGame => {
+                let result = gui::save_slot_menu(self, ctx, true);
+                match result.0 {
+                    gui::SlotMenuResult::Cancel => newrunstate = RunState::AwaitingInput,
+                    gui::SlotMenuResult::NoResponse | gui::SlotMenuResult::Delete => {}
+                    gui::SlotMenuResult::Selected => {
+                        let slot = result.1.unwrap();
+                        if self.save_slots[slot as usize].is_some() {
+                            newrunstate = RunState::ConfirmSlot{ slot, action: SlotAction::Overwrite };
+                        } else {
+                            let result = saveload_system::save_game(&mut self.ecs, slot, self.save_encoding);
+                            newrunstate = self.finish_saving(result);
+                        }
+                    }
+                }
+            }
+            RunState::LoadGame => {
+                let result = gui::save_slot_menu(self, ctx, false);
+                match result.0 {
+                    gui::SlotMenuResult::Cancel => newrunstate = RunState::MainMenu{ menu_selection : gui::MainMenuSelection::LoadGame },
+                    gui::SlotMenuResult::NoResponse => {}
+                    gui::SlotMenuResult::Delete => {
+                        newrunstate = RunState::ConfirmSlot{ slot: result.1.unwrap(), action: SlotAction::Delete };
+                    }
+                    gui::SlotMenuResult::Selected => {
+                        let result = saveload_system::load_game(&mut self.ecs, result.1.unwrap());
+                        newrunstate = self.finish_loading(result);
+                    }
+                }
+            }
+            RunState::ConfirmSlot{ slot, action } => {
+                let question = match action {
+                    SlotAction::Overwrite => format!("Nadpisac zapis {}?", slot + 1),
+                    SlotAction::Delete => format!("Usunac zapis {}?", slot + 1)
+                };
+                match gui::confirm_menu(ctx, &question) {
+                    gui::ConfirmResult::NoResponse => {}
+                    gui::ConfirmResult::No => {
+                        newrunstate = match action {
+                            SlotAction::Overwrite => RunState::SaveGame,
+                            SlotAction::Delete => RunState::LoadGame
+                        };
+                    }
+                    gui::ConfirmResult::Yes => {
+                        match action {
+                            SlotAction::Overwrite => {
+                                let result = saveload_system::save_game(&mut self.ecs, slot, self.save_encoding);
+                                newrunstate = self.finish_saving(result);
+                            }
+                            SlotAction::Delete => {
+                                match saveload_system::delete_save(slot) {
+                                    Ok(()) => {
+                                        self.slot_error = None;
+                                        if saveload_system::does_save_exist() {
+                                            newrunstate = RunState::LoadGame;
+                                        } else {
+                                            newrunstate = RunState::MainMenu{ menu_selection : gui::MainMenuSelection::NewGame };
+                                        }
+                                    }
+                                    Err(e) => {
+                                        self.slot_error = Some(e);
+                                        newrunstate = RunState::LoadGame;
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            RunState::ShowRemoveItem => {
+                let result = gui::remove_item_menu(self, ctx);
+                match result.0 {
+                    gui::ItemMenuResult::Cancel => newrunstate = RunState::AwaitingInput,
+                    gui::ItemMenuResult::NoResponse => {}
+                    gui::ItemMenuResult::Selected => {
+                        newrunstate = execute_command(self, PlayerCommand::RemoveItem{ item: result.1.unwrap().id() });
+                    }
+                }
+            }
+            RunState::ShowCombatMenu => {
+                let result = gui::show_combat_menu(self, ctx);
+                match result {
+                    gui::CombatMenuResult::Cancel => newrunstate = RunState::AwaitingInput,
+                    gui::CombatMenuResult::NoResponse => {}
+                    gui::CombatMenuResult::Selected{ maneuver } => newrunstate = RunState::ShowManeuverTargeting{ maneuver }
+                }
+            }
+            RunState::ShowManeuverTargeting{ maneuver } => {
+                let result = gui::melee_target(self, ctx);
+                match result.0 {
+                    gui::ItemMenuResult::Cancel => newrunstate = execute_command(self, PlayerCommand::Cancel),
+                    gui::ItemMenuResult::NoResponse => {}
+                    gui::ItemMenuResult::Selected => {
+                        newrunstate = execute_command(self, PlayerCommand::Maneuver{ maneuver, target: result.1.unwrap() });
+                    }
+                }
+            }
+            RunState::ShowCheatMenu => {
+                let result = gui::show_cheat_mode(self, ctx);
+                match result {
+                    gui::CheatMenuResult::Cancel => newrunstate = RunState::AwaitingInput,
+                    gui::CheatMenuResult::NoResponse => {}
+                    gui::CheatMenuResult::TeleportToExit => newrunstate = execute_command(self, PlayerCommand::Cheat{ cheat: Cheat::TeleportToExit }),
+                    gui::CheatMenuResult::Heal => newrunstate = execute_command(self, PlayerCommand::Cheat{ cheat: Cheat::Heal }),
+                    gui::CheatMenuResult::Revive => newrunstate = execute_command(self, PlayerCommand::Cheat{ cheat: Cheat::RevealMap }),
+                    gui::CheatMenuResult::ReloadRaws => newrunstate = execute_command(self, PlayerCommand::Cheat{ cheat: Cheat::ReloadRaws }),
+                    gui::CheatMenuResult::GodMode => newrunstate = execute_command(self, PlayerCommand::Cheat{ cheat: Cheat::GodMode })
+                }
+            }
+            RunState::ShowVendor{vendor, mode} => {
+                let result = gui::show_vendor_menu(self, ctx, vendor, mode);
+                match result.0 {
+                    gui::VendorResult::Cancel => newrunstate = execute_command(self, PlayerCommand::Cancel),
+                    gui::VendorResult::NoResponse => {}
+                    gui::VendorResult::Sell => {
+                        newrunstate = execute_command(self, PlayerCommand::SellItem{ item: result.1.unwrap().id() });
+                    }
+                    gui::VendorResult::Buy => {
+                        newrunstate = execute_command(self, PlayerCommand::BuyItem{ index: result.2.unwrap() });
+                    }
+                    gui::VendorResult::BuyMode => newrunstate = RunState::ShowVendor{vendor, mode: VendorMode::Buy },
+                    gui::VendorResult::SellMode => newrunstate = RunState::ShowVendor{vendor, mode: VendorMode::Sell }
+                }
+            }
+            RunState::ShowRemoveCurse => {
+                let result = gui::remove_curse_menu(self, ctx);
+                match result.0 {
+                    gui::ItemMenuResult::Cancel => newrunstate = execute_command(self, PlayerCommand::Cancel),
+                    gui::ItemMenuResult::NoResponse => {}
+                    gui::ItemMenuResult::Selected => {
+                        newrunstate = execute_command(self, PlayerCommand::RemoveCurse{ item: result.1.unwrap().id() });
+                    }
+                }
+            }
+            RunState::ShowIdentify => {
+                let result = gui::identify_menu(self, ctx);
+                match result.0 {
+                    gui::ItemMenuResult::Cancel => newrunstate = execute_command(self, PlayerCommand::Cancel),
+                    gui::ItemMenuResult::NoResponse => {}
+                    gui::ItemMenuResult::Selected => {
+                        newrunstate = execute_command(self, PlayerCommand::Identify{ item: result.1.unwrap().id() });
+                    }
+                }
+            }
+        }
+
+        let opens_slot_menu = matches!(newrunstate, RunState::SaveGame | RunState::LoadGame);
+        if opens_slot_menu && newrunstate != *self.ecs.fetch::<RunState>() {
+            self.save_slots = saveload_system::list_save_slots();
+        }
+
+        {
+            let mut runwriter = self.ecs.write_resource::<RunState>();
+            *runwriter = newrunstate;
+        }
+        damage_system::delete_the_dead(&mut self.ecs);
+
+        rltk::render_draw_buffer(ctx).expect("Unable to render buffer");
+        if SHOW_FPS {
+            ctx.print(1, 59, &format!("FPS: {}", ctx.fps));
+        }
+
+    }
+}
+
+impl State {
+    fn goto_map(&mut self, destination : MapId) {
+        freeze_level_entities(&mut self.ecs);
+
+        let current_map = self.ecs.fetch::<Map>().id();
+        self.generate_world_map(destination, current_map);
+
+        crate::gamelog::Logger::new().append("Wieclaw przemieszcza sie.").log();
+        self.autosave_pending = self.autosave_enabled;
+    }
+
+    fn reload_game_data(&mut self) {
+        match raws::reload_raws() {
+            Ok(()) => {
+                self.ecs.write_resource::<MasterDungeonMap>().add_missing_mappings();
+                raws::spawn_all_spells(&mut self.ecs);
+                gamelog::Logger::new().color(rltk::GREEN).append("Dane gry zostaly przeladowane.").log();
+            }
+            Err(problems) => {
+                gamelog::Logger::new().color(rltk::RED).append("Nie przeladowano danych gry:").log();
+                for problem in problems.iter() {
+                    gamelog::Logger::new().color(rltk::RED).append(problem).log();
+                }
+            }
+        }
+    }
+
+    // A failed save leaves the player in the game with the reason in the log, so they can try another slot
+    fn finish_saving(&mut self, result : Result<(), String>) -> RunState {
+        match result {
+            Ok(()) => RunState::MainMenu{ menu_selection : gui::MainMenuSelection::LoadGame },
+            Err(e) => {
+                rltk::console::log(format!("Save failed: {}", e));
+                gamelog::Logger::new().color(rltk::RED).append(format!("Nie udalo sie zapisac gry: {}", e)).log();
+                RunState::AwaitingInput
+            }
+        }
+    }
+
+    fn finish_loading(&mut self, result : Result<(), String>) -> RunState {
+        match result {
+            Ok(()) => {
+                self.load_error = None;
+                // A replay starts from the run seed and cannot follow a jump into a saved game
+                if let Some(mut recorder) = self.recorder.take() {
+                    recorder.finish("game loaded from a save");
+                    gamelog::Logger::new().color(rltk::YELLOW).append("Nagrywanie zakonczone, wczytanego zapisu nie da sie odtworzyc.").log();
+                }
+                RunState::AwaitingInput
+            }
+            Err(e) => {
+                self.load_error = Some(e);
+                RunState::MainMenu{ menu_selection: gui::MainMenuSelection::NewGame }
+            }
+        }
+    }
+
+    fn game_over_cleanup(&mut self) {
+        let mut to_delete = Vec::new();
+        for e in self.ecs.entities().join() {
+            to_delete.push(e);
+        }
+        for del in to_delete.iter() {
+            self.ecs.delete_entity(*del).expect("Deletion failed");
+        }
+
+        {
+            let player_entity = spawner::player(&mut self.ecs, 0, 0);
+            let mut player_entity_writer = self.ecs.write_resource::<Entity>();
+            *player_entity_writer = player_entity;
+        }
+
+        let run_seed = self.requested_seed.unwrap_or_else(rng::new_run_seed);
+        self.ecs.insert(MasterDungeonMap::new(run_seed));
+
+        self.recorder = None;
+        if let Some(path) = &self.record_path {
+            match replay::Recorder::create(path, run_seed) {
+                Ok(recorder) => self.recorder = Some(recorder),
+                Err(e) => rltk::console::log(format!("Unable to record to {}: {}", path, e))
+            }
+        }
+
+        self.generate_world_map(MapId::TOWN, MapId::TOWN);
+    }
+
+    fn generate_world_map(&mut self, destination : MapId, from : MapId) {
+        self.mapgen_index = 0;
+        self.mapgen_timer = 0.0;
+        self.mapgen_history.clear();
+        let map_building_info = level_transition(&mut self.ecs, destination, from);
+        if let Some(history) = map_building_info {
+            self.mapgen_history = history;
+        } else {
+            thaw_level_entities(&mut self.ecs);
+        }
+
+        gamelog::clear_log();
+        gamelog::Logger::new()
+            .append("Wieclaw budzi sie w knajpie Pod Smierdzaca Pacha i zauwaza, ze zniknal jego")
+            .color(rltk::GOLD)
+            .append("Zloty Zombek!")
+            .log();
+
+        gamelog::clear_events();
+    }
+}
+
+impl State {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> State {
+        let mut gs = State {
+            ecs: World::new(),
+            mapgen_next_state : Some(RunState::MainMenu{ menu_selection: gui::MainMenuSelection::NewGame }),
+            mapgen_index : 0,
+            mapgen_history: Vec::new(),
+            mapgen_timer: 0.0,
+            dispatcher: systems::build(),
+            requested_seed: None,
+            record_path: None,
+            recorder: None,
+            load_error: None,
+            slot_error: None,
+            save_slots: Vec::new(),
+            save_encoding: saveload_system::SaveEncoding::Binary,
+            autosave_enabled: true,
+            autosave_pending: false,
+            raws_watcher: None
+        };
+
+        gs.ecs.register::<Position>();
+        gs.ecs.register::<Renderable>();
+        gs.ecs.register::<Player>();
+        gs.ecs.register::<Viewshed>();
+        gs.ecs.register::<Name>();
+        gs.ecs.register::<BlocksTile>();
+        gs.ecs.register::<WantsToMelee>();
+        gs.ecs.register::<Item>();
+        gs.ecs.register::<ProvidesHealing>();
+        gs.ecs.register::<InBackpack>();
+        gs.ecs.register::<WantsToPickupItem>();
+        gs.ecs.register::<WantsToUseItem>();
+        gs.ecs.register::<WantsToDropItem>();
+        gs.ecs.register::<Consumable>();
+        gs.ecs.register::<Ranged>();
+        gs.ecs.register::<InflictsDamage>();
+        gs.ecs.register::<AreaOfEffect>();
+        gs.ecs.register::<Confusion>();
+        gs.ecs.register::<SimpleMarker<SerializeMe>>();
+        gs.ecs.register::<SerializationHelper>();
+        gs.ecs.register::<DMSerializationHelper>();
+        gs.ecs.register::<Equippable>();
+        gs.ecs.register::<Equipped>();
+        gs.ecs.register::<Weapon>();
+        gs.ecs.register::<Wearable>();
+        gs.ecs.register::<WantsToRemoveItem>();
+        gs.ecs.register::<ParticleLifetime>();
+        gs.ecs.register::<HungerClock>();
+        gs.ecs.register::<ProvidesFood>();
+        gs.ecs.register::<MagicMapper>();
+        gs.ecs.register::<Hidden>();
+        gs.ecs.register::<EntryTrigger>();
+        gs.ecs.register::<EntityMoved>();
+        gs.ecs.register::<SingleActivation>();
+        gs.ecs.register::<BlocksVisibility>();
+        gs.ecs.register::<Door>();
+        gs.ecs.register::<Quips>();
+        gs.ecs.register::<Attributes>();
+        gs.ecs.register::<Skills>();
+        gs.ecs.register::<Pools>();
+        gs.ecs.register::<NaturalAttackDefense>();
+        gs.ecs.register::<LootTable>();
+        gs.ecs.register::<OtherLevelPosition>();
+        gs.ecs.register::<LightSource>();
+        gs.ecs.register::<Initiative>();
+        gs.ecs.register::<MyTurn>();
+        gs.ecs.register::<Faction>();
+        gs.ecs.register::<WantsToApproach>();
+        gs.ecs.register::<WantsToFlee>();
+        gs.ecs.register::<MoveMode>();
+        gs.ecs.register::<Chasing>();
+        gs.ecs.register::<EquipmentChanged>();
+        gs.ecs.register::<Vendor>();
+        gs.ecs.register::<TownPortal>();
+        gs.ecs.register::<TeleportTo>();
+        gs.ecs.register::<ApplyMove>();
+        gs.ecs.register::<ApplyTeleport>();
+        gs.ecs.register::<MagicItem>();
+        gs.ecs.register::<ObfuscatedName>();
+        gs.ecs.register::<IdentifiedItem>();
+        gs.ecs.register::<SpawnParticleLine>();
+        gs.ecs.register::<SpawnParticleBurst>();
+        gs.ecs.register::<CursedItem>();
+        gs.ecs.register::<ProvidesRemoveCurse>();
+        gs.ecs.register::<ProvidesIdentification>();
+        gs.ecs.register::<AttributeBonus>();
+        gs.ecs.register::<Duration>();
+        gs.ecs.register::<StatusEffect>();
+        gs.ecs.register::<KnownSpells>();
+        gs.ecs.register::<SpellTemplate>();
+        gs.ecs.register::<WantsToCastSpell>();
+        gs.ecs.register::<ProvidesMana>();
+        gs.ecs.register::<TeachesSpell>();
+        gs.ecs.register::<Slow>();
+        gs.ecs.register::<DamageOverTime>();
+        gs.ecs.register::<SpecialAbilities>();
+        gs.ecs.register::<TileSize>();
+        gs.ecs.register::<OnDeath>();
+        gs.ecs.register::<AlwaysTargetsSelf>();
+        gs.ecs.register::<Stationary>();
+        gs.ecs.register::<Target>();
+        gs.ecs.register::<WantsToShoot>();
+        gs.ecs.register::<Digs>();
+        gs.ecs.register::<PoisonGas>();
+        gs.ecs.register::<Ammunition>();
+        gs.ecs.register::<Magazine>();
+        gs.ecs.register::<Reloading>();
+        gs.ecs.register::<Resistances>();
+        gs.ecs.register::<WantsToManeuver>();
+        gs.ecs.register::<Maneuvers>();
+        gs.ecs.register::<Prone>();
+        gs.ecs.register::<Awareness>();
+        gs.ecs.insert(SimpleMarkerAllocator::<SerializeMe>::new());
+
+        raws::load_raws();
+
+        // The startup world is thrown away on New Game, but the entity ids it frees are not;
+        // a fixed seed keeps those (and so every later run) reproducible.
+        gs.ecs.insert(MasterDungeonMap::new(0));
+        gs.ecs.insert(Map::new(1, 64, 64, "Nowa Mapa"));
+        gs.ecs.insert(Point::new(0, 0));
+        let player_entity = spawner::player(&mut gs.ecs, 0, 0);
+        gs.ecs.insert(player_entity);
+        gs.ecs.insert(RunState::MapGeneration{} );
+        gs.ecs.insert(systems::particle_system::ParticleBuilder::new());
+        gs.ecs.insert(rex_assets::RexAssets::new());
+
+        gs.generate_world_map(MapId::TOWN, MapId::TOWN);
+        gs
+    }
+}
@@ -0,0 +1,81 @@
+use serde::{Serialize, Deserialize, Serializer, Deserializer};
+use std::{fmt, str::FromStr};
+
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Branch {
+    #[default]
+    Main,
+    Overworld,
+    Mines
+}
+
+const BRANCHES : [Branch; 3] = [Branch::Main, Branch::Overworld, Branch::Mines];
+
+impl Branch {
+    pub fn key(self) -> &'static str {
+        match self {
+            Branch::Main => "main",
+            Branch::Overworld => "overworld",
+            Branch::Mines => "mines"
+        }
+    }
+
+    pub fn from_key(key : &str) -> Option<Branch> {
+        BRANCHES.iter().find(|branch| branch.key() == key).copied()
+    }
+}
+
+// A level is identified by its branch and its depth, which also sets how dangerous it is
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug)]
+pub struct MapId {
+    pub branch : Branch,
+    pub depth : i32
+}
+
+impl MapId {
+    pub const TOWN : MapId = MapId{ branch: Branch::Main, depth: 1 };
+
+    pub fn new(branch : Branch, depth : i32) -> MapId {
+        MapId{ branch, depth }
+    }
+
+    pub fn below(self) -> MapId {
+        MapId::new(self.branch, self.depth + 1)
+    }
+
+    pub fn above(self) -> MapId {
+        MapId::new(self.branch, self.depth - 1)
+    }
+}
+
+impl fmt::Display for MapId {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.branch.key(), self.depth)
+    }
+}
+
+impl FromStr for MapId {
+    type Err = String;
+
+    fn from_str(s : &str) -> Result<MapId, String> {
+        let (branch, depth) = s.split_once(':').ok_or(format!("[{}] is not a branch:depth map id", s))?;
+        let branch = Branch::from_key(branch).ok_or(format!("unknown branch [{}]", branch))?;
+        let depth = depth.parse::<i32>().map_err(|_| format!("[{}] is not a depth", depth))?;
+        Ok(MapId::new(branch, depth))
+    }
+}
+
+// Written as "branch:depth", so map ids can key the dungeon's maps in JSON
+impl Serialize for MapId {
+    fn serialize<S : Serializer>(&self, serializer : S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for MapId {
+    fn deserialize<D : Deserializer<'de>>(deserializer : D) -> Result<MapId, D::Error> {
+        let id = String::deserialize(deserializer)?;
+        id.parse().map_err(serde::de::Error::custom)
+    }
+}
@@ -1,47 +1,59 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, BTreeSet};
 use specs::prelude::*;
 use serde::{Serialize, Deserialize};
-use super::{Map, TileType, super::{Viewshed, Position, map_builders::level_builder, OtherLevelPosition}};
+use super::{Map, MapId, TileType, super::{Viewshed, Position, map_builders::generate_level, OtherLevelPosition}};
 use rltk::Point;
 
 #[derive(Default, Serialize, Deserialize, Clone)]
 pub struct MasterDungeonMap {
-    maps : HashMap<i32, Map>,
-    pub identified_items : HashSet<String>,
+    maps : HashMap<MapId, Map>,
+    pub identified_items : BTreeSet<String>,
     pub scroll_mappings : HashMap<String, String>,
-    pub potion_mappings : HashMap<String, String>
+    pub potion_mappings : HashMap<String, String>,
+    #[serde(default)]
+    pub run_seed : u64
 }
 
 impl MasterDungeonMap {
-    pub fn new() -> MasterDungeonMap {
+    pub fn new(run_seed : u64) -> MasterDungeonMap {
+        crate::rng::reseed(run_seed);
         let mut dm = MasterDungeonMap{
             maps: HashMap::new(),
-            identified_items: HashSet::new(),
+            identified_items: BTreeSet::new(),
             scroll_mappings: HashMap::new(),
-            potion_mappings: HashMap::new()
+            potion_mappings: HashMap::new(),
+            run_seed
         };
 
+        dm.add_missing_mappings();
+        dm
+    }
+
+    // Raws reloaded mid-game can bring scrolls and potions that have no disguise yet
+    pub fn add_missing_mappings(&mut self) {
         for scroll_tag in crate::raws::get_scroll_tags().iter() {
-            let masked_name = make_scroll_name();
-            dm.scroll_mappings.insert(scroll_tag.to_string(), masked_name);
+            if !self.scroll_mappings.contains_key(scroll_tag) {
+                let masked_name = make_scroll_name();
+                self.scroll_mappings.insert(scroll_tag.to_string(), masked_name);
+            }
         }
 
-        let mut used_potion_names : HashSet<String> = HashSet::new();
+        let mut used_potion_names : HashSet<String> = self.potion_mappings.values().cloned().collect();
         for potion_tag in crate::raws::get_potion_tags().iter() {
-            let masked_name = make_potion_name(&mut used_potion_names);
-            dm.potion_mappings.insert(potion_tag.to_string(), masked_name);
+            if !self.potion_mappings.contains_key(potion_tag) {
+                let masked_name = make_potion_name(&mut used_potion_names);
+                self.potion_mappings.insert(potion_tag.to_string(), masked_name);
+            }
         }
-
-        dm
     }
 
     pub fn store_map(&mut self, map : &Map) {
-        self.maps.insert(map.depth, map.clone());
+        self.maps.insert(map.id(), map.clone());
     }
 
-    pub fn get_map(&self, depth : i32) -> Option<Map> {
-        if self.maps.contains_key(&depth) {
-            let result = self.maps[&depth].clone();
+    pub fn get_map(&self, id : MapId) -> Option<Map> {
+        if self.maps.contains_key(&id) {
+            let result = self.maps[&id].clone();
             Some(result)
         } else {
             None
@@ -49,35 +61,37 @@ impl MasterDungeonMap {
     }
 }
 
-pub fn level_transition(ecs : &mut World, new_depth: i32, offset: i32) -> Option<Vec<Map>> {
+pub fn level_transition(ecs : &mut World, destination : MapId, from : MapId) -> Option<Vec<Map>> {
     let dungeon_master = ecs.read_resource::<MasterDungeonMap>();
 
-    if dungeon_master.get_map(new_depth).is_some() {
+    if dungeon_master.get_map(destination).is_some() {
         std::mem::drop(dungeon_master);
-        transition_to_existing_map(ecs, new_depth, offset);
+        transition_to_existing_map(ecs, destination, from);
         None
     } else {
         std::mem::drop(dungeon_master);
-        Some(transition_to_new_map(ecs, new_depth))
+        Some(transition_to_new_map(ecs, destination, from))
     }
 }
 
-fn transition_to_new_map(ecs: &mut World, new_depth: i32) -> Vec<Map> {
-    let mut builder = level_builder(new_depth, 80, 50);
-    builder.build_map();
-    if new_depth > 1 {
-        if let Some(pos) = &builder.build_data.starting_position {
-            let up_idx = builder.build_data.map.xy_idx(pos.x, pos.y);
-            builder.build_data.map.tiles[up_idx] = TileType::UpStairs;
-        }
-    }
+fn transition_to_new_map(ecs: &mut World, destination : MapId, from : MapId) -> Vec<Map> {
+    let run_seed = ecs.fetch::<MasterDungeonMap>().run_seed;
+    let mut builder = generate_level(run_seed, destination, 80, 50);
     let mapgen_history = builder.build_data.history.clone();
-    let player_start;
-    {
-        let mut worldmap_resource = ecs.write_resource::<Map>();
-        *worldmap_resource = builder.build_data.map.clone();
-        player_start = builder.build_data.starting_position.as_mut().unwrap().clone();
+    let mut player_start = builder.build_data.starting_position.clone().unwrap();
+
+    // Arriving from another branch, the player comes out where the level connects back to it,
+    // or else the up stairs at the start are made to lead back the way they came
+    let map = &mut builder.build_data.map;
+    if let Some(idx) = map.connection_to(from) {
+        player_start = Position{ x: idx as i32 % map.width, y: idx as i32 / map.width };
+    } else if from.branch != destination.branch {
+        let start_idx = map.xy_idx(player_start.x, player_start.y);
+        if map.tiles[start_idx] == TileType::UpStairs {
+            map.connections.insert(start_idx, from);
+        }
     }
+    *ecs.write_resource::<Map>() = builder.build_data.map.clone();
 
     builder.spawn_entities(ecs);
 
@@ -104,16 +118,17 @@ fn transition_to_new_map(ecs: &mut World, new_depth: i32) -> Vec<Map> {
     mapgen_history
 }
 
-fn transition_to_existing_map(ecs: &mut World, new_depth: i32, offset: i32) {
+fn transition_to_existing_map(ecs: &mut World, destination : MapId, from : MapId) {
     let dungeon_master = ecs.read_resource::<MasterDungeonMap>();
-    let map = dungeon_master.get_map(new_depth).unwrap();
+    let map = dungeon_master.get_map(destination).unwrap();
     let mut worldmap_resource = ecs.write_resource::<Map>();
     let player_entity = ecs.fetch::<Entity>();
 
     let w = map.width;
-    let stair_type = if offset < 0 { TileType::DownStairs } else { TileType::UpStairs };
+    let stair_type = if from.depth > destination.depth { TileType::DownStairs } else { TileType::UpStairs };
+    let arrival = map.connection_to(from);
     for (idx, tt) in map.tiles.iter().enumerate() {
-        if *tt == stair_type {
+        if arrival.map_or(*tt == stair_type, |arrival| arrival == idx) {
             let mut player_position = ecs.write_resource::<Point>();
             *player_position = Point::new(idx as i32 % w, idx as i32 / w);
             let mut position_components = ecs.write_storage::<Position>();
@@ -139,12 +154,12 @@ pub fn freeze_level_entities(ecs: &mut World) {
     let mut positions = ecs.write_storage::<Position>();
     let mut other_level_positions = ecs.write_storage::<OtherLevelPosition>();
     let player_entity = ecs.fetch::<Entity>();
-    let map_depth = ecs.fetch::<Map>().depth;
+    let map_id = ecs.fetch::<Map>().id();
 
     let mut pos_to_delete : Vec<Entity> = Vec::new();
     for (entity, pos) in (&entities, &positions).join() {
         if entity != *player_entity {
-            other_level_positions.insert(entity, OtherLevelPosition{ x: pos.x, y: pos.y, depth: map_depth }).expect("Insert fail");
+            other_level_positions.insert(entity, OtherLevelPosition{ x: pos.x, y: pos.y, map: map_id }).expect("Insert fail");
             pos_to_delete.push(entity);
         }
     }
@@ -159,11 +174,11 @@ pub fn thaw_level_entities(ecs: &mut World) {
     let mut positions = ecs.write_storage::<Position>();
     let mut other_level_positions = ecs.write_storage::<OtherLevelPosition>();
     let player_entity = ecs.fetch::<Entity>();
-    let map_depth = ecs.fetch::<Map>().depth;
+    let map_id = ecs.fetch::<Map>().id();
 
     let mut pos_to_delete : Vec<Entity> = Vec::new();
     for (entity, pos) in (&entities, &other_level_positions).join() {
-        if entity != *player_entity && pos.depth == map_depth {
+        if entity != *player_entity && pos.map == map_id {
             positions.insert(entity, Position{ x: pos.x, y: pos.y }).expect("Insert fail");
             pos_to_delete.push(entity);
         }
@@ -1,30 +1,62 @@
 use rltk::{ BaseMap, Algorithm2D, Point };
-use std::collections::HashSet;
+use std::collections::{BTreeSet, BTreeMap};
 use serde::{Serialize, Deserialize};
 mod tiletype;
-pub use tiletype::{TileType, tile_walkable, tile_opaque, tile_cost};
+pub use tiletype::{TileType, tile_walkable, tile_blocks_monsters, tile_opaque, tile_cost, tile_hardness, tile_hazard, tile_from_key};
 mod themes;
 pub use themes::*;
+mod branches;
+pub use branches::*;
 pub mod dungeon;
 pub mod camera;
+pub mod rle;
 
 
 #[derive(Default, Serialize, Deserialize, Clone)]
 pub struct Map {
+    #[serde(with = "rle")]
     pub tiles : Vec<TileType>,
     pub width : i32,
     pub height : i32,
+    #[serde(with = "rle")]
     pub revealed_tiles : Vec<bool>,
+    #[serde(with = "rle")]
     pub visible_tiles : Vec<bool>,
     pub depth : i32,
-    pub bloodstains : HashSet<usize>,
-    pub view_blocked : HashSet<usize>,
+    #[serde(with = "rle::indices")]
+    pub bloodstains : BTreeSet<usize>,
+    // Rebuilt by the visibility system every turn
+    #[serde(skip)]
+    pub view_blocked : BTreeSet<usize>,
     pub name : String,
     pub outdoors : bool,
+    pub theme : Theme,
+    pub branch : Branch,
+    pub connections : BTreeMap<usize, MapId>,
+    pub tile_damage : BTreeMap<usize, i32>,
+    pub gas : BTreeMap<usize, i32>,
+    #[serde(with = "rle")]
     pub light : Vec<rltk::RGB>
 }
 
 impl Map {
+    pub fn id(&self) -> MapId {
+        MapId::new(self.branch, self.depth)
+    }
+
+    // Stairs lead on through the branch unless the level connects them somewhere else
+    pub fn stairs_destination(&self, idx : usize) -> Option<MapId> {
+        let connection = self.connections.get(&idx).copied();
+        match self.tiles[idx] {
+            TileType::DownStairs => Some(connection.unwrap_or(self.id().below())),
+            TileType::UpStairs => Some(connection.unwrap_or(self.id().above())),
+            _ => None
+        }
+    }
+
+    pub fn connection_to(&self, id : MapId) -> Option<usize> {
+        self.connections.iter().find(|(_, to)| **to == id).map(|(idx, _)| *idx)
+    }
 
     pub fn xy_idx(&self, x: i32, y: i32) -> usize {
         (y as usize * self.width as usize) + x as usize
@@ -84,10 +116,15 @@ impl Map {
             revealed_tiles : vec![false; map_tile_count],
             visible_tiles : vec![false; map_tile_count],
             depth : new_depth,
-            bloodstains : HashSet::new(),
-            view_blocked : HashSet::new(),
+            bloodstains : BTreeSet::new(),
+            view_blocked : BTreeSet::new(),
             name : name.to_string(),
             outdoors : true,
+            theme : Theme::Default,
+            branch : Branch::Main,
+            connections : BTreeMap::new(),
+            tile_damage : BTreeMap::new(),
+            gas : BTreeMap::new(),
             light: vec![rltk::RGB::from_f32(0.0, 0.0, 0.0); map_tile_count]
         }
     }
@@ -138,3 +175,35 @@ impl Algorithm2D for Map {
         Point::new(self.width, self.height)
     }
 }
+
+
+// The map as a careful monster paths over it, never stepping into a hazard or a gas cloud
+pub struct AvoidHazards<'a>(pub &'a Map);
+
+impl AvoidHazards<'_> {
+    fn dangerous(&self, idx : usize) -> bool {
+        tile_hazard(self.0.tiles[idx]) || self.0.gas.contains_key(&idx)
+    }
+}
+
+impl BaseMap for AvoidHazards<'_> {
+    fn is_opaque(&self, idx:usize) -> bool {
+        self.0.is_opaque(idx)
+    }
+
+    fn get_pathing_distance(&self, idx1:usize, idx2:usize) -> f32 {
+        self.0.get_pathing_distance(idx1, idx2)
+    }
+
+    fn get_available_exits(&self, idx:usize) -> rltk::SmallVec<[(usize, f32); 10]> {
+        self.0.get_available_exits(idx).into_iter()
+            .filter(|(exit, _)| !self.dangerous(*exit))
+            .collect()
+    }
+}
+
+impl Algorithm2D for AvoidHazards<'_> {
+    fn dimensions(&self) -> Point {
+        self.0.dimensions()
+    }
+}
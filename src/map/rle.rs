@@ -0,0 +1,157 @@
+use serde::{Serialize, Deserialize, Serializer, Deserializer};
+
+#[derive(Serialize)]
+struct Runs<'a, T> {
+    rle : Vec<(u32, &'a T)>
+}
+
+// Saves older than format 3 store the layers as plain arrays
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Stored<T> {
+    Runs { rle : Vec<(u32, T)> },
+    Plain(Vec<T>)
+}
+
+pub fn serialize<S, T>(values : &[T], serializer : S) -> Result<S::Ok, S::Error>
+where S : Serializer, T : Serialize + PartialEq
+{
+    let mut rle : Vec<(u32, &T)> = Vec::new();
+    for value in values {
+        match rle.last_mut() {
+            Some((count, last)) if *last == value => *count += 1,
+            _ => rle.push((1, value))
+        }
+    }
+    Runs{ rle }.serialize(serializer)
+}
+
+pub fn deserialize<'de, D, T>(deserializer : D) -> Result<Vec<T>, D::Error>
+where D : Deserializer<'de>, T : Deserialize<'de> + Clone
+{
+    Ok(match Stored::<T>::deserialize(deserializer)? {
+        Stored::Runs{ rle } => rle.into_iter()
+            .flat_map(|(count, value)| std::iter::repeat_n(value, count as usize))
+            .collect(),
+        Stored::Plain(values) => values
+    })
+}
+
+// Sets of tile indices, as runs of neighbouring indices
+pub mod indices {
+    use serde::{Serialize, Deserialize, Serializer, Deserializer};
+    use std::collections::BTreeSet;
+
+    #[derive(Serialize)]
+    struct Runs {
+        runs : Vec<(usize, u32)>
+    }
+
+    // Saves older than format 12 store the plain indices
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Stored {
+        Runs { runs : Vec<(usize, u32)> },
+        Plain(Vec<usize>)
+    }
+
+    pub fn serialize<S : Serializer>(indices : &BTreeSet<usize>, serializer : S) -> Result<S::Ok, S::Error> {
+        let mut runs : Vec<(usize, u32)> = Vec::new();
+        for idx in indices.iter() {
+            match runs.last_mut() {
+                Some((start, count)) if *start + *count as usize == *idx => *count += 1,
+                _ => runs.push((*idx, 1))
+            }
+        }
+        Runs{ runs }.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D : Deserializer<'de>>(deserializer : D) -> Result<BTreeSet<usize>, D::Error> {
+        Ok(match Stored::deserialize(deserializer)? {
+            Stored::Runs{ runs } => runs.into_iter()
+                .flat_map(|(start, count)| start .. start + count as usize)
+                .collect(),
+            Stored::Plain(indices) => indices.into_iter().collect()
+        })
+    }
+}
+
+// Points listed row by row, as spans along each row
+pub mod spans {
+    use serde::{Serialize, Deserialize, Serializer, Deserializer};
+    use rltk::Point;
+
+    #[derive(Serialize)]
+    struct Spans {
+        spans : Vec<(i32, i32, u32)>
+    }
+
+    // Saves older than format 12 store every point
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Stored {
+        Spans { spans : Vec<(i32, i32, u32)> },
+        Plain(Vec<Point>)
+    }
+
+    pub fn serialize<S : Serializer>(points : &[Point], serializer : S) -> Result<S::Ok, S::Error> {
+        let mut spans : Vec<(i32, i32, u32)> = Vec::new();
+        for point in points.iter() {
+            match spans.last_mut() {
+                Some((x, y, count)) if *y == point.y && *x + *count as i32 == point.x => *count += 1,
+                _ => spans.push((point.x, point.y, 1))
+            }
+        }
+        Spans{ spans }.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D : Deserializer<'de>>(deserializer : D) -> Result<Vec<Point>, D::Error> {
+        Ok(match Stored::deserialize(deserializer)? {
+            Stored::Spans{ spans } => spans.into_iter()
+                .flat_map(|(x, y, count)| (x .. x + count as i32).map(move |x| Point::new(x, y)))
+                .collect(),
+            Stored::Plain(points) => points
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Serialize, Deserialize};
+    use serde_json::json;
+    use std::collections::BTreeSet;
+    use rltk::Point;
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Layers {
+        #[serde(with = "super")]
+        tiles : Vec<bool>,
+        #[serde(with = "super::indices")]
+        stains : BTreeSet<usize>,
+        #[serde(with = "super::spans")]
+        seen : Vec<Point>
+    }
+
+    #[test]
+    fn layers_shrink_to_runs_and_come_back_the_same() {
+        let layers = Layers{
+            tiles : vec![false, false, false, true, true, false],
+            stains : [3, 4, 5, 9, 40, 41].into_iter().collect(),
+            seen : vec![Point::new(2, 1), Point::new(3, 1), Point::new(4, 1), Point::new(0, 2), Point::new(2, 2)]
+        };
+        let stored = serde_json::to_value(&layers).unwrap();
+        assert_eq!(stored["tiles"], json!({ "rle" : [[3, false], [2, true], [1, false]] }));
+        assert_eq!(stored["stains"], json!({ "runs" : [[3, 3], [9, 1], [40, 2]] }));
+        assert_eq!(stored["seen"], json!({ "spans" : [[2, 1, 3], [0, 2, 1], [2, 2, 1]] }));
+        assert_eq!(serde_json::from_value::<Layers>(stored).unwrap(), layers);
+    }
+
+    #[test]
+    fn plain_layers_from_old_saves_still_load() {
+        let stored = json!({ "tiles" : [true, false], "stains" : [7, 2], "seen" : [{ "x" : 1, "y" : 2 }] });
+        let layers = serde_json::from_value::<Layers>(stored).unwrap();
+        assert_eq!(layers.tiles, vec![true, false]);
+        assert_eq!(layers.stains, [2, 7].into_iter().collect());
+        assert_eq!(layers.seen, vec![Point::new(1, 2)]);
+    }
+}
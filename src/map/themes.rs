@@ -1,11 +1,25 @@
 use super::{Map, TileType};
 use rltk::RGB;
+use serde::{Serialize, Deserialize};
+
+#[derive(PartialEq, Copy, Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Theme {
+    #[default]
+    Default,
+    Forest,
+    Limestone,
+    LimestoneTransition,
+    Mushroom,
+    MushroomEntrance
+}
 
 pub fn tile_glyph(idx: usize, map : &Map) -> (rltk::FontCharType, RGB, RGB) {
-    let (glyph, mut fg, mut bg) = match map.depth {
-        9 => get_mushroom_glyph(idx, map),
-        8 => get_mushroom_glyph(idx, map),
-        7 => {
+    let (glyph, mut fg, mut bg) = match (map.tiles[idx], map.theme) {
+        // Hazards have to look the same whatever the theme, or nobody would know to keep off them
+        (TileType::Lava | TileType::Chasm | TileType::Ice, _) => get_tile_glyph_default(idx, map),
+        (_, Theme::Mushroom) => get_mushroom_glyph(idx, map),
+        (_, Theme::MushroomEntrance) => {
             let x = idx as i32 % map.width;
             if x > map.width-16 {
                 get_tile_glyph_default(idx, map)
@@ -13,7 +27,7 @@ pub fn tile_glyph(idx: usize, map : &Map) -> (rltk::FontCharType, RGB, RGB) {
                 get_mushroom_glyph(idx, map)
             }
         }
-        5 => {
+        (_, Theme::LimestoneTransition) => {
             let x = idx as i32 % map.width;
             if x < map.width/2 {
                 get_limestone_glyph(idx, map)
@@ -21,13 +35,13 @@ pub fn tile_glyph(idx: usize, map : &Map) -> (rltk::FontCharType, RGB, RGB) {
                 get_tile_glyph_default(idx, map)
             }
         }
-        4 => get_limestone_glyph(idx, map),
-        3 => get_limestone_glyph(idx, map),
-        2 => get_forest_glyph(idx, map),
-        _ => get_tile_glyph_default(idx, map)
+        (_, Theme::Limestone) => get_limestone_glyph(idx, map),
+        (_, Theme::Forest) => get_forest_glyph(idx, map),
+        (_, Theme::Default) => get_tile_glyph_default(idx, map)
     };
 
     if map.bloodstains.contains(&idx) { bg = RGB::from_f32(0.75, 0., 0.); }
+    if let Some(strength) = map.gas.get(&idx) { bg = RGB::from_f32(0., 0.1 + 0.05 * i32::min(*strength, 10) as f32, 0.); }
     if !map.visible_tiles[idx] {
         fg = fg.to_greyscale();
         bg = RGB::from_f32(0., 0., 0.);
@@ -42,12 +56,12 @@ pub fn tile_glyph(idx: usize, map : &Map) -> (rltk::FontCharType, RGB, RGB) {
 fn get_tile_glyph_default(idx: usize, map : &Map) -> (rltk::FontCharType, RGB, RGB) {
     let glyph;
     let fg;
-    let bg = RGB::from_f32(0., 0., 0.);
+    let mut bg = RGB::from_f32(0., 0., 0.);
 
     match map.tiles[idx] {
         TileType::Floor => { glyph = rltk::to_cp437('.'); fg = RGB::from_f32(0.0, 0.5, 0.5); }
         TileType::WoodFloor => { glyph = rltk::to_cp437('.'); fg = RGB::named(rltk::CHOCOLATE); }
-        TileType::Wall => {
+        TileType::Wall | TileType::SecretDoor => {
             let x = idx as i32 % map.width;
             let y = idx as i32 / map.width;
             glyph = wall_glyph(&*map, x, y);
@@ -60,9 +74,13 @@ fn get_tile_glyph_default(idx: usize, map : &Map) -> (rltk::FontCharType, RGB, R
         TileType::ShallowWater => { glyph = rltk::to_cp437('≈'); fg = RGB::named(rltk::CYAN); }
         TileType::DeepWater => { glyph = rltk::to_cp437('≈'); fg = RGB::named(rltk::NAVY_BLUE); }
         TileType::Gravel => { glyph = rltk::to_cp437(';'); fg = RGB::named(rltk::GRAY); }
+        TileType::Rubble => { glyph = rltk::to_cp437('%'); fg = RGB::named(rltk::GRAY); }
         TileType::UpStairs => { glyph = rltk::to_cp437('<'); fg = RGB::from_f32(0., 1.0, 1.0); }
         TileType::Stalactite => { glyph = rltk::to_cp437('╨'); fg = RGB::from_f32(0.5, 0.5, 0.5); }
         TileType::Stalagmite => { glyph = rltk::to_cp437('╥'); fg = RGB::from_f32(0.5, 0.5, 0.5); }
+        TileType::Lava => { glyph = rltk::to_cp437('≈'); fg = RGB::named(rltk::YELLOW); bg = RGB::from_f32(0.6, 0.15, 0.); }
+        TileType::Chasm => { glyph = rltk::to_cp437('∙'); fg = RGB::from_f32(0.25, 0.25, 0.3); }
+        TileType::Ice => { glyph = rltk::to_cp437('.'); fg = RGB::named(rltk::WHITE); bg = RGB::from_f32(0.2, 0.35, 0.5); }
     }
 
     (glyph, fg, bg)
@@ -101,7 +119,7 @@ fn wall_glyph(map : &Map, x: i32, y: i32) -> rltk::FontCharType {
 
 fn is_revealed_and_wall(map: &Map, x: i32, y: i32) -> bool {
     let idx = map.xy_idx(x, y);
-    map.tiles[idx] == TileType::Wall && map.revealed_tiles[idx]
+    (map.tiles[idx] == TileType::Wall || map.tiles[idx] == TileType::SecretDoor) && map.revealed_tiles[idx]
 }
 
 fn get_forest_glyph(idx: usize, map: &Map) -> (rltk::FontCharType, RGB, RGB) {
@@ -110,13 +128,14 @@ fn get_forest_glyph(idx: usize, map: &Map) -> (rltk::FontCharType, RGB, RGB) {
     let bg = RGB::from_f32(0., 0., 0.);
 
     match map.tiles[idx] {
-        TileType::Wall => { glyph = rltk::to_cp437('♣'); fg = RGB::from_f32(0.0, 0.6, 0.0); }
+        TileType::Wall | TileType::SecretDoor => { glyph = rltk::to_cp437('♣'); fg = RGB::from_f32(0.0, 0.6, 0.0); }
         TileType::Bridge => { glyph = rltk::to_cp437('.'); fg = RGB::named(rltk::CHOCOLATE); }
         TileType::Road => { glyph = rltk::to_cp437('≡'); fg = RGB::named(rltk::YELLOW); }
         TileType::Grass => { glyph = rltk::to_cp437('"'); fg = RGB::named(rltk::GREEN); }
         TileType::ShallowWater => { glyph = rltk::to_cp437('~'); fg = RGB::named(rltk::CYAN); }
         TileType::DeepWater => { glyph = rltk::to_cp437('~'); fg = RGB::named(rltk::BLUE); }
         TileType::Gravel => { glyph = rltk::to_cp437(';'); fg = RGB::from_f32(0.5, 0.5, 0.5); }
+        TileType::Rubble => { glyph = rltk::to_cp437('%'); fg = RGB::from_f32(0.5, 0.5, 0.5); }
         TileType::DownStairs => { glyph = rltk::to_cp437('>'); fg = RGB::from_f32(0., 1.0, 1.0); }
         TileType::UpStairs => { glyph = rltk::to_cp437('<'); fg = RGB::from_f32(0., 1.0, 1.0); }
         _ => { glyph = rltk::to_cp437('"'); fg = RGB::from_f32(0.0, 0.6, 0.0); }
@@ -131,13 +150,14 @@ fn get_limestone_glyph(idx: usize, map: &Map) -> (rltk::FontCharType, RGB, RGB)
     let bg = RGB::from_f32(0., 0., 0.);
 
     match map.tiles[idx] {
-        TileType::Wall => { glyph = rltk::to_cp437('▒'); fg = RGB::from_f32(0.7, 0.7, 0.7); }
+        TileType::Wall | TileType::SecretDoor => { glyph = rltk::to_cp437('▒'); fg = RGB::from_f32(0.7, 0.7, 0.7); }
         TileType::Bridge => { glyph = rltk::to_cp437('.'); fg = RGB::named(rltk::CHOCOLATE); }
         TileType::Road => { glyph = rltk::to_cp437('≡'); fg = RGB::named(rltk::YELLOW); }
         TileType::Grass => { glyph = rltk::to_cp437('"'); fg = RGB::named(rltk::GREEN); }
         TileType::ShallowWater => { glyph = rltk::to_cp437('▒'); fg = RGB::named(rltk::CYAN); }
         TileType::DeepWater => { glyph = rltk::to_cp437('▓'); fg = RGB::from_f32(0.2, 0.2, 1.0); }
         TileType::Gravel => { glyph = rltk::to_cp437(';'); fg = RGB::from_f32(0.5, 0.5, 0.5); }
+        TileType::Rubble => { glyph = rltk::to_cp437('%'); fg = RGB::from_f32(0.5, 0.5, 0.5); }
         TileType::DownStairs => { glyph = rltk::to_cp437('>'); fg = RGB::from_f32(0., 1.0, 1.0); }
         TileType::UpStairs => { glyph = rltk::to_cp437('<'); fg = RGB::from_f32(0., 1.0, 1.0); }
         TileType::Stalactite => { glyph = rltk::to_cp437('╨'); fg = RGB::from_f32(0.5, 0.5, 0.5); }
@@ -154,13 +174,14 @@ fn get_mushroom_glyph(idx: usize, map: &Map) -> (rltk::FontCharType, RGB, RGB) {
     let bg = RGB::from_f32(0., 0., 0.,);
 
     match map.tiles[idx] {
-        TileType::Wall => { glyph = rltk::to_cp437('♠'); fg = RGB::from_f32(0.77, 0.6, 0.2); }
+        TileType::Wall | TileType::SecretDoor => { glyph = rltk::to_cp437('♠'); fg = RGB::from_f32(0.77, 0.6, 0.2); }
         TileType::Bridge => { glyph = rltk::to_cp437('.'); fg = RGB::named(rltk::GREEN); }
         TileType::Road => { glyph = rltk::to_cp437('≡'); fg = RGB::named(rltk::CHOCOLATE); }
         TileType::Grass => { glyph = rltk::to_cp437('"'); fg = RGB::named(rltk::GREEN); }
         TileType::ShallowWater => { glyph = rltk::to_cp437('~'); fg = RGB::named(rltk::CYAN); }
         TileType::DeepWater => { glyph = rltk::to_cp437('~'); fg = RGB::named(rltk::BLUE); }
         TileType::Gravel => { glyph = rltk::to_cp437(';'); fg = RGB::from_f32(0.5, 0.5, 0.5); }
+        TileType::Rubble => { glyph = rltk::to_cp437('%'); fg = RGB::from_f32(0.5, 0.5, 0.5); }
         TileType::DownStairs => { glyph = rltk::to_cp437('>'); fg = RGB::from_f32(0., 1.0, 1.0); }
         TileType::UpStairs => { glyph = rltk::to_cp437('<'); fg = RGB::from_f32(0., 1.0, 1.0); }
         _ => { glyph = rltk::to_cp437('"'); fg = RGB::from_f32(0.0, 0.6, 0.0); }
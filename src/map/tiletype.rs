@@ -14,22 +14,32 @@ pub enum TileType {
     WoodFloor,
     Bridge,
     Gravel,
-    UpStairs
+    UpStairs,
+    SecretDoor,
+    Rubble,
+    Lava,
+    Chasm,
+    Ice
 }
 
 pub fn tile_walkable(tt : TileType) -> bool {
     match tt {
         TileType::Floor | TileType::DownStairs | TileType::Road | TileType::Grass |
         TileType::ShallowWater | TileType::WoodFloor | TileType::Bridge | TileType::Gravel |
-        TileType::UpStairs
+        TileType::UpStairs | TileType::Rubble | TileType::Lava | TileType::Chasm | TileType::Ice
             => true,
         _ => false
     }
 }
 
+// Monsters know where the secret doors are, only the player has to find them
+pub fn tile_blocks_monsters(tt : TileType) -> bool {
+    !tile_walkable(tt) && tt != TileType::SecretDoor
+}
+
 pub fn tile_opaque(tt : TileType) -> bool {
     match tt {
-        TileType::Wall | TileType::Stalactite | TileType::Stalagmite => true,
+        TileType::Wall | TileType::Stalactite | TileType::Stalagmite | TileType::SecretDoor => true,
         _ => false
     }
 }
@@ -39,6 +49,47 @@ pub fn tile_cost(tt : TileType) -> f32 {
         TileType::Road => 0.8,
         TileType::Grass => 1.1,
         TileType::ShallowWater => 1.2,
+        TileType::Rubble => 1.5,
+        TileType::Ice => 1.2,
         _ => 1.0
     }
-}
\ No newline at end of file
+}
+
+// Tiles that hurt whoever walks onto them, monsters keep off them unless running for their lives
+pub fn tile_hazard(tt : TileType) -> bool {
+    matches!(tt, TileType::Lava | TileType::Chasm)
+}
+
+// How much digging a tile takes before it collapses into rubble, None when it cannot be dug
+pub fn tile_hardness(tt : TileType) -> Option<i32> {
+    match tt {
+        TileType::Wall | TileType::SecretDoor => Some(12),
+        TileType::Stalactite | TileType::Stalagmite => Some(6),
+        _ => None
+    }
+}
+
+// Tile names as data files write them
+pub fn tile_from_key(key : &str) -> Option<TileType> {
+    match key {
+        "wall" => Some(TileType::Wall),
+        "stalactite" => Some(TileType::Stalactite),
+        "stalagmite" => Some(TileType::Stalagmite),
+        "floor" => Some(TileType::Floor),
+        "down_stairs" => Some(TileType::DownStairs),
+        "road" => Some(TileType::Road),
+        "grass" => Some(TileType::Grass),
+        "shallow_water" => Some(TileType::ShallowWater),
+        "deep_water" => Some(TileType::DeepWater),
+        "wood_floor" => Some(TileType::WoodFloor),
+        "bridge" => Some(TileType::Bridge),
+        "gravel" => Some(TileType::Gravel),
+        "up_stairs" => Some(TileType::UpStairs),
+        "secret_door" => Some(TileType::SecretDoor),
+        "rubble" => Some(TileType::Rubble),
+        "lava" => Some(TileType::Lava),
+        "chasm" => Some(TileType::Chasm),
+        "ice" => Some(TileType::Ice),
+        _ => None
+    }
+}
@@ -1,26 +1,4 @@
-use super::{BuilderChain, XStart, YStart, AreaStartingPosition, CullUnreachable, VoronoiSpawning,
-    AreaEndingPosition, XEnd, YEnd, BspInteriorBuilder, InitialMapBuilder, BuilderMap, TileType};
-
-pub fn buhaj_city(new_depth: i32, width: i32, height: i32) -> BuilderChain {
-    println!("Buhaj builder");
-    let mut chain = BuilderChain::new(new_depth, width, height, "Buhajówka");
-    chain.start_with(BspInteriorBuilder::new());
-    chain.with(AreaStartingPosition::new(XStart::CENTER, YStart::CENTER));
-    chain.with(CullUnreachable::new());
-    chain.with(AreaStartingPosition::new(XStart::RIGHT, YStart::CENTER));
-    chain.with(AreaEndingPosition::new(XEnd::LEFT, YEnd::CENTER));
-    chain.with(VoronoiSpawning::new());
-    chain
-}
-
-pub fn buhaj_plaza(new_depth: i32, width: i32, height: i32) -> BuilderChain {
-    println!("Buhaj plaza builder");
-    let mut chain = BuilderChain::new(new_depth, width, height, "Buhajówka - Rynek");
-    chain.start_with(PlazaMapBuilder::new());
-    chain.with(AreaStartingPosition::new(XStart::LEFT, YStart::CENTER));
-    chain.with(CullUnreachable::new());
-    chain
-}
+use super::{InitialMapBuilder, BuilderMap, TileType};
 
 pub struct PlazaMapBuilder {}
 
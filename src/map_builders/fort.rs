@@ -1,22 +1,4 @@
-use super::{BuilderChain, XStart, YStart, AreaStartingPosition, RoomSorter, RoomSort, CullUnreachable, VoronoiSpawning, TileType, dla::DLABuilder,
-            BspDungeonBuilder, DistantExit, BspCorridors, CorridorSpawner, RoomDrawer, AreaEndingPosition, XEnd, YEnd, MetaMapBuilder, BuilderMap};
-
-pub fn fort_builder(new_depth: i32, width: i32, height: i32) -> BuilderChain {
-    let mut chain = BuilderChain::new(new_depth, width, height, "Fort Biskupi");
-    chain.start_with(BspDungeonBuilder::new());
-    chain.with(RoomSorter::new(RoomSort::CENTRAL));
-    chain.with(RoomDrawer::new());
-    chain.with(BspCorridors::new());
-    chain.with(CorridorSpawner::new());
-    chain.with(DragonsLair::new());
-    chain.with(AreaStartingPosition::new(XStart::LEFT, YStart::TOP));
-    chain.with(CullUnreachable::new());
-    chain.with(AreaEndingPosition::new(XEnd::RIGHT, YEnd::BOTTOM));
-    chain.with(VoronoiSpawning::new());
-    chain.with(DistantExit::new());
-    chain.with(DragonSpawner::new());
-    chain
-}
+use super::{BuilderChain, TileType, dla::DLABuilder, MetaMapBuilder, BuilderMap};
 
 pub struct DragonsLair {}
 
@@ -33,10 +15,9 @@ impl DragonsLair {
     }
 
     fn build(&mut self, build_data: &mut BuilderMap) {
-        build_data.map.depth = 6;
         build_data.take_snapshot();
 
-        let mut builder = BuilderChain::new(6, build_data.width, build_data.height, "New Map");
+        let mut builder = BuilderChain::new(build_data.map.depth, build_data.width, build_data.height, "New Map");
         builder.start_with(DLABuilder::insectoid());
         builder.build_map();
 
@@ -0,0 +1,119 @@
+use super::{MetaMapBuilder, BuilderMap, Map, TileType, has_deeper_level};
+use crate::map::{tile_walkable, tile_hazard};
+use std::collections::VecDeque;
+
+const SAFE_DISTANCE : f32 = 5.0;
+
+pub struct HazardPlacement {
+    tile : TileType,
+    count : i32
+}
+
+impl MetaMapBuilder for HazardPlacement {
+    fn build_map(&mut self, build_data : &mut BuilderMap) {
+        self.build(build_data);
+    }
+}
+
+impl HazardPlacement {
+    #[allow(dead_code)]
+    pub fn new(tile : TileType, count : i32) -> Box<HazardPlacement> {
+        Box::new(HazardPlacement{ tile, count })
+    }
+
+    fn build(&mut self, build_data : &mut BuilderMap) {
+        // A chasm has to lead somewhere
+        if self.tile == TileType::Chasm && !has_deeper_level(build_data.map.id()) { return; }
+        let start = match &build_data.starting_position {
+            None => return,
+            Some(start) => build_data.map.xy_idx(start.x, start.y)
+        };
+        let mut keep_clear : Vec<rltk::Point> = build_data.map.tiles.iter().enumerate()
+            .filter(|(_, tile)| matches!(tile, TileType::DownStairs | TileType::UpStairs))
+            .map(|(idx, _)| idx_point(&build_data.map, idx))
+            .collect();
+        keep_clear.push(idx_point(&build_data.map, start));
+        let reachable_before = safely_reachable(&build_data.map, start);
+
+        for _ in 0 .. self.count {
+            let centre = match pick_centre(&build_data.map, &keep_clear) {
+                None => break,
+                Some(centre) => centre
+            };
+            let painted = paint_blob(&mut build_data.map, centre, self.tile, &keep_clear);
+
+            // Nothing the player could walk to before may end up behind the hazard
+            let reachable = safely_reachable(&build_data.map, start);
+            let cut_off = reachable_before.iter().enumerate()
+                .any(|(idx, before)| *before && !reachable[idx] && !tile_hazard(build_data.map.tiles[idx]));
+            if cut_off {
+                for (idx, tile) in painted {
+                    build_data.map.tiles[idx] = tile;
+                }
+            }
+        }
+
+        let map = &build_data.map;
+        build_data.spawn_list.retain(|spawn| !tile_hazard(map.tiles[spawn.0]));
+        build_data.take_snapshot();
+    }
+}
+
+fn idx_point(map : &Map, idx : usize) -> rltk::Point {
+    rltk::Point::new(idx as i32 % map.width, idx as i32 / map.width)
+}
+
+fn far_enough(point : rltk::Point, keep_clear : &[rltk::Point]) -> bool {
+    keep_clear.iter().all(|clear| rltk::DistanceAlg::Pythagoras.distance2d(point, *clear) > SAFE_DISTANCE)
+}
+
+fn pick_centre(map : &Map, keep_clear : &[rltk::Point]) -> Option<rltk::Point> {
+    for _ in 0 .. 50 {
+        let point = rltk::Point::new(crate::rng::roll_dice(1, map.width-2), crate::rng::roll_dice(1, map.height-2));
+        let idx = map.xy_idx(point.x, point.y);
+        if map.tiles[idx] == TileType::Floor && far_enough(point, keep_clear) {
+            return Some(point);
+        }
+    }
+    None
+}
+
+// A ragged patch around the centre, returning what each tile was so it can be put back
+fn paint_blob(map : &mut Map, centre : rltk::Point, tile : TileType, keep_clear : &[rltk::Point]) -> Vec<(usize, TileType)> {
+    let radius = crate::rng::roll_dice(1, 3) + 1;
+    let mut painted = Vec::new();
+    for y in centre.y - radius ..= centre.y + radius {
+        for x in centre.x - radius ..= centre.x + radius {
+            if x < 1 || x > map.width-2 || y < 1 || y > map.height-2 { continue; }
+            let point = rltk::Point::new(x, y);
+            let distance = rltk::DistanceAlg::Pythagoras.distance2d(point, centre);
+            if distance > radius as f32 || (distance > 1.0 && crate::rng::roll_dice(1, 3) == 1) { continue; }
+            let idx = map.xy_idx(x, y);
+            if !matches!(map.tiles[idx], TileType::Floor | TileType::Gravel | TileType::Grass) || !far_enough(point, keep_clear) { continue; }
+            painted.push((idx, map.tiles[idx]));
+            map.tiles[idx] = tile;
+        }
+    }
+    painted
+}
+
+fn safely_reachable(map : &Map, start : usize) -> Vec<bool> {
+    let mut reached = vec![false; map.tiles.len()];
+    reached[start] = true;
+    let mut open = VecDeque::new();
+    open.push_back(start);
+    while let Some(idx) = open.pop_front() {
+        let x = idx as i32 % map.width;
+        let y = idx as i32 / map.width;
+        for (dx, dy) in [(-1, 0), (1, 0), (0, -1), (0, 1), (-1, -1), (1, -1), (-1, 1), (1, 1)] {
+            let (nx, ny) = (x + dx, y + dy);
+            if nx < 1 || nx > map.width-2 || ny < 1 || ny > map.height-2 { continue; }
+            let next = map.xy_idx(nx, ny);
+            if !reached[next] && tile_walkable(map.tiles[next]) && !tile_hazard(map.tiles[next]) {
+                reached[next] = true;
+                open.push_back(next);
+            }
+        }
+    }
+    reached
+}
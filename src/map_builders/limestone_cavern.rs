@@ -1,44 +1,5 @@
-use super::{BuilderChain, DrunkardsWalkBuilder, XStart, YStart, AreaStartingPosition, DLABuilder, CellularAutomataBuilder,
-    CullUnreachable, VoronoiSpawning, MetaMapBuilder, BuilderMap, TileType, DistantExit, PrefabBuilder, AreaEndingPosition, XEnd, YEnd,
-    BspDungeonBuilder, RoomBasedSpawner, RoomDrawer, RoomSorter, RoomSort, NearestCorridors, RoomExploder};
-
-pub fn limestone_cavern_builder(new_depth: i32, width: i32, height: i32) -> BuilderChain {
-    let mut chain = BuilderChain::new(new_depth, width, height, "Groty Zegdorskie");
-    chain.start_with(DrunkardsWalkBuilder::winding_passages());
-    chain.with(AreaStartingPosition::new(XStart::CENTER, YStart::CENTER));
-    chain.with(CullUnreachable::new());
-    chain.with(AreaStartingPosition::new(XStart::LEFT, YStart::CENTER));
-    chain.with(VoronoiSpawning::new());
-    chain.with(DistantExit::new());
-    chain.with(CaveDecorator::new());
-    chain
-}
-
-pub fn limestone_deep_cavern_builder(new_depth: i32,  width: i32, height: i32) -> BuilderChain {
-    let mut chain = BuilderChain::new(new_depth, width, height, "Zegdorskie Glebiny");
-    chain.start_with(DLABuilder::central_attractor());
-    chain.with(AreaStartingPosition::new(XStart::LEFT, YStart::TOP));
-    chain.with(VoronoiSpawning::new());
-    chain.with(DistantExit::new());
-    chain.with(CaveDecorator::new());
-    chain.with(PrefabBuilder::sectional(super::prefab_builder::prefab_sections::GNOM_CAMP));
-    chain
-}
-
-pub fn limestone_transition_builder(new_depth: i32, width: i32, height: i32) -> BuilderChain {
-    let mut chain = BuilderChain::new(new_depth, width, height, "Fort Biskupi - górny poziom");
-    chain.start_with(CellularAutomataBuilder::new());
-    chain.with(AreaStartingPosition::new(XStart::CENTER, YStart::CENTER));
-    chain.with(CullUnreachable::new());
-    chain.with(AreaStartingPosition::new(XStart::LEFT, YStart::CENTER));
-    chain.with(VoronoiSpawning::new());
-    chain.with(CaveDecorator::new());
-    chain.with(CaveTransition::new());
-    chain.with(AreaStartingPosition::new(XStart::LEFT, YStart::CENTER));
-    chain.with(CullUnreachable::new());
-    chain.with(AreaEndingPosition::new(XEnd::RIGHT, YEnd::CENTER));
-    chain
-}
+use super::{BuilderChain, MetaMapBuilder, BuilderMap, TileType, BspDungeonBuilder, RoomBasedSpawner, RoomDrawer, RoomSorter, RoomSort,
+    NearestCorridors, RoomExploder};
 
 pub struct CaveDecorator {}
 
@@ -101,10 +62,9 @@ impl CaveTransition {
     }
 
     fn build(&mut self, build_data: &mut BuilderMap) {
-        build_data.map.depth = 5;
         build_data.take_snapshot();
 
-        let mut builder = BuilderChain::new(5, build_data.width, build_data.height, "Nowa mapa");
+        let mut builder = BuilderChain::new(build_data.map.depth, build_data.width, build_data.height, "Nowa mapa");
         builder.start_with(BspDungeonBuilder::new());
         builder.with(RoomDrawer::new());
         builder.with(RoomSorter::new(RoomSort::RIGHTMOST));
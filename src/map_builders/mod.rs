@@ -1,4 +1,4 @@
-use super::{Map, Rect, TileType, Position, spawner, SHOW_MAPGEN_VISUALIZER};
+use super::{Map, MapId, Branch, Rect, TileType, Position, spawner, SHOW_MAPGEN_VISUALIZER};
 mod simple_map;
 use simple_map::SimpleMapBuilder;
 mod bsp_dungeon;
@@ -16,9 +16,10 @@ use maze::MazeBuilder;
 mod voronoi;
 use voronoi::VoronoiCellBuilder;
 mod waveform_collapse;
-use waveform_collapse::WaveformCollapseBuilder;
+use waveform_collapse::{WaveformCollapseBuilder, WfcSample, ChunkSymmetry};
 mod prefab_builder;
 use prefab_builder::PrefabBuilder;
+pub use prefab_builder::prefab_tile;
 mod room_based_spawner;
 use room_based_spawner::RoomBasedSpawner;
 mod room_based_starting_position;
@@ -55,21 +56,33 @@ mod room_corridor_spawner;
 use room_corridor_spawner::CorridorSpawner;
 mod door_placement;
 use door_placement::DoorPlacement;
+mod secret_doors;
+use secret_doors::{SecretDoors, secret_area};
+mod hazards;
+use hazards::HazardPlacement;
 mod common;
 use common::*;
 mod town;
-use town::town_builder;
+use town::TownBuilder;
 mod forest;
-use forest::forest_builder;
+use forest::YellowBrickRoad;
 mod limestone_cavern;
 use limestone_cavern::*;
 mod fort;
-use fort::fort_builder;
-mod mushroom_forest;
-use mushroom_forest::*;
+use fort::{DragonsLair, DragonSpawner};
 mod buhaje;
 use buhaje::*;
+mod rivers;
+use rivers::RiverBuilder;
+mod overworld;
+use overworld::{OverworldBuilder, ConnectExits};
+mod registry;
+pub use registry::*;
+mod quality;
+pub use quality::*;
 use specs::prelude::*;
+use std::sync::atomic::{AtomicBool, Ordering};
+use crate::raws::{RAWS, LevelDefinition};
 
 pub struct BuilderMap {
     pub spawn_list : Vec<(usize, String)>,
@@ -82,9 +95,15 @@ pub struct BuilderMap {
     pub height: i32
 }
 
+static RECORD_HISTORY : AtomicBool = AtomicBool::new(SHOW_MAPGEN_VISUALIZER);
+
+pub fn set_record_history(record : bool) {
+    RECORD_HISTORY.store(record, Ordering::Relaxed);
+}
+
 impl BuilderMap {
     fn take_snapshot(&mut self) {
-        if SHOW_MAPGEN_VISUALIZER {
+        if RECORD_HISTORY.load(Ordering::Relaxed) {
             let mut snapshot = self.map.clone();
             for v in snapshot.revealed_tiles.iter_mut() {
                 *v = true;
@@ -142,6 +161,22 @@ impl BuilderChain {
         }
     }
 
+    // Below the surface the player arrives by the up stairs at the starting position
+    pub fn build_level(&mut self) {
+        self.build_map();
+        let depth = self.build_data.map.depth;
+        if depth > 1 {
+            if let Some(pos) = &self.build_data.starting_position {
+                let up_idx = self.build_data.map.xy_idx(pos.x, pos.y);
+                self.build_data.map.tiles[up_idx] = TileType::UpStairs;
+            }
+        }
+    }
+
+    pub fn starter_name(&self) -> &'static str {
+        self.starter.as_ref().map(|starter| starter.name()).unwrap_or("-")
+    }
+
     pub fn spawn_entities(&mut self, ecs : &mut World) {
         for entity in self.build_data.spawn_list.iter() {
             spawner::spawn_entity(ecs, &(&entity.0, &entity.1));
@@ -151,6 +186,11 @@ impl BuilderChain {
 
 pub trait InitialMapBuilder {
     fn build_map(&mut self, build_data : &mut BuilderMap);
+
+    fn name(&self) -> &'static str {
+        let path = std::any::type_name::<Self>();
+        path.rsplit("::").next().unwrap_or(path)
+    }
 }
 
 pub trait MetaMapBuilder {
@@ -256,7 +296,7 @@ fn random_shape_builder(builder : &mut BuilderChain) {
         11 => builder.start_with(DLABuilder::insectoid()),
         12 => builder.start_with(VoronoiCellBuilder::pythagoras()),
         13 => builder.start_with(VoronoiCellBuilder::manhattan()),
-        _ => builder.start_with(PrefabBuilder::constant(prefab_builder::prefab_levels::WFC_POPULATED)),
+        _ => builder.start_with(PrefabBuilder::constant("wfc_populated")),
     }
     
     builder.with(AreaStartingPosition::new(XStart::CENTER, YStart::CENTER));
@@ -287,30 +327,76 @@ pub fn random_builder(new_depth : i32,width: i32, height: i32) -> BuilderChain {
         builder.with(DistantExit::new());
     }
 
+    match crate::rng::roll_dice(1, 6) {
+        1 => builder.with(RiverBuilder::river()),
+        2 => builder.with(RiverBuilder::lakes()),
+        _ => {}
+    }
+
     if crate::rng::roll_dice(1, 20)==1 {
-        builder.with(PrefabBuilder::sectional(prefab_builder::prefab_sections::UNDERGROUND_FORT));
+        builder.with(PrefabBuilder::sectional("underground_fort"));
+    }
+
+    match crate::rng::roll_dice(1, 8) {
+        1 => builder.with(HazardPlacement::new(TileType::Lava, 3)),
+        2 => builder.with(HazardPlacement::new(TileType::Chasm, 2)),
+        3 => builder.with(HazardPlacement::new(TileType::Ice, 4)),
+        _ => {}
     }
 
+    builder.with(SecretDoors::new(25));
     builder.with(DoorPlacement::new());
     builder.with(PrefabBuilder::vaults());
 
     builder
 }
 
-pub fn level_builder(new_depth : i32, width: i32, height: i32) -> BuilderChain {
-    rltk::console::log(format!("Pietro: {}", new_depth));
-    match new_depth {
-        1 => town_builder(new_depth, width, height),
-        2 => forest_builder(new_depth, width, height),
-        3 => limestone_cavern_builder(new_depth, width, height),
-        4 => limestone_deep_cavern_builder(new_depth, width, height),
-        5 => limestone_transition_builder(new_depth, width, height),
-        6 => fort_builder(new_depth, width, height),
-        7 => mushroom_entrance(new_depth, width, height),
-        8 => mushroom_builder(new_depth, width, height),
-        9 => mushroom_exit(new_depth, width, height),
-        10 => buhaj_city(new_depth, width, height),
-        11 => buhaj_plaza(new_depth, width, height),
-        _ => random_builder(new_depth, width, height)
+fn level_chain(level : &LevelDefinition, id : MapId, width: i32, height: i32) -> Result<BuilderChain, String> {
+    let mut chain = BuilderChain::new(id.depth, width, height, &level.map_name);
+    chain.build_data.map.theme = level.theme;
+    chain.build_data.map.branch = id.branch;
+    chain.start_with(initial_builder(&level.initial)?);
+    for step in level.meta.iter() {
+        chain.with(meta_builder(step)?);
     }
-}
\ No newline at end of file
+    Ok(chain)
+}
+
+pub fn level_builder(id : MapId, width: i32, height: i32) -> BuilderChain {
+    rltk::console::log(format!("Pietro: {}", id));
+    let level = RAWS.lock().unwrap().level_for(id).cloned();
+    let mut builder = match level {
+        None => random_builder(id.depth, width, height),
+        Some(level) => match level_chain(&level, id, width, height) {
+            Ok(chain) => chain,
+            Err(e) => {
+                rltk::console::log(format!("WARNING - level {}: {}", level.name, e));
+                random_builder(id.depth, width, height)
+            }
+        }
+    };
+    builder.build_data.map.branch = id.branch;
+    builder
+}
+
+// The main branch keeps the level seeds it had before there were other branches
+pub fn map_seed(run_seed : u64, id : MapId) -> u64 {
+    let seed = crate::rng::level_seed(run_seed, id.depth);
+    match id.branch {
+        Branch::Main => seed,
+        branch => crate::rng::level_seed(seed, branch as i32)
+    }
+}
+
+// The main branch goes on forever, the others end at their deepest level definition
+pub fn has_deeper_level(id : MapId) -> bool {
+    id.branch == Branch::Main || RAWS.lock().unwrap().level_for(id.below()).is_some()
+}
+
+// Builds the level exactly as the game does when the player first enters it
+pub fn generate_level(run_seed : u64, id : MapId, width: i32, height: i32) -> BuilderChain {
+    crate::rng::reseed(map_seed(run_seed, id));
+    let mut builder = level_builder(id, width, height);
+    builder.build_level();
+    builder
+}
@@ -0,0 +1,117 @@
+use super::{InitialMapBuilder, MetaMapBuilder, BuilderMap, TileType, Position, MapId};
+
+pub struct OverworldBuilder {
+    sites : Vec<MapId>
+}
+
+impl InitialMapBuilder for OverworldBuilder {
+    fn build_map(&mut self, build_data : &mut BuilderMap) {
+        self.build(build_data);
+    }
+}
+
+impl OverworldBuilder {
+    #[allow(dead_code)]
+    pub fn new(sites : Vec<MapId>) -> Box<OverworldBuilder> {
+        Box::new(OverworldBuilder{ sites })
+    }
+
+    fn build(&mut self, build_data : &mut BuilderMap) {
+        let (width, height) = (build_data.map.width, build_data.map.height);
+        for y in 0 .. height {
+            for x in 0 .. width {
+                let idx = build_data.map.xy_idx(x, y);
+                let border = x == 0 || y == 0 || x == width-1 || y == height-1;
+                build_data.map.tiles[idx] = if border { TileType::Wall } else { TileType::Grass };
+            }
+        }
+        self.woods(build_data);
+        build_data.take_snapshot();
+
+        // The sites stand in a row across the map, each on its own patch of cleared ground
+        let mut places = Vec::new();
+        for (i, site) in self.sites.iter().enumerate() {
+            let x = (i as i32 + 1) * width / (self.sites.len() as i32 + 1);
+            let y = crate::rng::roll_dice(1, height - 14) + 6;
+            for cy in y-2 ..= y+2 {
+                for cx in x-2 ..= x+2 {
+                    let idx = build_data.map.xy_idx(cx, cy);
+                    build_data.map.tiles[idx] = TileType::Grass;
+                }
+            }
+            let idx = build_data.map.xy_idx(x, y);
+            build_data.map.connections.insert(idx, *site);
+            places.push((x, y));
+        }
+
+        for pair in places.windows(2) {
+            self.road(build_data, pair[0], pair[1]);
+        }
+        for (x, y) in places.iter() {
+            let idx = build_data.map.xy_idx(*x, *y);
+            build_data.map.tiles[idx] = TileType::DownStairs;
+        }
+        build_data.take_snapshot();
+
+        let (start_x, start_y) = places.first().copied().unwrap_or((width / 2, height / 2));
+        build_data.starting_position = Some(Position{ x: start_x + 1, y: start_y });
+        for t in build_data.map.visible_tiles.iter_mut() {
+            *t = true;
+        }
+    }
+
+    fn woods(&mut self, build_data : &mut BuilderMap) {
+        let (width, height) = (build_data.map.width, build_data.map.height);
+        for _ in 0 .. width * height / 40 {
+            let cx = crate::rng::roll_dice(1, width - 2);
+            let cy = crate::rng::roll_dice(1, height - 2);
+            let radius = crate::rng::roll_dice(1, 3) - 1;
+            for y in i32::max(1, cy - radius) ..= i32::min(height - 2, cy + radius) {
+                for x in i32::max(1, cx - radius) ..= i32::min(width - 2, cx + radius) {
+                    if (x - cx).abs() + (y - cy).abs() <= radius {
+                        let idx = build_data.map.xy_idx(x, y);
+                        build_data.map.tiles[idx] = TileType::Wall;
+                    }
+                }
+            }
+        }
+    }
+
+    fn road(&mut self, build_data : &mut BuilderMap, from : (i32, i32), to : (i32, i32)) {
+        let mid_x = (from.0 + to.0) / 2;
+        let mut path = Vec::new();
+        for x in i32::min(from.0, mid_x) ..= i32::max(from.0, mid_x) { path.push((x, from.1)); }
+        for y in i32::min(from.1, to.1) ..= i32::max(from.1, to.1) { path.push((mid_x, y)); }
+        for x in i32::min(mid_x, to.0) ..= i32::max(mid_x, to.0) { path.push((x, to.1)); }
+        for (x, y) in path {
+            let idx = build_data.map.xy_idx(x, y);
+            build_data.map.tiles[idx] = TileType::Road;
+        }
+    }
+}
+
+pub struct ConnectExits {
+    to : MapId
+}
+
+impl MetaMapBuilder for ConnectExits {
+    fn build_map(&mut self, build_data : &mut BuilderMap) {
+        self.build(build_data);
+    }
+}
+
+impl ConnectExits {
+    #[allow(dead_code)]
+    pub fn new(to : MapId) -> Box<ConnectExits> {
+        Box::new(ConnectExits{ to })
+    }
+
+    // Sends every down stairs on the level to another map instead of the next level of the branch
+    fn build(&mut self, build_data : &mut BuilderMap) {
+        for (idx, tile) in build_data.map.tiles.iter().enumerate() {
+            if *tile == TileType::DownStairs {
+                build_data.map.connections.insert(idx, self.to);
+            }
+        }
+    }
+}
@@ -1,15 +1,14 @@
-use super::{InitialMapBuilder, MetaMapBuilder, BuilderMap, TileType, Position};
-pub mod prefab_levels;
-pub mod prefab_sections;
-pub mod prefab_rooms;
-use std::collections::HashSet;
+use super::{InitialMapBuilder, MetaMapBuilder, BuilderMap, TileType, Position, secret_area};
+use crate::map::tile_from_key;
+use crate::raws::{RAWS, PrefabDefinition, PrefabKind, LegendEntry, get_spawn_table_for_level, get_named_spawn_table};
+use std::collections::{HashMap, HashSet};
 
 #[derive(PartialEq, Clone)]
 #[allow(dead_code)]
 pub enum PrefabMode {
     RexLevel{ template : &'static str },
-    Constant{ level : prefab_levels::PrefabLevel },
-    Sectional{ section : prefab_sections::PrefabSection },
+    Constant{ level : String },
+    Sectional{ section : String },
     RoomVaults
 }
 
@@ -31,6 +30,85 @@ impl InitialMapBuilder for PrefabBuilder {
     }
 }
 
+// Characters every prefab understands without a legend entry
+fn default_tile(ch : char) -> Option<TileType> {
+    match ch {
+        ' ' | '☺' => Some(TileType::Floor),
+        '#' => Some(TileType::Wall),
+        '>' => Some(TileType::DownStairs),
+        '≈' => Some(TileType::DeepWater),
+        _ => None
+    }
+}
+
+// The tile a template character stands for, None when the prefab does not say
+pub fn prefab_tile(ch : char, legend : &HashMap<String, LegendEntry>) -> Option<TileType> {
+    match legend.get(&ch.to_string()) {
+        Some(entry) => match &entry.tile {
+            None => Some(TileType::Floor),
+            Some(key) => tile_from_key(key)
+        },
+        None => default_tile(ch)
+    }
+}
+
+// A prefab's characters laid out the way it is about to be placed
+pub struct Stamp {
+    pub glyphs : Vec<char>,
+    pub width : usize,
+    pub height : usize
+}
+
+impl Stamp {
+    pub fn new(prefab : &PrefabDefinition) -> Stamp {
+        let width = prefab.width();
+        let mut glyphs = Vec::new();
+        for row in prefab.template.iter() {
+            let mut chars : Vec<char> = row.chars().collect();
+            chars.resize(width, ' ');
+            glyphs.extend(chars);
+        }
+        Stamp{ glyphs, width, height: prefab.height() }
+    }
+
+    // Turned and flipped as far as the prefab allows, rotating only when the result still fits
+    fn oriented(prefab : &PrefabDefinition, max_width : usize, max_height : usize) -> Stamp {
+        let mut stamp = Stamp::new(prefab);
+        if prefab.rotate {
+            for _ in 0 .. crate::rng::roll_dice(1, 4) - 1 {
+                let turned = stamp.rotated();
+                if turned.width <= max_width && turned.height <= max_height {
+                    stamp = turned;
+                }
+            }
+        }
+        if prefab.mirror && crate::rng::roll_dice(1, 2) == 1 {
+            stamp = stamp.mirrored();
+        }
+        stamp
+    }
+
+    fn rotated(&self) -> Stamp {
+        let mut glyphs = Vec::with_capacity(self.glyphs.len());
+        for y in 0 .. self.width {
+            for x in 0 .. self.height {
+                glyphs.push(self.glyphs[(self.height - 1 - x) * self.width + y]);
+            }
+        }
+        Stamp{ glyphs, width: self.height, height: self.width }
+    }
+
+    fn mirrored(&self) -> Stamp {
+        let mut glyphs = Vec::with_capacity(self.glyphs.len());
+        for y in 0 .. self.height {
+            for x in 0 .. self.width {
+                glyphs.push(self.glyphs[y * self.width + self.width - 1 - x]);
+            }
+        }
+        Stamp{ glyphs, width: self.width, height: self.height }
+    }
+}
+
 impl PrefabBuilder {
     #[allow(dead_code)]
     pub fn new() -> Box<PrefabBuilder> {
@@ -47,16 +125,16 @@ impl PrefabBuilder {
     }
 
     #[allow(dead_code)]
-    pub fn constant(level: prefab_levels::PrefabLevel) -> Box<PrefabBuilder> {
+    pub fn constant<S : ToString>(level : S) -> Box<PrefabBuilder> {
         Box::new(PrefabBuilder{
-            mode : PrefabMode::Constant { level },
+            mode : PrefabMode::Constant { level : level.to_string() },
         })
     }
 
     #[allow(dead_code)]
-    pub fn sectional(section : prefab_sections::PrefabSection) -> Box<PrefabBuilder> {
+    pub fn sectional<S : ToString>(section : S) -> Box<PrefabBuilder> {
         Box::new(PrefabBuilder{
-            mode : PrefabMode::Sectional { section },
+            mode : PrefabMode::Sectional { section : section.to_string() },
         })
     }
 
@@ -68,65 +146,33 @@ impl PrefabBuilder {
     }
 
     fn build(&mut self, build_data : &mut BuilderMap) {
-        match self.mode {
-            PrefabMode::RexLevel{template} => self.load_rex_map(&template, build_data),
-            PrefabMode::Constant{level} => self.load_ascii_map(&level, build_data),
-            PrefabMode::Sectional{section} => self.apply_sectional(&section, build_data),
+        match self.mode.clone() {
+            PrefabMode::RexLevel{template} => self.load_rex_map(template, build_data),
+            PrefabMode::Constant{level} => {
+                if let Some(prefab) = find_prefab(&level) { self.load_ascii_map(&prefab, build_data); }
+            }
+            PrefabMode::Sectional{section} => {
+                if let Some(prefab) = find_prefab(&section) { self.apply_sectional(&prefab, build_data); }
+            }
             PrefabMode::RoomVaults => self.apply_room_vaults(build_data)
         }
         build_data.take_snapshot();
     }
 
-    fn char_to_map(&mut self, ch : char, idx: usize, build_data : &mut BuilderMap) {
-        match ch {
-            ' ' => build_data.map.tiles[idx] = TileType::Floor,
-            '#' => build_data.map.tiles[idx] = TileType::Wall,
-            '☺' => {
-                let x = idx as i32 % build_data.map.width;
-                let y = idx as i32 / build_data.map.width;
-                build_data.map.tiles[idx] = TileType::Floor;
-                build_data.starting_position = Some(Position{ x:x as i32, y:y as i32 });
-            }
-            '>' => build_data.map.tiles[idx] = TileType::DownStairs,
-            'g' => { 
-                build_data.map.tiles[idx] = TileType::Floor;
-                build_data.spawn_list.push((idx, "Gnomon".to_string()));
-            }
-            'G' => {
-                build_data.map.tiles[idx] = TileType::Floor;
-                build_data.spawn_list.push((idx, "Golem Zoledny".to_string()));
-            }
-            'D' => {
-                build_data.map.tiles[idx] = TileType::Floor;
-                build_data.spawn_list.push((idx, "Diakon tarnowski".to_string()));
-            }
-            'B' => {
-                build_data.map.tiles[idx] = TileType::Floor;
-                build_data.spawn_list.push((idx, "Buhaj".to_string()));
-            }
-            '^' => {
-                build_data.map.tiles[idx] = TileType::Floor;
-                build_data.spawn_list.push((idx, "Stupkogryzarka".to_string()));
-            }
-            '%' => {
-                build_data.map.tiles[idx] = TileType::Floor;
-                build_data.spawn_list.push((idx, "Surówka Grzeskowiak".to_string()));
-            }
-            '∞' => {
-                build_data.map.tiles[idx] = TileType::Floor;
-                build_data.spawn_list.push((idx, "Pasztecik".to_string()));
-            }
-            '≈' => build_data.map.tiles[idx] = TileType::DeepWater,
-            'A' => {
-                build_data.map.tiles[idx] = TileType::Floor;
-                build_data.spawn_list.push((idx, "Arcygnomon".to_string()));
-            }
-            '☼' => {
-                build_data.map.tiles[idx] = TileType::Floor;
-                build_data.spawn_list.push((idx, "Ognisko".to_string()));
-            }
-            _ => {
-                rltk::console::log(format!("Unknown glyph loading map: {}", (ch as u8) as char));
+    fn char_to_map(&mut self, ch : char, idx: usize, legend : &HashMap<String, LegendEntry>, build_data : &mut BuilderMap) {
+        let Some(tile) = prefab_tile(ch, legend) else {
+            rltk::console::log(format!("Unknown glyph loading map: {}", ch));
+            return;
+        };
+        build_data.map.tiles[idx] = tile;
+        if ch == '☺' && !legend.contains_key(&ch.to_string()) {
+            let x = idx as i32 % build_data.map.width;
+            let y = idx as i32 / build_data.map.width;
+            build_data.starting_position = Some(Position{ x, y });
+        }
+        if let Some(entry) = legend.get(&ch.to_string()) {
+            if let Some(name) = legend_spawn(entry, build_data) {
+                build_data.spawn_list.push((idx, name));
             }
         }
     }
@@ -134,6 +180,7 @@ impl PrefabBuilder {
     #[allow(dead_code)]
     fn load_rex_map(&mut self, path: &str, build_data : &mut BuilderMap) {
         let xp_file = rltk::rex::XpFile::from_resource(path).unwrap();
+        let legend = HashMap::new();
 
         for layer in &xp_file.layers {
             for y in 0..layer.height {
@@ -141,37 +188,29 @@ impl PrefabBuilder {
                     let cell = layer.get(x, y).unwrap();
                     if x < build_data.map.width as usize && y < build_data.map.height as usize {
                         let idx = build_data.map.xy_idx(x as i32, y as i32);
-                        self.char_to_map(cell.ch as u8 as char, idx, build_data);
+                        self.char_to_map(rltk::to_char(cell.ch as u8), idx, &legend, build_data);
                     }
                 }
             }
         }
     }
 
-    fn read_ascii_to_vec(template : &str) -> Vec<char> {
-        let mut string_vec : Vec<char> = template.chars().filter(|a| *a != '\r' && *a != '\n').collect();
-        for c in string_vec.iter_mut() { if *c as u8 == 160u8 { *c = ' '; } }
-        string_vec
-    }
-
     #[allow(dead_code)]
-    fn load_ascii_map(&mut self, level: &prefab_levels::PrefabLevel, build_data : &mut BuilderMap) {
-        let string_vec = PrefabBuilder::read_ascii_to_vec(level.template);
+    fn load_ascii_map(&mut self, level: &PrefabDefinition, build_data : &mut BuilderMap) {
+        let stamp = Stamp::oriented(level, build_data.map.width as usize, build_data.map.height as usize);
 
-        let mut i = 0;
-        for ty in 0..level.height {
-            for tx in 0..level.width {
+        for ty in 0..stamp.height {
+            for tx in 0..stamp.width {
                 if tx < build_data.map.width as usize && ty < build_data.map.height as usize {
                     let idx = build_data.map.xy_idx(tx as i32, ty as i32);
-                    if i < string_vec.len() { self.char_to_map(string_vec[i], idx, build_data); }
+                    self.char_to_map(stamp.glyphs[ty * stamp.width + tx], idx, &level.legend, build_data);
                 }
-                i += 1;
             }
         }
     }
-   
+
     fn apply_previous_iteration<F>(&mut self, mut filter: F, build_data : &mut BuilderMap)
-        where F : FnMut(i32, i32) -> bool 
+        where F : FnMut(i32, i32) -> bool
     {
         let width = build_data.map.width;
         build_data.spawn_list.retain(|(idx, _name)| {
@@ -183,107 +222,114 @@ impl PrefabBuilder {
     }
 
     #[allow(dead_code)]
-    pub fn apply_sectional(&mut self, section : &prefab_sections::PrefabSection, build_data : &mut BuilderMap) {
-        use prefab_sections::*;
+    pub fn apply_sectional(&mut self, section : &PrefabDefinition, build_data : &mut BuilderMap) {
+        let stamp = Stamp::oriented(section, build_data.map.width as usize - 2, build_data.map.height as usize - 2);
+        let (horizontal, vertical) = section.placement.as_ref()
+            .map_or(("center", "center"), |placement| (placement.x.as_str(), placement.y.as_str()));
+
+        let chunk_x = match horizontal {
+            "left" => 0,
+            "right" => (build_data.map.width-1) - stamp.width as i32,
+            _ => (build_data.map.width / 2) - (stamp.width as i32 / 2)
+        };
+
+        let chunk_y = match vertical {
+            "top" => 0,
+            "bottom" => (build_data.map.height-1) - stamp.height as i32,
+            _ => (build_data.map.height / 2) - (stamp.height as i32 / 2)
+        };
 
-        let string_vec = PrefabBuilder::read_ascii_to_vec(section.template);
-
-        let chunk_x;
-        match section.placement.0 {
-            HorizontalPlacement::Left => chunk_x = 0,
-            HorizontalPlacement::Center => chunk_x = (build_data.map.width / 2) - (section.width as i32 / 2),
-            HorizontalPlacement::Right => chunk_x = (build_data.map.width-1) - section.width as i32
-        }
-
-        let chunk_y;
-        match section.placement.1 {
-            VerticalPlacement::Top => chunk_y = 0,
-            VerticalPlacement::Center => chunk_y = (build_data.map.height / 2) - (section.height as i32 / 2),
-            VerticalPlacement::Bottom => chunk_y = (build_data.map.height-1) - section.height as i32
-        }
-        
         self.apply_previous_iteration(|x,y| {
-            x < chunk_x || x > (chunk_x + section.width as i32) || y < chunk_y || y > (chunk_y + section.height as i32)
+            x < chunk_x || x > (chunk_x + stamp.width as i32) || y < chunk_y || y > (chunk_y + stamp.height as i32)
         }, build_data);
 
-        let mut i = 0;
-        for ty in 0..section.height {
-            for tx in 0..section.width {
-                if tx > 0 && tx < build_data.map.width as usize -1 && ty < build_data.map.height as usize -1 && ty > 0 {
-                    let idx = build_data.map.xy_idx(tx as i32 + chunk_x, ty as i32 + chunk_y);
-                    if i < string_vec.len() { self.char_to_map(string_vec[i], idx, build_data); }
+        for ty in 0..stamp.height {
+            for tx in 0..stamp.width {
+                let (x, y) = (tx as i32 + chunk_x, ty as i32 + chunk_y);
+                if x > 0 && x < build_data.map.width - 1 && y > 0 && y < build_data.map.height - 1 {
+                    let idx = build_data.map.xy_idx(x, y);
+                    self.char_to_map(stamp.glyphs[ty * stamp.width + tx], idx, &section.legend, build_data);
                 }
-                i += 1;
             }
         }
         build_data.take_snapshot();
     }
 
-    fn apply_room_vaults(&mut self, build_data : &mut BuilderMap) {
-        use prefab_rooms::*;
+    fn vault_position(&self, build_data : &BuilderMap, stamp : &Stamp, used_tiles : &HashSet<usize>, area : Option<&[bool]>) -> Option<Position> {
+        let mut idx = 0usize;
+        loop {
+            let x = (idx % build_data.map.width as usize) as i32;
+            let y = (idx / build_data.map.width as usize) as i32;
+
+            if x > 1
+                && (x+stamp.width as i32) < build_data.map.width-2
+                && y > 1
+                && (y+stamp.height as i32) < build_data.map.height-2
+            {
+                let mut possible = true;
+                for ty in 0..stamp.height as i32 {
+                    for tx in 0..stamp.width as i32 {
+
+                        let idx = build_data.map.xy_idx(tx + x, ty + y);
+                        if build_data.map.tiles[idx] != TileType::Floor {
+                            possible = false;
+                        }
+                        if used_tiles.contains(&idx) {
+                            possible = false;
+                        }
+                        if let Some(area) = area {
+                            if !area[idx] { possible = false; }
+                        }
+                    }
+                }
 
+                if possible {
+                    return Some(Position{ x, y });
+                }
+            }
+
+            idx += 1;
+            if idx >= build_data.map.tiles.len()-1 { return None; }
+        }
+    }
+
+    fn apply_room_vaults(&mut self, build_data : &mut BuilderMap) {
         self.apply_previous_iteration(|_x,_y| true, build_data);
 
         let vault_roll = crate::rng::roll_dice(1, 6) + build_data.map.depth;
         if vault_roll < 4 { return; }
 
-        let master_vault_list = vec![TOTALLY_NOT_A_TRAP, CHECKERBOARD, SILLY_SMILE];
+        let depth = build_data.map.depth;
+        let master_vault_list : Vec<PrefabDefinition> = RAWS.lock().unwrap()
+            .prefabs_of_kind(PrefabKind::Room)
+            .filter(|v| depth >= v.min_depth && depth <= v.max_depth)
+            .cloned()
+            .collect();
 
-        let mut possible_vaults : Vec<&PrefabRoom> = master_vault_list
+        // A vault of rarity 3 makes it into the draw one time in three
+        let mut possible_vaults : Vec<&PrefabDefinition> = master_vault_list
             .iter()
-            .filter(|v| { build_data.map.depth >= v.first_depth && build_data.map.depth <= v.last_depth })
+            .filter(|v| v.rarity <= 1 || crate::rng::roll_dice(1, v.rarity) == 1)
             .collect();
 
         if possible_vaults.is_empty() { return; }
 
         let n_vaults = i32::min(crate::rng::roll_dice(1, 3), possible_vaults.len() as i32);
         let mut used_tiles : HashSet<usize> = HashSet::new();
+        let secret = build_data.starting_position.as_ref().map(|start| secret_area(&build_data.map, start));
 
         for _i in 0..n_vaults {
-            
+
             let vault_index = if possible_vaults.len() == 1 { 0 } else { (crate::rng::roll_dice(1, possible_vaults.len() as i32)-1) as usize };
             let vault = possible_vaults[vault_index];
-        
-            let mut vault_positions : Vec<Position> = Vec::new();
-
-            let mut idx = 0usize;
-            loop {
-                let x = (idx % build_data.map.width as usize) as i32;
-                let y = (idx / build_data.map.width as usize) as i32;
-
-                if x > 1
-                    && (x+vault.width as i32) < build_data.map.width-2
-                    && y > 1
-                    && (y+vault.height as i32) < build_data.map.height-2
-                {
-                    let mut possible = true;
-                    for ty in 0..vault.height as i32 {
-                        for tx in 0..vault.width as i32 {
-
-                            let idx = build_data.map.xy_idx(tx + x, ty + y);
-                            if build_data.map.tiles[idx] != TileType::Floor {
-                                possible = false;
-                            }
-                            if used_tiles.contains(&idx) {
-                                possible = false;
-                            }
-                        }
-                    }
-
-                    if possible {
-                        vault_positions.push(Position{ x, y});
-                        break;
-                    }
-                }
-                
-                idx += 1;
-                if idx >= build_data.map.tiles.len()-1 { break; }
-            }
+            let stamp = Stamp::oriented(vault, build_data.map.width as usize, build_data.map.height as usize);
 
-            if !vault_positions.is_empty() {
-                let pos_idx = if vault_positions.len()==1 { 0 } else { (crate::rng::roll_dice(1, vault_positions.len() as i32)-1) as usize };
-                let pos = &vault_positions[pos_idx];
+            // Treasure rooms go behind secret doors when the level has any room for them there
+            let position = secret.as_ref()
+                .and_then(|secret| self.vault_position(build_data, &stamp, &used_tiles, Some(secret)))
+                .or_else(|| self.vault_position(build_data, &stamp, &used_tiles, None));
 
+            if let Some(pos) = position {
                 let chunk_x = pos.x;
                 let chunk_y = pos.y;
 
@@ -292,17 +338,14 @@ impl PrefabBuilder {
                     let idx = e.0 as i32;
                     let x = idx % width;
                     let y = idx / width;
-                    x < chunk_x || x > chunk_x + vault.width as i32 || y < chunk_y || y > chunk_y + vault.height as i32
+                    x < chunk_x || x > chunk_x + stamp.width as i32 || y < chunk_y || y > chunk_y + stamp.height as i32
                 });
 
-                let string_vec = PrefabBuilder::read_ascii_to_vec(vault.template);
-                let mut i = 0;
-                for ty in 0..vault.height {
-                    for tx in 0..vault.width {
+                for ty in 0..stamp.height {
+                    for tx in 0..stamp.width {
                         let idx = build_data.map.xy_idx(tx as i32 + chunk_x, ty as i32 + chunk_y);
-                        if i < string_vec.len() { self.char_to_map(string_vec[i], idx, build_data); }
+                        self.char_to_map(stamp.glyphs[ty * stamp.width + tx], idx, &vault.legend, build_data);
                         used_tiles.insert(idx);
-                        i += 1;
                     }
                 }
                 build_data.take_snapshot();
@@ -311,4 +354,27 @@ impl PrefabBuilder {
             }
         }
     }
-}
\ No newline at end of file
+}
+
+fn find_prefab(name : &str) -> Option<PrefabDefinition> {
+    let prefab = RAWS.lock().unwrap().prefab(name).cloned();
+    if prefab.is_none() {
+        rltk::console::log(format!("Unknown prefab: {}", name));
+    }
+    prefab
+}
+
+// A named mob, item or prop, or whatever the level's or a named spawn table rolls
+fn legend_spawn(entry : &LegendEntry, build_data : &BuilderMap) -> Option<String> {
+    if let Some(name) = entry.mob.as_ref().or(entry.item.as_ref()).or(entry.prop.as_ref()) {
+        return Some(name.clone());
+    }
+    let table = entry.roll.as_ref()?;
+    let raws = RAWS.lock().unwrap();
+    let roll = if table == "level" {
+        get_spawn_table_for_level(&raws, build_data.map.id()).roll()
+    } else {
+        get_named_spawn_table(&raws, table, build_data.map.depth)?.roll()
+    };
+    if roll == "None" { None } else { Some(roll) }
+}
@@ -0,0 +1,79 @@
+use std::collections::HashMap;
+use super::{BuilderChain, TileType};
+use crate::{map::tile_walkable, raws::RAWS};
+
+pub struct LevelReport {
+    pub walkable_ratio : f32,
+    pub spawns : usize,
+    pub exit_distance : Option<f32>,
+    pub problems : Vec<String>
+}
+
+// Invariants every generated level should hold once the builder chain has run
+pub fn check_level(builder : &mut BuilderChain, min_walkable : f32, expect_exit : bool) -> LevelReport {
+    let build_data = &mut builder.build_data;
+    let map = &mut build_data.map;
+    let width = map.width as usize;
+    let position = |idx : usize| format!("({}, {})", idx % width, idx / width);
+    let mut problems = Vec::new();
+
+    let walkable = map.tiles.iter().filter(|tile| tile_walkable(**tile)).count();
+    let walkable_ratio = walkable as f32 / map.tiles.len() as f32;
+    if walkable_ratio < min_walkable {
+        problems.push(format!("only {:.0}% of the map is walkable", walkable_ratio * 100.0));
+    }
+
+    let start_idx = build_data.starting_position.as_ref().map(|start| map.xy_idx(start.x, start.y));
+    let mut exit_distance = None;
+    match start_idx {
+        None => problems.push("no starting position".to_string()),
+        Some(start_idx) if !tile_walkable(map.tiles[start_idx]) => {
+            problems.push(format!("starting position {} is not walkable", position(start_idx)));
+        }
+        Some(start_idx) => {
+            map.populate_blocked();
+            let dijkstra_map = rltk::DijkstraMap::new(width, map.height as usize, &[start_idx], &*map, 1000.0);
+            let mut has_exit = false;
+            let mut unreachable = Vec::new();
+            for (idx, tile) in map.tiles.iter().enumerate() {
+                if *tile != TileType::DownStairs && *tile != TileType::UpStairs { continue; }
+                let distance = dijkstra_map.map[idx];
+                if distance == f32::MAX {
+                    unreachable.push(idx);
+                } else if *tile == TileType::DownStairs {
+                    has_exit = true;
+                    exit_distance = Some(exit_distance.map_or(distance, |d : f32| d.min(distance)));
+                }
+            }
+            if let Some(first) = unreachable.first() {
+                problems.push(format!("{} stairs tile(s) unreachable from the start, first at {}", unreachable.len(), position(*first)));
+            }
+            if expect_exit && !has_exit {
+                problems.push("no reachable down stairs".to_string());
+            }
+        }
+    }
+
+    let raws = RAWS.lock().unwrap();
+    let mut blockers : HashMap<usize, &str> = HashMap::new();
+    let mut spawns = 0;
+    for (idx, name) in build_data.spawn_list.iter().filter(|spawn| spawn.1 != "None") {
+        spawns += 1;
+        if *idx >= map.tiles.len() {
+            problems.push(format!("spawn [{}] is outside the map", name));
+            continue;
+        }
+        if !tile_walkable(map.tiles[*idx]) {
+            problems.push(format!("spawn [{}] at {} is inside a wall", name, position(*idx)));
+        }
+        if !raws.spawn_blocks_tile(name) { continue; }
+        if Some(*idx) == start_idx {
+            problems.push(format!("spawn [{}] blocks the starting position", name));
+        }
+        if let Some(other) = blockers.insert(*idx, name) {
+            problems.push(format!("spawn [{}] at {} is stacked on [{}]", name, position(*idx), other));
+        }
+    }
+
+    LevelReport{ walkable_ratio, spawns, exit_distance, problems }
+}
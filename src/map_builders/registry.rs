@@ -0,0 +1,229 @@
+use super::{InitialMapBuilder, MetaMapBuilder, SimpleMapBuilder, BspDungeonBuilder, BspInteriorBuilder, CellularAutomataBuilder,
+    DrunkardsWalkBuilder, MazeBuilder, DLABuilder, VoronoiCellBuilder, WaveformCollapseBuilder, PrefabBuilder,
+    RoomSorter, RoomSort, RoomDrawer, DoglegCorridors, NearestCorridors, StraightLineCorridors, BspCorridors, CorridorSpawner,
+    RoomExploder, RoomCornerRounder, RoomBasedStartingPosition, AreaStartingPosition, XStart, YStart, AreaEndingPosition, XEnd, YEnd,
+    RoomBasedStairs, DistantExit, RoomBasedSpawner, VoronoiSpawning, CullUnreachable, DoorPlacement, SecretDoors, HazardPlacement, TownBuilder, PlazaMapBuilder,
+    YellowBrickRoad, CaveDecorator, CaveTransition, DragonsLair, DragonSpawner, RiverBuilder, OverworldBuilder, ConnectExits, MapId,
+    WfcSample, ChunkSymmetry, TileType};
+use crate::raws::BuilderStep;
+
+// Builder names and parameters as they appear in raws/levels.json, the raws validator checks prefab names
+
+fn param<'a>(step : &'a BuilderStep, key : &str) -> Result<&'a str, String> {
+    step.params.get(key)
+        .map(|value| value.as_str())
+        .ok_or(format!("{} needs the [{}] parameter", step.builder, key))
+}
+
+fn count_param(step : &BuilderStep, key : &str, default : i32) -> Result<i32, String> {
+    match step.params.get(key) {
+        None => Ok(default),
+        Some(value) => value.parse::<i32>().ok()
+            .filter(|count| *count >= 0)
+            .ok_or(format!("{} needs a count for [{}], got [{}]", step.builder, key, value))
+    }
+}
+
+fn map_ids_param(step : &BuilderStep, key : &str) -> Result<Vec<MapId>, String> {
+    param(step, key)?.split_whitespace()
+        .map(|id| id.parse::<MapId>().map_err(|e| format!("{} [{}]: {}", step.builder, key, e)))
+        .collect()
+}
+
+fn map_id_param(step : &BuilderStep, key : &str) -> Result<MapId, String> {
+    match map_ids_param(step, key)?.as_slice() {
+        [id] => Ok(*id),
+        _ => Err(format!("{} needs a single map id for [{}]", step.builder, key))
+    }
+}
+
+fn unknown_value(step : &BuilderStep, key : &str, value : &str) -> String {
+    format!("{} has no {} [{}]", step.builder, key, value)
+}
+
+fn drunkards_walk(step : &BuilderStep) -> Result<Box<DrunkardsWalkBuilder>, String> {
+    match param(step, "variant")? {
+        "open_area" => Ok(DrunkardsWalkBuilder::open_area()),
+        "open_halls" => Ok(DrunkardsWalkBuilder::open_halls()),
+        "winding_passages" => Ok(DrunkardsWalkBuilder::winding_passages()),
+        "fat_passages" => Ok(DrunkardsWalkBuilder::fat_passages()),
+        "fearful_symmetry" => Ok(DrunkardsWalkBuilder::fearful_symmetry()),
+        other => Err(unknown_value(step, "variant", other))
+    }
+}
+
+fn dla(step : &BuilderStep) -> Result<Box<DLABuilder>, String> {
+    match param(step, "variant")? {
+        "walk_inwards" => Ok(DLABuilder::walk_inwards()),
+        "walk_outwards" => Ok(DLABuilder::walk_outwards()),
+        "central_attractor" => Ok(DLABuilder::central_attractor()),
+        "insectoid" => Ok(DLABuilder::insectoid()),
+        "heavy_erosion" => Ok(DLABuilder::heavy_erosion()),
+        other => Err(unknown_value(step, "variant", other))
+    }
+}
+
+fn voronoi(step : &BuilderStep) -> Result<Box<VoronoiCellBuilder>, String> {
+    match param(step, "distance")? {
+        "pythagoras" => Ok(VoronoiCellBuilder::pythagoras()),
+        "manhattan" => Ok(VoronoiCellBuilder::manhattan()),
+        other => Err(unknown_value(step, "distance", other))
+    }
+}
+
+// A WFC sample is a REX image, a text prefab, or with neither the map the chain has built so far
+fn wfc_sample(step : &BuilderStep) -> Result<WfcSample, String> {
+    match (step.params.get("rex").map(|value| value.as_str()), step.params.get("level")) {
+        (Some(_), Some(_)) => Err(format!("{} takes either [rex] or [level], not both", step.builder)),
+        (Some("wfc_demo1"), None) => Ok(WfcSample::Rex("../resources/wfc-demo1.xp")),
+        (Some("wfc_populated"), None) => Ok(WfcSample::Rex("../resources/wfc-populated.xp")),
+        (Some(other), None) => Err(unknown_value(step, "rex", other)),
+        (None, Some(level)) => Ok(WfcSample::Prefab(level.clone())),
+        (None, None) => Ok(WfcSample::CurrentMap)
+    }
+}
+
+fn waveform_collapse(step : &BuilderStep) -> Result<Box<WaveformCollapseBuilder>, String> {
+    let sample = wfc_sample(step)?;
+    let chunk_size = count_param(step, "chunk_size", 8)?;
+    if chunk_size < 2 {
+        return Err(format!("{} needs a [chunk_size] of at least 2", step.builder));
+    }
+    let symmetry = match step.params.get("symmetry").map(|value| value.as_str()).unwrap_or("flip") {
+        "none" => ChunkSymmetry::None,
+        "flip" => ChunkSymmetry::Flip,
+        "rotate" => ChunkSymmetry::Rotate,
+        other => return Err(unknown_value(step, "symmetry", other))
+    };
+    Ok(WaveformCollapseBuilder::from_sample(sample, chunk_size, symmetry))
+}
+
+fn hazard_tile(step : &BuilderStep) -> Result<TileType, String> {
+    match param(step, "tile")? {
+        "lava" => Ok(TileType::Lava),
+        "chasm" => Ok(TileType::Chasm),
+        "ice" => Ok(TileType::Ice),
+        other => Err(unknown_value(step, "tile", other))
+    }
+}
+
+fn room_sort(step : &BuilderStep) -> Result<RoomSort, String> {
+    match param(step, "sort")? {
+        "leftmost" => Ok(RoomSort::LEFTMOST),
+        "rightmost" => Ok(RoomSort::RIGHTMOST),
+        "topmost" => Ok(RoomSort::TOPMOST),
+        "bottommost" => Ok(RoomSort::BOTTOMMOST),
+        "central" => Ok(RoomSort::CENTRAL),
+        other => Err(unknown_value(step, "sort", other))
+    }
+}
+
+fn start_position(step : &BuilderStep) -> Result<(XStart, YStart), String> {
+    let x = match param(step, "x")? {
+        "left" => XStart::LEFT,
+        "center" => XStart::CENTER,
+        "right" => XStart::RIGHT,
+        other => return Err(unknown_value(step, "x", other))
+    };
+    let y = match param(step, "y")? {
+        "top" => YStart::TOP,
+        "center" => YStart::CENTER,
+        "bottom" => YStart::BOTTOM,
+        other => return Err(unknown_value(step, "y", other))
+    };
+    Ok((x, y))
+}
+
+fn end_position(step : &BuilderStep) -> Result<(XEnd, YEnd), String> {
+    let x = match param(step, "x")? {
+        "left" => XEnd::LEFT,
+        "center" => XEnd::CENTER,
+        "right" => XEnd::RIGHT,
+        other => return Err(unknown_value(step, "x", other))
+    };
+    let y = match param(step, "y")? {
+        "top" => YEnd::TOP,
+        "center" => YEnd::CENTER,
+        "bottom" => YEnd::BOTTOM,
+        other => return Err(unknown_value(step, "y", other))
+    };
+    Ok((x, y))
+}
+
+pub fn initial_builder(step : &BuilderStep) -> Result<Box<dyn InitialMapBuilder>, String> {
+    let builder : Box<dyn InitialMapBuilder> = match step.builder.as_str() {
+        "simple_map" => SimpleMapBuilder::new(),
+        "bsp_dungeon" => BspDungeonBuilder::new(),
+        "bsp_interior" => BspInteriorBuilder::new(),
+        "cellular_automata" => CellularAutomataBuilder::new(),
+        "drunkards_walk" => drunkards_walk(step)?,
+        "maze" => MazeBuilder::new(),
+        "dla" => dla(step)?,
+        "voronoi" => voronoi(step)?,
+        "prefab_level" => PrefabBuilder::constant(param(step, "level")?),
+        "town" => TownBuilder::new(),
+        "plaza" => PlazaMapBuilder::new(),
+        "overworld" => OverworldBuilder::new(map_ids_param(step, "sites")?),
+        "waveform_collapse" => {
+            let builder = waveform_collapse(step)?;
+            if wfc_sample(step)? == WfcSample::CurrentMap {
+                return Err(format!("{} as the first step needs a [rex] or [level] sample", step.builder));
+            }
+            builder
+        }
+        other => return Err(format!("unknown initial builder [{}]", other))
+    };
+    Ok(builder)
+}
+
+pub fn meta_builder(step : &BuilderStep) -> Result<Box<dyn MetaMapBuilder>, String> {
+    let builder : Box<dyn MetaMapBuilder> = match step.builder.as_str() {
+        "cellular_automata" => CellularAutomataBuilder::new(),
+        "drunkards_walk" => drunkards_walk(step)?,
+        "dla" => dla(step)?,
+        "waveform_collapse" => waveform_collapse(step)?,
+        "prefab_section" => PrefabBuilder::sectional(param(step, "section")?),
+        "prefab_vaults" => PrefabBuilder::vaults(),
+        "room_sorter" => RoomSorter::new(room_sort(step)?),
+        "room_drawer" => RoomDrawer::new(),
+        "dogleg_corridors" => DoglegCorridors::new(),
+        "nearest_corridors" => NearestCorridors::new(),
+        "straight_line_corridors" => StraightLineCorridors::new(),
+        "bsp_corridors" => BspCorridors::new(),
+        "corridor_spawner" => CorridorSpawner::new(),
+        "room_exploder" => RoomExploder::new(),
+        "room_corner_rounder" => RoomCornerRounder::new(),
+        "room_based_starting_position" => RoomBasedStartingPosition::new(),
+        "area_starting_position" => {
+            let (x, y) = start_position(step)?;
+            AreaStartingPosition::new(x, y)
+        }
+        "area_ending_position" => {
+            let (x, y) = end_position(step)?;
+            AreaEndingPosition::new(x, y)
+        }
+        "room_based_stairs" => RoomBasedStairs::new(),
+        "distant_exit" => DistantExit::new(),
+        "room_based_spawner" => RoomBasedSpawner::new(),
+        "voronoi_spawning" => VoronoiSpawning::new(),
+        "cull_unreachable" => CullUnreachable::new(),
+        "door_placement" => DoorPlacement::new(),
+        "secret_doors" => {
+            let chance = count_param(step, "chance", 50)?;
+            if chance > 100 {
+                return Err(format!("{} needs a [chance] of at most 100", step.builder));
+            }
+            SecretDoors::new(chance)
+        }
+        "hazards" => HazardPlacement::new(hazard_tile(step)?, count_param(step, "count", 3)?),
+        "yellow_brick_road" => YellowBrickRoad::new(),
+        "cave_decorator" => CaveDecorator::new(),
+        "cave_transition" => CaveTransition::new(),
+        "dragons_lair" => DragonsLair::new(),
+        "dragon_spawner" => DragonSpawner::new(),
+        "connect_exits" => ConnectExits::new(map_id_param(step, "to")?),
+        "rivers_and_lakes" => RiverBuilder::new(count_param(step, "rivers", 1)?, count_param(step, "lakes", 0)?),
+        other => return Err(format!("unknown meta builder [{}]", other))
+    };
+    Ok(builder)
+}
@@ -0,0 +1,252 @@
+use super::{MetaMapBuilder, BuilderMap, TileType, Map};
+use crate::map::tile_walkable;
+use std::collections::{BinaryHeap, HashSet};
+use std::cmp::Reverse;
+
+const DEEP_WATER_COST : i32 = 6;
+const MIN_REGION_SIZE : usize = 12;
+
+pub struct RiverBuilder {
+    rivers : i32,
+    lakes : i32
+}
+
+impl MetaMapBuilder for RiverBuilder {
+    fn build_map(&mut self, build_data : &mut BuilderMap) {
+        self.build(build_data);
+    }
+}
+
+impl RiverBuilder {
+    #[allow(dead_code)]
+    pub fn new(rivers : i32, lakes : i32) -> Box<RiverBuilder> {
+        Box::new(RiverBuilder{ rivers, lakes })
+    }
+
+    #[allow(dead_code)]
+    pub fn river() -> Box<RiverBuilder> {
+        RiverBuilder::new(1, 0)
+    }
+
+    #[allow(dead_code)]
+    pub fn lakes() -> Box<RiverBuilder> {
+        RiverBuilder::new(0, 2)
+    }
+
+    fn build(&mut self, build_data : &mut BuilderMap) {
+        let start_idx = build_data.starting_position.as_ref().map(|pos| build_data.map.xy_idx(pos.x, pos.y));
+        let mut protected : HashSet<usize> = build_data.map.tiles.iter().enumerate()
+            .filter(|(_, tile)| matches!(tile, TileType::DownStairs | TileType::UpStairs | TileType::Bridge))
+            .map(|(idx, _)| idx)
+            .collect();
+        protected.extend(start_idx);
+        let reachable_before = start_idx.map(|start| reachable_from(&build_data.map, start));
+
+        for _ in 0 .. self.rivers {
+            carve_river(&mut build_data.map, &protected);
+        }
+        for _ in 0 .. self.lakes {
+            carve_lake(&mut build_data.map, &protected);
+        }
+        build_data.take_snapshot();
+
+        let (Some(start_idx), Some(reachable_before)) = (start_idx, reachable_before) else {
+            let map = &build_data.map;
+            build_data.spawn_list.retain(|spawn| tile_walkable(map.tiles[spawn.0]));
+            return;
+        };
+
+        let exits : Vec<usize> = build_data.map.tiles.iter().enumerate()
+            .filter(|(_, tile)| **tile == TileType::DownStairs)
+            .map(|(idx, _)| idx)
+            .collect();
+        for exit in exits {
+            bridge(&mut build_data.map, start_idx, exit);
+        }
+
+        // Reconnect anything big the water cut off, small pockets are simply left behind
+        let mut reachable = reachable_from(&build_data.map, start_idx);
+        let mut checked = vec![false; reachable.len()];
+        for idx in 0 .. reachable.len() {
+            if !reachable_before[idx] || reachable[idx] || checked[idx] { continue; }
+            let region = reachable_from(&build_data.map, idx);
+            let size = region.iter().filter(|r| **r).count();
+            region.iter().enumerate().filter(|(_, r)| **r).for_each(|(i, _)| checked[i] = true);
+            if size >= MIN_REGION_SIZE && bridge(&mut build_data.map, start_idx, idx) {
+                reachable = reachable_from(&build_data.map, start_idx);
+            }
+        }
+        build_data.take_snapshot();
+
+        let map = &build_data.map;
+        build_data.spawn_list.retain(|spawn| {
+            tile_walkable(map.tiles[spawn.0]) && (reachable[spawn.0] || !reachable_before[spawn.0])
+        });
+    }
+}
+
+// Deep water goes through anything, the shallow banks only flood walkable ground
+fn paint(map : &mut Map, protected : &HashSet<usize>, x : i32, y : i32, tile : TileType) {
+    if x < 1 || y < 1 || x > map.width-2 || y > map.height-2 { return; }
+    let idx = map.xy_idx(x, y);
+    if protected.contains(&idx) { return; }
+    let current = map.tiles[idx];
+    if tile == TileType::ShallowWater && (current == TileType::DeepWater || !tile_walkable(current)) { return; }
+    map.tiles[idx] = tile;
+}
+
+fn carve_river(map : &mut Map, protected : &HashSet<usize>) {
+    let horizontal = crate::rng::roll_dice(1, 2) == 1;
+    let (length, breadth) = if horizontal { (map.width, map.height) } else { (map.height, map.width) };
+    let core = crate::rng::roll_dice(1, 2) - 1;
+    // Small maps get a river anywhere across them rather than a panicking dice roll
+    let mut center = (crate::rng::roll_dice(1, i32::max(1, breadth - 10)) + 4) as f32;
+    let mut bend = 0.0f32;
+
+    for step in 0 .. length {
+        let previous = center.round() as i32;
+        bend = f32::clamp(bend + (crate::rng::roll_dice(1, 5) - 3) as f32 * 0.12, -0.8, 0.8);
+        if center + bend < 3.0 || center + bend > (breadth - 4) as f32 {
+            bend = -bend;
+        }
+        center += bend;
+        let center = center.round() as i32;
+
+        // Covering both the old and the new bend keeps the deep channel closed to diagonal steps
+        let low = i32::min(previous, center) - core;
+        let high = i32::max(previous, center) + core;
+        for offset in low-1 ..= high+1 {
+            let tile = if offset < low || offset > high { TileType::ShallowWater } else { TileType::DeepWater };
+            let (x, y) = if horizontal { (step, offset) } else { (offset, step) };
+            paint(map, protected, x, y, tile);
+        }
+    }
+}
+
+fn carve_lake(map : &mut Map, protected : &HashSet<usize>) {
+    let center_x = crate::rng::roll_dice(1, i32::max(1, map.width - 20)) + 9;
+    let center_y = crate::rng::roll_dice(1, i32::max(1, map.height - 16)) + 7;
+    let mut circles = Vec::new();
+    for _ in 0 .. 3 {
+        circles.push((
+            center_x + crate::rng::roll_dice(1, 7) - 4,
+            center_y + crate::rng::roll_dice(1, 5) - 3,
+            crate::rng::roll_dice(1, 3) + 1
+        ));
+    }
+
+    for (depth, tile) in [(0, TileType::DeepWater), (1, TileType::ShallowWater)] {
+        for (cx, cy, radius) in circles.iter() {
+            let reach = radius + depth;
+            for y in cy - reach ..= cy + reach {
+                for x in cx - reach ..= cx + reach {
+                    let distance = rltk::DistanceAlg::Pythagoras.distance2d(rltk::Point::new(x, y), rltk::Point::new(*cx, *cy));
+                    if distance <= reach as f32 {
+                        paint(map, protected, x, y, tile);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn neighbours(map : &Map, idx : usize, diagonals : bool) -> Vec<usize> {
+    let (x, y) = (idx as i32 % map.width, idx as i32 / map.width);
+    let mut result = Vec::new();
+    for (dx, dy) in [(-1, 0), (1, 0), (0, -1), (0, 1), (-1, -1), (1, -1), (-1, 1), (1, 1)] {
+        if !diagonals && dx != 0 && dy != 0 { continue; }
+        let (nx, ny) = (x + dx, y + dy);
+        if nx >= 0 && ny >= 0 && nx < map.width && ny < map.height {
+            result.push(map.xy_idx(nx, ny));
+        }
+    }
+    result
+}
+
+fn reachable_from(map : &Map, start : usize) -> Vec<bool> {
+    let mut reachable = vec![false; map.tiles.len()];
+    if !tile_walkable(map.tiles[start]) { return reachable; }
+    reachable[start] = true;
+    let mut open = vec![start];
+    while let Some(idx) = open.pop() {
+        for next in neighbours(map, idx, true) {
+            if !reachable[next] && tile_walkable(map.tiles[next]) {
+                reachable[next] = true;
+                open.push(next);
+            }
+        }
+    }
+    reachable
+}
+
+// Cheapest path that may wade straight across deep water, which then becomes a bridge
+fn bridge(map : &mut Map, start : usize, target : usize) -> bool {
+    let mut cost = vec![i32::MAX; map.tiles.len()];
+    let mut previous = vec![usize::MAX; map.tiles.len()];
+    let mut open = BinaryHeap::new();
+    let width = map.width as usize;
+    cost[start] = 0;
+    open.push(Reverse((0, start)));
+
+    while let Some(Reverse((current_cost, idx))) = open.pop() {
+        if idx == target { break; }
+        if current_cost > cost[idx] { continue; }
+        for next in neighbours(map, idx, true) {
+            let diagonal = idx % width != next % width && idx / width != next / width;
+            let step = match map.tiles[next] {
+                _ if diagonal && (map.tiles[idx] == TileType::DeepWater || map.tiles[next] == TileType::DeepWater) => continue,
+                TileType::DeepWater => DEEP_WATER_COST,
+                tile if tile_walkable(tile) => 1,
+                _ => continue
+            };
+            if current_cost + step < cost[next] {
+                cost[next] = current_cost + step;
+                previous[next] = idx;
+                open.push(Reverse((cost[next], next)));
+            }
+        }
+    }
+
+    if cost[target] == i32::MAX { return false; }
+    let mut idx = target;
+    while idx != start {
+        if map.tiles[idx] == TileType::DeepWater {
+            map.tiles[idx] = TileType::Bridge;
+        }
+        idx = previous[idx];
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Position;
+
+    fn open_field(width : i32, height : i32) -> BuilderMap {
+        let mut map = Map::new(1, width, height, "Test");
+        for y in 1 .. height-1 {
+            for x in 1 .. width-1 {
+                let idx = map.xy_idx(x, y);
+                map.tiles[idx] = TileType::Floor;
+            }
+        }
+        let exit = map.xy_idx(width-2, height-2);
+        map.tiles[exit] = TileType::DownStairs;
+        BuilderMap{ spawn_list: Vec::new(), map, starting_position: Some(Position{ x: 1, y: 1 }), rooms: None, corridors: None,
+            history: Vec::new(), width, height }
+    }
+
+    #[test]
+    fn rivers_and_lakes_fit_on_a_small_map() {
+        let _lock = crate::headless::TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        for _ in 0 .. 50 {
+            let mut build_data = open_field(16, 12);
+            RiverBuilder::new(1, 2).build_map(&mut build_data);
+
+            let map = &build_data.map;
+            let reachable = reachable_from(map, map.xy_idx(1, 1));
+            assert!(reachable[map.xy_idx(14, 10)], "the exit was cut off");
+        }
+    }
+}
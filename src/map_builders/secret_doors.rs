@@ -0,0 +1,91 @@
+use super::{MetaMapBuilder, BuilderMap, Map, TileType, Position};
+use super::door_placement::door_possible;
+use crate::map::tile_walkable;
+use std::collections::VecDeque;
+
+pub struct SecretDoors {
+    chance : i32
+}
+
+impl MetaMapBuilder for SecretDoors {
+    fn build_map(&mut self, build_data : &mut BuilderMap) {
+        self.build(build_data);
+    }
+}
+
+impl SecretDoors {
+    #[allow(dead_code)]
+    pub fn new(chance : i32) -> Box<SecretDoors> {
+        Box::new(SecretDoors{ chance })
+    }
+
+    fn build(&mut self, build_data : &mut BuilderMap) {
+        let halls = match &build_data.corridors {
+            None => return,
+            Some(halls) => halls.clone()
+        };
+        let start = match &build_data.starting_position {
+            None => return,
+            Some(start) => start.clone()
+        };
+
+        for hall in halls.iter() {
+            // Where the corridor leaves one room and where it enters the next
+            let first = hall.iter().find(|idx| door_possible(build_data, **idx)).copied();
+            let last = hall.iter().rev().find(|idx| door_possible(build_data, **idx)).copied();
+            let mut junctions = Vec::new();
+            junctions.extend(first);
+            if last != first { junctions.extend(last); }
+
+            for idx in junctions {
+                if crate::rng::roll_dice(1, 100) > self.chance { continue; }
+                build_data.map.tiles[idx] = TileType::SecretDoor;
+                if !exits_reachable(&build_data.map, &start) {
+                    build_data.map.tiles[idx] = TileType::Floor;
+                }
+            }
+        }
+        build_data.take_snapshot();
+    }
+}
+
+fn flood(map : &Map, start : &Position, through_secret_doors : bool) -> Vec<bool> {
+    let mut reached = vec![false; map.tiles.len()];
+    let start_idx = map.xy_idx(start.x, start.y);
+    reached[start_idx] = true;
+    let mut open = VecDeque::new();
+    open.push_back(start_idx);
+    while let Some(idx) = open.pop_front() {
+        let x = idx as i32 % map.width;
+        let y = idx as i32 / map.width;
+        for (dx, dy) in [(-1, 0), (1, 0), (0, -1), (0, 1), (-1, -1), (1, -1), (-1, 1), (1, 1)] {
+            let (nx, ny) = (x + dx, y + dy);
+            if nx < 1 || nx > map.width-2 || ny < 1 || ny > map.height-2 { continue; }
+            let next = map.xy_idx(nx, ny);
+            let passable = tile_walkable(map.tiles[next]) || (through_secret_doors && map.tiles[next] == TileType::SecretDoor);
+            if passable && !reached[next] {
+                reached[next] = true;
+                open.push_back(next);
+            }
+        }
+    }
+    reached
+}
+
+// Stairs must never end up behind a door the player may not find
+fn exits_reachable(map : &Map, start : &Position) -> bool {
+    let reached = flood(map, start, false);
+    map.tiles.iter().enumerate()
+        .filter(|(_, tile)| **tile == TileType::DownStairs || **tile == TileType::UpStairs)
+        .all(|(idx, _)| reached[idx])
+}
+
+// Floor the player can only get to by finding a secret door
+pub fn secret_area(map : &Map, start : &Position) -> Vec<bool> {
+    let open = flood(map, start, false);
+    let hidden = flood(map, start, true);
+    hidden.iter().zip(open.iter())
+        .enumerate()
+        .map(|(idx, (hidden, open))| *hidden && !*open && tile_walkable(map.tiles[idx]))
+        .collect()
+}
@@ -1,11 +1,5 @@
-use super::{BuilderChain, InitialMapBuilder, BuilderMap, TileType, Position};
-use std::collections::HashSet;
-
-pub fn town_builder(new_depth: i32, width: i32, height: i32) -> BuilderChain {
-    let mut chain = BuilderChain::new(new_depth, width, height, "Bellhatouve");
-    chain.start_with(TownBuilder::new());
-    chain
-}
+use super::{InitialMapBuilder, BuilderMap, TileType, Position};
+use std::collections::BTreeSet;
 
 pub struct TownBuilder {}
 
@@ -85,8 +79,8 @@ impl TownBuilder {
         build_data.take_snapshot();
     }
 
-    fn town_walls(&mut self, build_data: &mut BuilderMap) -> (HashSet<usize>, i32) {
-        let mut available_building_tiles : HashSet<usize> = HashSet::new();
+    fn town_walls(&mut self, build_data: &mut BuilderMap) -> (BTreeSet<usize>, i32) {
+        let mut available_building_tiles : BTreeSet<usize> = BTreeSet::new();
         let wall_gap_y = crate::rng::roll_dice(1, build_data.height - 9) + 5;
         for y in 1 .. build_data.height-2 {
             if !(y > wall_gap_y-4 && y < wall_gap_y+4) {
@@ -124,7 +118,7 @@ impl TownBuilder {
 
     fn buildings(& mut self,
         build_data: &mut BuilderMap,
-        available_building_tiles : &mut HashSet<usize>)
+        available_building_tiles : &mut BTreeSet<usize>)
     -> Vec<(i32, i32, i32, i32)>
     {
         let mut buildings : Vec<(i32, i32, i32, i32)> = Vec::new();
@@ -403,7 +397,7 @@ impl TownBuilder {
 
     fn spawn_townsfolk(&mut self,
         build_data: &mut BuilderMap,
-        available_building_tiles : &mut HashSet<usize>)
+        available_building_tiles : &mut BTreeSet<usize>)
     {
         for idx in available_building_tiles.iter() {
             if crate::rng::roll_dice(1, 20)==1 {
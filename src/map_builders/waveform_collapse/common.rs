@@ -3,6 +3,7 @@ use super::TileType;
 #[derive(PartialEq, Eq, Hash, Clone)]
 pub struct MapChunk {
     pub pattern : Vec<TileType>,
+    pub weight : i32,
     pub exits: [Vec<bool>; 4],
     pub has_exits: bool,
     pub compatible_with: [Vec<usize>; 4]
@@ -1,62 +1,71 @@
-use super::{TileType, Map, MapChunk, tile_idx_in_chunk};
-use std::collections::HashSet;
+use super::{TileType, Map, MapChunk, SampleTiles, tile_idx_in_chunk};
+use std::collections::HashMap;
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum ChunkSymmetry {
+    None,
+    Flip,
+    Rotate
+}
+
+// Where in a square chunk of the given size to read the tile for (x, y) of the pattern
+type Orientation = fn(i32, i32, i32) -> (i32, i32);
 
-pub fn build_patterns(map : &Map, chunk_size: i32, include_flipping: bool, dedupe: bool) -> Vec<Vec<TileType>> {
-    let chunks_x = map.width / chunk_size;
-    let chunks_y = map.height / chunk_size;
+fn orientations(symmetry : ChunkSymmetry) -> Vec<Orientation> {
+    let mut result : Vec<Orientation> = vec![|x, y, _| (x, y)];
+    if symmetry != ChunkSymmetry::None {
+        result.push(|x, y, n| (n - (x+1), y));
+        result.push(|x, y, n| (x, n - (y+1)));
+        result.push(|x, y, n| (n - (x+1), n - (y+1)));
+    }
+    if symmetry == ChunkSymmetry::Rotate {
+        result.push(|x, y, _| (y, x));
+        result.push(|x, y, n| (y, n - (x+1)));
+        result.push(|x, y, n| (n - (y+1), x));
+        result.push(|x, y, n| (n - (y+1), n - (x+1)));
+    }
+    result
+}
+
+// Patterns with how often they occur in the sample, which the solver uses as weights
+pub fn build_patterns(sample : &SampleTiles, chunk_size: i32, symmetry: ChunkSymmetry, dedupe: bool) -> Vec<(Vec<TileType>, i32)> {
+    let chunks_x = sample.width / chunk_size;
+    let chunks_y = sample.height / chunk_size;
     let mut patterns = Vec::new();
 
     for cy in 0..chunks_y {
         for cx in 0..chunks_x {
-            let mut pattern : Vec<TileType> = Vec::new();
             let start_x = cx * chunk_size;
-            let end_x = (cx+1) * chunk_size;
             let start_y = cy * chunk_size;
-            let end_y = (cy+1) * chunk_size;
 
-            for y in start_y .. end_y {
-                for x in start_x .. end_x {
-                    let idx = map.xy_idx(x, y);
-                    pattern.push(map.tiles[idx]);
-                }
-            }
-            patterns.push(pattern);
-
-            if include_flipping {
-                pattern = Vec::new();
-                for y in start_y .. end_y {
-                    for x in start_x .. end_x {
-                        let idx = map.xy_idx(end_x - (x+1), y);
-                        pattern.push(map.tiles[idx]);
+            for orientation in orientations(symmetry) {
+                let mut pattern : Vec<TileType> = Vec::new();
+                for y in 0 .. chunk_size {
+                    for x in 0 .. chunk_size {
+                        let (px, py) = orientation(x, y, chunk_size);
+                        let idx = sample.xy_idx(start_x + px, start_y + py);
+                        pattern.push(sample.tiles[idx]);
                     }
                 }
-                patterns.push(pattern);
-
-                pattern = Vec::new();
-                for y in start_y .. end_y {
-                    for x in start_x .. end_x {
-                        let idx = map.xy_idx(x, end_y - (y+1));
-                        pattern.push(map.tiles[idx]);
-                    }
-                }
-                patterns.push(pattern);
-
-                pattern = Vec::new();
-                for y in start_y .. end_y {
-                    for x in start_x .. end_x {
-                        let idx = map.xy_idx(end_x - (x+1), end_y - (y+1));
-                        pattern.push(map.tiles[idx]);
-                    }
-                }
-                patterns.push(pattern);
+                patterns.push((pattern, 1));
             }
         }
     }
 
     if dedupe {
         rltk::console::log(format!("Pre de-duplication there are {} patterns.", patterns.len()));
-        let set: HashSet<Vec<TileType>> = patterns.drain(..).collect();
-        patterns.extend(set.into_iter());
+        let mut seen: HashMap<Vec<TileType>, usize> = HashMap::new();
+        let mut unique : Vec<(Vec<TileType>, i32)> = Vec::new();
+        for (pattern, weight) in patterns {
+            match seen.get(&pattern) {
+                Some(i) => unique[*i].1 += weight,
+                None => {
+                    seen.insert(pattern.clone(), unique.len());
+                    unique.push((pattern, weight));
+                }
+            }
+        }
+        patterns = unique;
         rltk::console::log(format!("There are {} patterns.", patterns.len()));
     }
 
@@ -100,11 +109,12 @@ pub fn render_pattern_to_map(map : &mut Map, chunk: &MapChunk, chunk_size: i32,
     }
 }
 
-pub fn patterns_to_constraints(patterns: Vec<Vec<TileType>>, chunk_size : i32) -> Vec<MapChunk> {
+pub fn patterns_to_constraints(patterns: Vec<(Vec<TileType>, i32)>, chunk_size : i32) -> Vec<MapChunk> {
     let mut constraints : Vec<MapChunk> = Vec::new();
-    for p in patterns {
+    for (p, weight) in patterns {
         let mut new_chunk = MapChunk{
             pattern: p,
+            weight,
             exits: [ Vec::new(), Vec::new(), Vec::new(), Vec::new() ],
             has_exits : true,
             compatible_with: [ Vec::new(), Vec::new(), Vec::new(), Vec::new() ]
@@ -1,12 +1,26 @@
-use super::{MetaMapBuilder, BuilderMap, Map, TileType};
+use super::{InitialMapBuilder, MetaMapBuilder, BuilderMap, Map, TileType};
 mod constraints;
 use constraints::*;
+pub use constraints::ChunkSymmetry;
 mod common;
 use common::*;
 mod solver;
 use solver::*;
+mod sample;
+use sample::SampleTiles;
+pub use sample::WfcSample;
 
-pub struct WaveformCollapseBuilder {}
+pub struct WaveformCollapseBuilder {
+    sample : WfcSample,
+    chunk_size : i32,
+    symmetry : ChunkSymmetry
+}
+
+impl InitialMapBuilder for WaveformCollapseBuilder {
+    fn build_map(&mut self, build_data : &mut BuilderMap) {
+        self.build(build_data);
+    }
+}
 
 impl MetaMapBuilder for WaveformCollapseBuilder {
     fn build_map(&mut self, build_data : &mut BuilderMap) {
@@ -17,26 +31,41 @@ impl MetaMapBuilder for WaveformCollapseBuilder {
 impl WaveformCollapseBuilder {
     #[allow(dead_code)]
     pub fn new() -> Box<WaveformCollapseBuilder> {
-        Box::new(WaveformCollapseBuilder{})
+        WaveformCollapseBuilder::from_sample(WfcSample::CurrentMap, 8, ChunkSymmetry::Flip)
+    }
+
+    #[allow(dead_code)]
+    pub fn from_sample(sample : WfcSample, chunk_size : i32, symmetry : ChunkSymmetry) -> Box<WaveformCollapseBuilder> {
+        Box::new(WaveformCollapseBuilder{ sample, chunk_size, symmetry })
     }
 
     fn build(&mut self, build_data : &mut BuilderMap) {
-        const CHUNK_SIZE : i32 = 8;
+        let chunk_size = self.chunk_size;
         build_data.take_snapshot();
 
-        let patterns = build_patterns(&build_data.map, CHUNK_SIZE, true, true);
-        let constraints = patterns_to_constraints(patterns, CHUNK_SIZE);
-        self.render_tile_gallery(&constraints, CHUNK_SIZE, build_data);
-
+        let sample = match SampleTiles::load(&self.sample, &build_data.map) {
+            Ok(sample) => sample,
+            Err(e) => {
+                rltk::console::log(e);
+                return;
+            }
+        };
+        let patterns = build_patterns(&sample, chunk_size, self.symmetry, true);
+        if patterns.is_empty() {
+            rltk::console::log(format!("The sample is smaller than a {}x{} chunk.", chunk_size, chunk_size));
+            return;
+        }
+        let constraints = patterns_to_constraints(patterns, chunk_size);
         let old_map = build_data.map.clone();
+        self.render_tile_gallery(&constraints, chunk_size, build_data);
 
-        build_data.map = Map::new(build_data.map.depth, build_data.width, build_data.height, &build_data.map.name);
+        build_data.map = blank_map(build_data);
         build_data.spawn_list.clear();
         build_data.rooms = None;
         build_data.corridors = None;
         let mut tries = 0;
         loop {
-            let mut solver = Solver::new(constraints.clone(), CHUNK_SIZE, &build_data.map);
+            let mut solver = Solver::new(constraints.clone(), chunk_size, &build_data.map);
             while !solver.iteration(&mut build_data.map) {
                 build_data.take_snapshot();
             }
@@ -52,7 +81,7 @@ impl WaveformCollapseBuilder {
     }
 
     fn render_tile_gallery(&mut self, constraints: &[MapChunk], chunk_size: i32, build_data : &mut BuilderMap) {
-        build_data.map = Map::new(build_data.map.depth, build_data.width, build_data.height, &build_data.map.name);
+        build_data.map = blank_map(build_data);
         let mut counter = 0;
         let mut x = 1;
         let mut y = 1;
@@ -66,7 +95,7 @@ impl WaveformCollapseBuilder {
 
                 if y + chunk_size > build_data.map.height {
                     build_data.take_snapshot();
-                    build_data.map = Map::new(build_data.map.depth, build_data.width, build_data.height, &build_data.map.name);
+                    build_data.map = blank_map(build_data);
 
                     x = 1;
                     y = 1;
@@ -77,4 +106,12 @@ impl WaveformCollapseBuilder {
         }
         build_data.take_snapshot();
     }
-}
\ No newline at end of file
+}
+
+// Starts over on an empty map of the same level, keeping what the chain already said about it
+fn blank_map(build_data : &BuilderMap) -> Map {
+    let mut map = Map::new(build_data.map.depth, build_data.width, build_data.height, &build_data.map.name);
+    map.theme = build_data.map.theme;
+    map.branch = build_data.map.branch;
+    map
+}
@@ -0,0 +1,73 @@
+use super::{Map, TileType};
+use super::super::prefab_builder::{Stamp, prefab_tile};
+use crate::raws::{RAWS, PrefabDefinition};
+
+#[derive(PartialEq, Clone)]
+pub enum WfcSample {
+    CurrentMap,
+    Rex(&'static str),
+    Prefab(String)
+}
+
+// The tiles the solver learns its patterns from
+pub struct SampleTiles {
+    pub tiles : Vec<TileType>,
+    pub width : i32,
+    pub height : i32
+}
+
+impl SampleTiles {
+    pub fn xy_idx(&self, x : i32, y : i32) -> usize {
+        (y * self.width + x) as usize
+    }
+
+    pub fn from_map(map : &Map) -> SampleTiles {
+        SampleTiles{ tiles: map.tiles.clone(), width: map.width, height: map.height }
+    }
+
+    pub fn load(sample : &WfcSample, map : &Map) -> Result<SampleTiles, String> {
+        match sample {
+            WfcSample::CurrentMap => Ok(SampleTiles::from_map(map)),
+            WfcSample::Rex(path) => SampleTiles::from_rex(path),
+            WfcSample::Prefab(name) => {
+                let prefab = RAWS.lock().unwrap().prefab(name).cloned();
+                prefab.map(|prefab| SampleTiles::from_prefab(&prefab)).ok_or(format!("unknown prefab sample {}", name))
+            }
+        }
+    }
+
+    fn from_rex(path : &str) -> Result<SampleTiles, String> {
+        crate::rex_assets::link_resources();
+        let xp_file = rltk::rex::XpFile::from_resource(path).map_err(|e| format!("cannot read sample {}: {:?}", path, e))?;
+        let layer = xp_file.layers.first().ok_or(format!("sample {} has no layers", path))?;
+        let mut sample = SampleTiles{ tiles: Vec::new(), width: layer.width as i32, height: layer.height as i32 };
+        for y in 0 .. layer.height {
+            for x in 0 .. layer.width {
+                let ch = layer.get(x, y).map(|cell| rltk::to_char(cell.ch as u8)).unwrap_or('#');
+                sample.tiles.push(glyph_to_tile(ch));
+            }
+        }
+        Ok(sample)
+    }
+
+    fn from_prefab(level : &PrefabDefinition) -> SampleTiles {
+        let stamp = Stamp::new(level);
+        SampleTiles{
+            tiles: stamp.glyphs.iter().map(|ch| match prefab_tile(*ch, &level.legend) {
+                Some(TileType::DownStairs) | None => TileType::Floor,
+                Some(tile) => tile
+            }).collect(),
+            width: stamp.width as i32,
+            height: stamp.height as i32
+        }
+    }
+}
+
+// Only the terrain is learned, creatures and stairs in a sample are plain floor
+fn glyph_to_tile(ch : char) -> TileType {
+    match ch {
+        '#' => TileType::Wall,
+        '≈' => TileType::DeepWater,
+        _ => TileType::Floor
+    }
+}
@@ -1,5 +1,12 @@
-use super::{Map, MapChunk};
-use std::collections::HashSet;
+use super::{Map, MapChunk, TileType};
+use std::collections::BTreeSet;
+
+const MAX_BACKTRACKS : i32 = 500;
+
+struct Placement {
+    chunk : usize,
+    untried : Vec<usize>
+}
 
 pub struct Solver {
     constraints: Vec<MapChunk>,
@@ -8,6 +15,8 @@ pub struct Solver {
     chunks_x : usize,
     chunks_y : usize,
     remaining : Vec<(usize, i32)>,
+    history : Vec<Placement>,
+    backtracks : i32,
     pub possible: bool
 }
 
@@ -27,6 +36,8 @@ impl Solver {
             chunks_x,
             chunks_y,
             remaining,
+            history: Vec::new(),
+            backtracks: 0,
             possible: true
         }
     }
@@ -81,6 +92,99 @@ impl Solver {
         neighbors
     }
 
+    // What may go into a chunk, given the chunks already placed around it
+    fn options_for(&self, chunk_x:usize, chunk_y:usize) -> Vec<usize> {
+        let mut options : Vec<Vec<usize>> = Vec::new();
+
+        if chunk_x > 0 {
+            if let Some(nt) = self.chunks[self.chunk_idx(chunk_x-1, chunk_y)] {
+                options.push(self.constraints[nt].compatible_with[3].clone());
+            }
+        }
+
+        if chunk_x < self.chunks_x-1 {
+            if let Some(nt) = self.chunks[self.chunk_idx(chunk_x+1, chunk_y)] {
+                options.push(self.constraints[nt].compatible_with[2].clone());
+            }
+        }
+
+        if chunk_y > 0 {
+            if let Some(nt) = self.chunks[self.chunk_idx(chunk_x, chunk_y-1)] {
+                options.push(self.constraints[nt].compatible_with[1].clone());
+            }
+        }
+
+        if chunk_y < self.chunks_y-1 {
+            if let Some(nt) = self.chunks[self.chunk_idx(chunk_x, chunk_y+1)] {
+                options.push(self.constraints[nt].compatible_with[0].clone());
+            }
+        }
+
+        if options.is_empty() {
+            return (0..self.constraints.len()).collect();
+        }
+
+        let options_to_check : BTreeSet<usize> = options.iter().flatten().copied().collect();
+        options_to_check.into_iter()
+            .filter(|new_chunk_idx| options.iter().all(|o| o.contains(new_chunk_idx)))
+            .collect()
+    }
+
+    // Picks one of the options by how often its pattern occurs in the sample
+    fn weighted_pick(&self, options : &[usize]) -> usize {
+        let total : i32 = options.iter().map(|o| self.constraints[*o].weight).sum();
+        let mut roll = crate::rng::roll_dice(1, total);
+        for (i, option) in options.iter().enumerate() {
+            roll -= self.constraints[*option].weight;
+            if roll <= 0 { return i; }
+        }
+        options.len() - 1
+    }
+
+    fn place(&mut self, chunk_index : usize, mut untried : Vec<usize>, map: &mut Map) {
+        let pick = self.weighted_pick(&untried);
+        let new_chunk_idx = untried.remove(pick);
+        self.chunks[chunk_index] = Some(new_chunk_idx);
+        self.render(chunk_index, Some(new_chunk_idx), map);
+        self.history.push(Placement{ chunk: chunk_index, untried });
+    }
+
+    fn render(&self, chunk_index : usize, new_chunk_idx : Option<usize>, map: &mut Map) {
+        let chunk_x = (chunk_index % self.chunks_x) as i32;
+        let chunk_y = (chunk_index / self.chunks_x) as i32;
+        let mut i : usize = 0;
+        for y in chunk_y * self.chunk_size .. (chunk_y+1) * self.chunk_size {
+            for x in chunk_x * self.chunk_size .. (chunk_x+1) * self.chunk_size {
+                let mapidx = map.xy_idx(x, y);
+                map.tiles[mapidx] = match new_chunk_idx {
+                    Some(c) => self.constraints[c].pattern[i],
+                    None => TileType::Wall
+                };
+                i += 1;
+            }
+        }
+    }
+
+    // Undoes the latest placements until one of them can take a pattern it has not tried yet
+    fn backtrack(&mut self, map: &mut Map) -> bool {
+        while let Some(placement) = self.history.pop() {
+            self.backtracks += 1;
+            if self.backtracks > MAX_BACKTRACKS { break; }
+
+            self.chunks[placement.chunk] = None;
+            self.render(placement.chunk, None, map);
+            if !placement.untried.is_empty() {
+                self.place(placement.chunk, placement.untried, map);
+                return false;
+            }
+            self.remaining.push((placement.chunk, 0));
+        }
+
+        rltk::console::log("Slepa uliczka, tak sie nie da.");
+        self.possible = false;
+        true
+    }
+
     pub fn iteration(&mut self, map: &mut Map) -> bool {
         if self.remaining.is_empty() { return true; }
 
@@ -103,122 +207,16 @@ impl Solver {
             0usize
         };
         let chunk_index = self.remaining[remaining_index].0;
-        self.remaining.remove(remaining_index);
 
         let chunk_x = chunk_index % self.chunks_x;
         let chunk_y = chunk_index / self.chunks_x;
-
-        let mut neighbors = 0;
-
-        let mut options : Vec<Vec<usize>> = Vec::new();
-
-        if chunk_x > 0 {
-            let left_idx = self.chunk_idx(chunk_x-1, chunk_y);
-            match self.chunks[left_idx] {
-                None => {}
-                Some(nt) => {
-                    neighbors += 1;
-                    options.push(self.constraints[nt].compatible_with[3].clone());
-                }
-            }
-        }
-
-        if chunk_x < self.chunks_x-1 {
-            let right_idx = self.chunk_idx(chunk_x+1, chunk_y);
-            match self.chunks[right_idx] {
-                None => {}
-                Some(nt) => {
-                    neighbors += 1;
-                    options.push(self.constraints[nt].compatible_with[2].clone());
-                }
-            }
-        }
-
-        if chunk_y > 0 {
-            let up_idx = self.chunk_idx(chunk_x, chunk_y-1);
-            match self.chunks[up_idx] {
-                None => {}
-                Some(nt) => {
-                    neighbors += 1;
-                    options.push(self.constraints[nt].compatible_with[1].clone());
-                }
-            }
-        }
-
-        if chunk_y < self.chunks_y-1 {
-            let down_idx = self.chunk_idx(chunk_x, chunk_y+1);
-            match self.chunks[down_idx] {
-                None => {}
-                Some(nt) => {
-                    neighbors += 1;
-                    options.push(self.constraints[nt].compatible_with[0].clone());
-                }
-            }
-        }
-
-        if neighbors == 0 {
-            let new_chunk_idx = (crate::rng::roll_dice(1, self.constraints.len() as i32)-1) as usize;
-            self.chunks[chunk_index] = Some(new_chunk_idx);
-            let left_x = chunk_x as i32 * self.chunk_size as i32;
-            let right_x = (chunk_x as i32+1) * self.chunk_size as i32;
-            let top_y = chunk_y as i32 * self.chunk_size as i32;
-            let bottom_y = (chunk_y as i32+1) * self.chunk_size as i32;
-
-            let mut i : usize = 0;
-            for y in top_y .. bottom_y {
-                for x in left_x .. right_x {
-                    let mapidx = map.xy_idx(x, y);
-                    let tile = self.constraints[new_chunk_idx].pattern[i];
-                    map.tiles[mapidx] = tile;
-                    i += 1;
-                }
-            }
-        }
-        else {
-            let mut options_to_check : HashSet<usize> = HashSet::new();
-            for o in options.iter() {
-                for i in o.iter() {
-                    options_to_check.insert(*i);
-                }
-            }
-
-            let mut possible_options : Vec<usize> = Vec::new();
-            for new_chunk_idx in options_to_check.iter() {
-                let mut possible = true;
-                for o in options.iter() {
-                    if !o.contains(new_chunk_idx) { possible = false; }
-                }
-                if possible {
-                    possible_options.push(*new_chunk_idx);
-                }
-            }
-
-            if possible_options.is_empty() {
-                rltk::console::log("Slepa uliczka, tak sie nie da.");
-                self.possible = false;
-                return true;
-            } else {
-                let new_chunk_idx = if possible_options.len() ==1 { 0 }
-                    else { crate::rng::roll_dice(1, possible_options.len() as i32)-1 };
-
-                self.chunks[chunk_index] = Some(new_chunk_idx as usize);
-                let left_x = chunk_x as i32 * self.chunk_size as i32;
-                let right_x  = (chunk_x as i32+1) * self.chunk_size as i32;
-                let top_y = chunk_y as i32 * self.chunk_size as i32;
-                let bottom_y = (chunk_y as i32+1) * self.chunk_size as i32;
-
-                let mut i : usize = 0;
-                for y in top_y .. bottom_y {
-                    for x in left_x .. right_x {
-                        let mapidx = map.xy_idx(x, y);
-                        let tile = self.constraints[new_chunk_idx as usize].pattern[i];
-                        map.tiles[mapidx] = tile;
-                        i += 1;
-                    }
-                }
-            }
+        let possible_options = self.options_for(chunk_x, chunk_y);
+        if possible_options.is_empty() {
+            return self.backtrack(map);
         }
 
+        self.remaining.remove(remaining_index);
+        self.place(chunk_index, possible_options, map);
         false
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,304 @@
+use std::{fs::{self, File}, io::BufWriter, path::{Path, PathBuf}, collections::BTreeMap, panic::{self, AssertUnwindSafe}};
+use crate::{Map, MapId, Branch, TileType, tile_glyph, raws, rng, seed_from_args, map_builders::{self, BuilderChain}};
+
+const PLAYER_COLOR : (u8, u8, u8) = (255, 201, 14);
+const SPAWN_COLOR : (u8, u8, u8) = (255, 0, 0);
+const MAX_LISTED_PROBLEMS : usize = 4;
+// Smallest map every builder chain can handle, same as the game uses
+const MIN_WIDTH : i32 = 80;
+const MIN_HEIGHT : i32 = 50;
+
+struct MapgenOptions {
+    branch : Branch,
+    depths : (i32, i32),
+    seed : u64,
+    count : u64,
+    width : i32,
+    height : i32,
+    random : bool,
+    history : bool,
+    check : bool,
+    min_walkable : f32,
+    scale : u32,
+    out : PathBuf
+}
+
+fn parse_depths(value : &str) -> Option<(i32, i32)> {
+    match value.split_once('-') {
+        Some((min, max)) => Some((min.parse().ok()?, max.parse().ok()?)),
+        None => value.parse().ok().map(|depth| (depth, depth))
+    }
+}
+
+fn options_from_args(args : &[String]) -> Result<MapgenOptions, String> {
+    let mut options = MapgenOptions{
+        branch : Branch::Main,
+        depths : (1, 1),
+        seed : seed_from_args(args).unwrap_or_else(rng::new_run_seed),
+        count : 1,
+        width : 80,
+        height : 50,
+        random : args.iter().any(|a| a == "--random"),
+        history : args.iter().any(|a| a == "--history"),
+        check : args.iter().any(|a| a == "--check"),
+        min_walkable : 0.1,
+        scale : 4,
+        out : PathBuf::from("mapgen")
+    };
+    let mut i = 0;
+    while i < args.len() {
+        let value = args.get(i+1).map(|v| v.as_str()).unwrap_or("");
+        let bad_value = || format!("invalid value [{}] for {}", value, args[i]);
+        match args[i].as_str() {
+            "--branch" => options.branch = Branch::from_key(value).ok_or_else(bad_value)?,
+            "--depth" => options.depths = parse_depths(value).ok_or_else(bad_value)?,
+            "--count" => options.count = value.parse().map_err(|_| bad_value())?,
+            "--width" => options.width = value.parse().map_err(|_| bad_value())?,
+            "--height" => options.height = value.parse().map_err(|_| bad_value())?,
+            "--scale" => options.scale = value.parse().map_err(|_| bad_value())?,
+            "--min-walkable" => options.min_walkable = value.parse().map_err(|_| bad_value())?,
+            "--out" => options.out = PathBuf::from(value),
+            _ => { i += 1; continue; }
+        }
+        i += 2;
+    }
+    if options.depths.0 > options.depths.1 || options.scale == 0 {
+        return Err("depth range or scale out of bounds".to_string());
+    }
+    if options.width < MIN_WIDTH || options.height < MIN_HEIGHT {
+        return Err(format!("map size {}x{} is below the minimum {}x{}", options.width, options.height, MIN_WIDTH, MIN_HEIGHT));
+    }
+    Ok(options)
+}
+
+fn tile_char(tile : TileType) -> char {
+    match tile {
+        TileType::Wall => '#',
+        TileType::Stalactite => '|',
+        TileType::Stalagmite => '^',
+        TileType::Floor => '.',
+        TileType::DownStairs => '>',
+        TileType::UpStairs => '<',
+        TileType::Road => ':',
+        TileType::Grass => '"',
+        TileType::ShallowWater => '-',
+        TileType::DeepWater => '~',
+        TileType::WoodFloor => ',',
+        TileType::Bridge => '=',
+        TileType::Gravel => ';',
+        TileType::SecretDoor => '+',
+        TileType::Rubble => '%',
+        TileType::Lava => '*',
+        TileType::Chasm => 'v',
+        TileType::Ice => '_'
+    }
+}
+
+fn spawns(builder : &BuilderChain) -> impl Iterator<Item = &(usize, String)> {
+    builder.build_data.spawn_list.iter().filter(|spawn| spawn.1 != "None")
+}
+
+fn map_to_ascii(builder : &BuilderChain) -> String {
+    let map = &builder.build_data.map;
+    let mut rows : Vec<Vec<char>> = map.tiles.chunks(map.width as usize)
+        .map(|row| row.iter().map(|tile| tile_char(*tile)).collect())
+        .collect();
+    if let Some(start) = &builder.build_data.starting_position {
+        rows[start.y as usize][start.x as usize] = '@';
+    }
+
+    let mut text = format!("{} (glebokosc {})\n", map.name, map.depth);
+    for row in rows {
+        text.extend(row);
+        text.push('\n');
+    }
+    for (idx, name) in spawns(builder) {
+        text.push_str(&format!("{},{} {}\n", *idx as i32 % map.width, *idx as i32 / map.width, name));
+    }
+    text
+}
+
+fn write_png(path : &Path, map : &Map, markers : &[(usize, (u8, u8, u8))], scale : u32) -> Result<(), String> {
+    let mut lit = map.clone();
+    lit.visible_tiles.iter_mut().for_each(|v| *v = true);
+    lit.outdoors = true;
+
+    let mut colors : Vec<(u8, u8, u8)> = (0 .. lit.tiles.len()).map(|idx| {
+        let (_, fg, _) = tile_glyph(idx, &lit);
+        ((fg.r * 255.0) as u8, (fg.g * 255.0) as u8, (fg.b * 255.0) as u8)
+    }).collect();
+    for (idx, color) in markers.iter() {
+        colors[*idx] = *color;
+    }
+
+    let (width, height) = (map.width as u32 * scale, map.height as u32 * scale);
+    let mut data = Vec::with_capacity((width * height * 3) as usize);
+    for y in 0 .. height {
+        for x in 0 .. width {
+            let (r, g, b) = colors[((y / scale) * map.width as u32 + x / scale) as usize];
+            data.extend([r, g, b]);
+        }
+    }
+
+    let file = File::create(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), width, height);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+    writer.write_image_data(&data).map_err(|e| e.to_string())
+}
+
+fn chain(options : &MapgenOptions, seed : u64, depth : i32) -> BuilderChain {
+    let id = MapId::new(options.branch, depth);
+    rng::reseed(map_builders::map_seed(seed, id));
+    if options.random {
+        map_builders::random_builder(depth, options.width, options.height)
+    } else {
+        map_builders::level_builder(id, options.width, options.height)
+    }
+}
+
+fn stem(options : &MapgenOptions, seed : u64, depth : i32) -> String {
+    match options.branch {
+        Branch::Main => format!("depth{}_seed{}", depth, seed),
+        branch => format!("{}_depth{}_seed{}", branch.key(), depth, seed)
+    }
+}
+
+fn export(options : &MapgenOptions, builder : &BuilderChain, stem : &str) -> Result<(), String> {
+    let map = &builder.build_data.map;
+    let text_path = options.out.join(format!("{}.txt", stem));
+    fs::write(&text_path, map_to_ascii(builder)).map_err(|e| format!("{}: {}", text_path.display(), e))?;
+
+    let mut markers : Vec<(usize, (u8, u8, u8))> = spawns(builder).map(|(idx, _)| (*idx, SPAWN_COLOR)).collect();
+    if let Some(start) = &builder.build_data.starting_position {
+        markers.push((map.xy_idx(start.x, start.y), PLAYER_COLOR));
+    }
+    write_png(&options.out.join(format!("{}.png", stem)), map, &markers, options.scale)?;
+
+    if options.history {
+        let frames = options.out.join(stem);
+        fs::create_dir_all(&frames).map_err(|e| format!("{}: {}", frames.display(), e))?;
+        for (i, snapshot) in builder.build_data.history.iter().enumerate() {
+            write_png(&frames.join(format!("{:04}.png", i)), snapshot, &[], options.scale)?;
+        }
+    }
+    Ok(())
+}
+
+#[derive(Default)]
+struct BuilderStats {
+    maps : u32,
+    failed : u32,
+    walkable : Vec<f32>,
+    spawns : usize,
+    exit_distances : Vec<f32>
+}
+
+fn panic_message(payload : Box<dyn std::any::Any + Send>) -> String {
+    match payload.downcast::<String>() {
+        Ok(message) => *message,
+        Err(payload) => payload.downcast_ref::<&str>().map(|m| m.to_string()).unwrap_or_default()
+    }
+}
+
+fn average(values : &[f32]) -> f32 {
+    if values.is_empty() { 0.0 } else { values.iter().sum::<f32>() / values.len() as f32 }
+}
+
+fn run_checks(options : &MapgenOptions) -> bool {
+    let mut stats : BTreeMap<&'static str, BuilderStats> = BTreeMap::new();
+    let mut failures : Vec<String> = Vec::new();
+    panic::set_hook(Box::new(|_| {}));
+
+    for depth in options.depths.0 ..= options.depths.1 {
+        for seed in options.seed .. options.seed + options.count {
+            let mut builder = chain(options, seed, depth);
+            let starter = builder.starter_name();
+            let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                builder.build_level();
+                let expect_exit = options.random || map_builders::has_deeper_level(MapId::new(options.branch, depth));
+                map_builders::check_level(&mut builder, options.min_walkable, expect_exit)
+            }));
+            let problems = match result {
+                Ok(report) => {
+                    let entry = stats.entry(starter).or_default();
+                    entry.walkable.push(report.walkable_ratio);
+                    entry.spawns += report.spawns;
+                    entry.exit_distances.extend(report.exit_distance);
+                    report.problems
+                }
+                Err(payload) => vec![format!("panicked: {}", panic_message(payload))]
+            };
+
+            let entry = stats.entry(starter).or_default();
+            entry.maps += 1;
+            if !problems.is_empty() {
+                entry.failed += 1;
+                let mut summary = problems.iter().take(MAX_LISTED_PROBLEMS).cloned().collect::<Vec<String>>().join("; ");
+                if problems.len() > MAX_LISTED_PROBLEMS {
+                    summary.push_str(&format!("; {} more", problems.len() - MAX_LISTED_PROBLEMS));
+                }
+                let branch = if options.branch == Branch::Main { String::new() } else { format!(" --branch {}", options.branch.key()) };
+                failures.push(format!("{} seed {} ({}): {}\n    reproduce with: mapgen --seed {}{} --depth {}{}",
+                    MapId::new(options.branch, depth), seed, starter, summary, seed, branch, depth, if options.random { " --random" } else { "" }));
+            }
+        }
+    }
+    let _ = panic::take_hook();
+
+    println!("{:<28} {:>5} {:>6} {:>20} {:>7} {:>9}", "builder", "maps", "failed", "walkable% min/avg/max", "spawns", "exit dist");
+    for (name, entry) in stats.iter() {
+        let min = entry.walkable.iter().cloned().fold(f32::MAX, f32::min);
+        let max = entry.walkable.iter().cloned().fold(0.0, f32::max);
+        let walkable = if entry.walkable.is_empty() { "-".to_string() } else {
+            format!("{:.0}/{:.0}/{:.0}", min * 100.0, average(&entry.walkable) * 100.0, max * 100.0)
+        };
+        let built = u32::max(entry.walkable.len() as u32, 1);
+        let exit_distance = if entry.exit_distances.is_empty() { "-".to_string() } else {
+            format!("{:.1}", average(&entry.exit_distances))
+        };
+        println!("{:<28} {:>5} {:>6} {:>20} {:>7.1} {:>9}", name, entry.maps, entry.failed, walkable,
+            entry.spawns as f32 / built as f32, exit_distance);
+    }
+
+    for failure in failures.iter() {
+        println!("FAILED {}", failure);
+    }
+    let total : u32 = stats.values().map(|entry| entry.maps).sum();
+    println!("{} of {} map(s) failed the checks", failures.len(), total);
+    failures.is_empty()
+}
+
+pub fn run_from_args(args : &[String]) {
+    let options = match options_from_args(args) {
+        Ok(options) => options,
+        Err(e) => { eprintln!("Mapgen error: {}", e); std::process::exit(1); }
+    };
+
+    raws::load_raws();
+    if options.check {
+        let passed = run_checks(&options);
+        std::process::exit(if passed { 0 } else { 1 });
+    }
+
+    if let Err(e) = fs::create_dir_all(&options.out) {
+        eprintln!("Mapgen error: {}: {}", options.out.display(), e);
+        std::process::exit(1);
+    }
+    map_builders::set_record_history(options.history);
+    for seed in options.seed .. options.seed + options.count {
+        for depth in options.depths.0 ..= options.depths.1 {
+            let mut builder = chain(&options, seed, depth);
+            builder.build_level();
+            let stem = stem(&options, seed, depth);
+            if let Err(e) = export(&options, &builder, &stem) {
+                eprintln!("Mapgen error: {}", e);
+                std::process::exit(1);
+            }
+            println!("depth {} seed {}: {}, {} spawns, {} frames -> {}",
+                depth, seed, builder.build_data.map.name, spawns(&builder).count(), builder.build_data.history.len(),
+                options.out.join(stem).display());
+        }
+    }
+}
@@ -2,9 +2,14 @@ use rltk::{VirtualKeyCode, Rltk};
 use specs::prelude::*;
 use super::{Position, Player, Map, State, Viewshed, RunState, Point, Item, WantsToCastSpell, WantsToShoot, raws::find_spell_entity,
             Pools, WantsToMelee, WantsToPickupItem, TileType, HungerClock, HungerState, EntityMoved, Equipped, Weapon,
-            Door, BlocksVisibility, BlocksTile, Renderable, Faction, raws::Reaction, Vendor, VendorMode, Target, Name, CHEATS_ALLOWED};
+            Door, BlocksVisibility, BlocksTile, Renderable, Faction, raws::Reaction, Vendor, VendorMode, Target, Name, CHEATS_ALLOWED,
+            Attributes, Skills, Skill, Hidden, Digs, tile_hardness, gamesystem::skill_bonus, effects::{add_effect, EffectType, Targets},
+            EquipmentSlot, Magazine, Ammunition, InBackpack, Reloading, Maneuver, Initiative, combat};
 use std::cmp::{min, max};
 
+const DOOR_NOISE : i32 = 5;
+const RUNNING_NOISE : i32 = 8;
+const RUNNING_SPEEDUP : i32 = 3;
 
 pub fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) -> RunState {
     let mut positions = ecs.write_storage::<Position>();
@@ -21,6 +26,8 @@ pub fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) -> RunState
     let mut renderables = ecs.write_storage::<Renderable>();
     let factions = ecs.read_storage::<Faction>();
     let vendors = ecs.read_storage::<Vendor>();
+    let equipped = ecs.read_storage::<Equipped>();
+    let diggers = ecs.read_storage::<Digs>();
     let mut result = RunState::AwaitingInput;
 
     let mut swap_entities : Vec<(Entity, i32, i32)> = Vec::new();
@@ -47,9 +54,10 @@ pub fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) -> RunState
             if !hostile {
                 swap_entities.push((potential_target, pos.x, pos.y));
 
+                let from = map.xy_idx(pos.x, pos.y);
                 pos.x = min(map.width-1, max(0, pos.x + delta_x));
                 pos.y = min(map.height-1, max(0, pos.y + delta_y));
-                entity_moved.insert(entity, EntityMoved{}).expect("Unable to insert marker");
+                entity_moved.insert(entity, EntityMoved{ from }).expect("Unable to insert marker");
 
                 viewshed.dirty = true;
                 let mut ppos = ecs.write_resource::<Point>();
@@ -71,17 +79,19 @@ pub fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) -> RunState
                 let glyph = renderables.get_mut(potential_target).unwrap();
                 glyph.glyph = rltk::to_cp437('/');
                 viewshed.dirty = true;
+                add_effect(Some(entity), EffectType::Noise{ volume: DOOR_NOISE }, Targets::Tile{ tile_idx: destination_idx as i32 });
                 return Some(RunState::Ticking);
             }
             None
         });
 
-        if !crate::spatial::is_blocked(destination_idx) {
+        // Monsters slip through secret doors, the player has to find them first
+        if !crate::spatial::is_blocked(destination_idx) && map.tiles[destination_idx] != TileType::SecretDoor {
             let old_idx = map.xy_idx(pos.x, pos.y);
             pos.x = min(map.width-1, max(0, pos.x + delta_x));
             pos.y = min(map.height-1, max(0, pos.y + delta_y));
             let new_idx = map.xy_idx(pos.x, pos.y);
-            entity_moved.insert(entity, EntityMoved{}).expect("Unable to insert marker");
+            entity_moved.insert(entity, EntityMoved{ from: old_idx }).expect("Unable to insert marker");
             crate::spatial::move_entity(entity, old_idx, new_idx);
 
             viewshed.dirty = true;
@@ -94,6 +104,16 @@ pub fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) -> RunState
                 TileType::UpStairs => result = RunState::PreviousLevel,
                 _ => {}
             }
+        } else if result == RunState::AwaitingInput && tile_hardness(map.tiles[destination_idx]).is_some() {
+            // Walking into rock with a pick in hand digs at it
+            let power = (&equipped, &diggers).join()
+                .filter(|(equipped, _)| equipped.owner == entity)
+                .map(|(_, digs)| digs.power)
+                .max();
+            if let Some(power) = power {
+                add_effect(Some(entity), EffectType::Dig{ power }, Targets::Tile{ tile_idx: destination_idx as i32 });
+                result = RunState::Ticking;
+            }
         }
     }
 
@@ -113,6 +133,22 @@ pub fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) -> RunState
 }
 
 
+// Running gets the player going again sooner, but everything nearby hears the footsteps
+pub fn run_player(delta_x: i32, delta_y: i32, ecs: &mut World) -> RunState {
+    let from = *ecs.fetch::<Point>();
+    let result = try_move_player(delta_x, delta_y, ecs);
+    let to = *ecs.fetch::<Point>();
+    if result == RunState::Ticking && from != to {
+        let player_entity = *ecs.fetch::<Entity>();
+        if let Some(initiative) = ecs.write_storage::<Initiative>().get_mut(player_entity) {
+            initiative.current = max(1, initiative.current - RUNNING_SPEEDUP);
+        }
+        let tile_idx = ecs.fetch::<Map>().xy_idx(to.x, to.y) as i32;
+        add_effect(Some(player_entity), EffectType::Noise{ volume: RUNNING_NOISE }, Targets::Tile{ tile_idx });
+    }
+    result
+}
+
 pub fn skip_turn(ecs: &mut World) -> RunState {
     let player_entity = ecs.fetch::<Entity>();
     let viewshed_components = ecs.read_storage::<Viewshed>();
@@ -212,119 +248,458 @@ fn get_item(ecs: &mut World) {
     }
 }
 
+const SEARCH_RADIUS : i32 = 2;
+const SEARCH_DIFFICULTY : i32 = 15;
+
+// Every secret door and hidden thing close by gets its own roll against the player's wits
+fn search(ecs: &mut World) -> RunState {
+    let player_pos = *ecs.fetch::<Point>();
+    let player_entity = *ecs.fetch::<Entity>();
+    let map = ecs.fetch::<Map>();
+    let attributes = ecs.read_storage::<Attributes>();
+    let skills = ecs.read_storage::<Skills>();
+    let names = ecs.read_storage::<Name>();
+    let mut hidden = ecs.write_storage::<Hidden>();
+
+    let mut bonus = skills.get(player_entity).map(|skills| skill_bonus(Skill::Search, skills)).unwrap_or(0);
+    if let Some(attributes) = attributes.get(player_entity) {
+        bonus += attributes.intelligence.bonus;
+    }
+    let mut found = false;
+    for y in player_pos.y - SEARCH_RADIUS ..= player_pos.y + SEARCH_RADIUS {
+        for x in player_pos.x - SEARCH_RADIUS ..= player_pos.x + SEARCH_RADIUS {
+            if x < 1 || x > map.width-2 || y < 1 || y > map.height-2 { continue; }
+            let idx = map.xy_idx(x, y);
+            if map.tiles[idx] == TileType::SecretDoor && crate::rng::roll_dice(1, 20) + bonus >= SEARCH_DIFFICULTY {
+                add_effect(Some(player_entity), EffectType::RevealSecretDoor, Targets::Tile{ tile_idx: idx as i32 });
+                found = true;
+            }
+            crate::spatial::for_each_tile_content(idx, |entity| {
+                if hidden.get(entity).is_some() && crate::rng::roll_dice(1, 20) + bonus >= SEARCH_DIFFICULTY {
+                    if let Some(name) = names.get(entity) {
+                        crate::gamelog::Logger::new()
+                            .append("Wieclaw zauwazyl:")
+                            .color(rltk::RED)
+                            .append(format!("{}.", name.name))
+                            .log();
+                    }
+                    hidden.remove(entity);
+                    found = true;
+                }
+            });
+        }
+    }
+    if !found {
+        crate::gamelog::Logger::new().append("Wieclaw niczego nie znalazl.").log();
+    }
+    RunState::Ticking
+}
+
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum Cheat { TeleportToExit, Heal, RevealMap, GodMode, ReloadRaws }
+
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum PlayerCommand {
+    Move { dx: i32, dy: i32 },
+    Run { dx: i32, dy: i32 },
+    Wait,
+    PickUp,
+    UseConsumable { slot: i32, target: Option<Point> },
+    CastSpell { slot: i32, target: Option<Point> },
+    CycleTarget,
+    Fire,
+    Reload,
+    Search,
+    NextLevel,
+    PreviousLevel,
+    UseItem { item: u32 },
+    DropItem { item: u32 },
+    RemoveItem { item: u32 },
+    SelectTarget { target: Point },
+    Maneuver { maneuver: Maneuver, target: Point },
+    SellItem { item: u32 },
+    BuyItem { index: i32 },
+    RemoveCurse { item: u32 },
+    Identify { item: u32 },
+    Cheat { cheat: Cheat },
+    Cancel
+}
+
+fn hotkey_number(key : VirtualKeyCode) -> Option<i32> {
+    match key {
+        VirtualKeyCode::Key1 => Some(1),
+        VirtualKeyCode::Key2 => Some(2),
+        VirtualKeyCode::Key3 => Some(3),
+        VirtualKeyCode::Key4 => Some(4),
+        VirtualKeyCode::Key5 => Some(5),
+        VirtualKeyCode::Key6 => Some(6),
+        VirtualKeyCode::Key7 => Some(7),
+        VirtualKeyCode::Key8 => Some(8),
+        VirtualKeyCode::Key9 => Some(9),
+        _ => None
+    }
+}
 
 pub fn player_input(gs: &mut State, ctx: &mut Rltk) -> RunState {
-    if ctx.shift && ctx.key.is_some() {
-        let key : Option<i32> =
-            match ctx.key.unwrap() {
-                VirtualKeyCode::Key1 => Some(1),
-                VirtualKeyCode::Key2 => Some(2),
-                VirtualKeyCode::Key3 => Some(3),
-                VirtualKeyCode::Key4 => Some(4),
-                VirtualKeyCode::Key5 => Some(5),
-                VirtualKeyCode::Key6 => Some(6),
-                VirtualKeyCode::Key7 => Some(7),
-                VirtualKeyCode::Key8 => Some(8),
-                VirtualKeyCode::Key9 => Some(9),
-                _ => None
-            };
-        if let Some(key) = key {
-            return use_consumable_hotkey(gs, key-1);
-        }
-    }
-    if ctx.control && ctx.key.is_some() {
-        let key : Option<i32> =
-            match ctx.key.unwrap() {
-                VirtualKeyCode::Key1 => Some(1),
-                VirtualKeyCode::Key2 => Some(2),
-                VirtualKeyCode::Key3 => Some(3),
-                VirtualKeyCode::Key4 => Some(4),
-                VirtualKeyCode::Key5 => Some(5),
-                VirtualKeyCode::Key6 => Some(6),
-                VirtualKeyCode::Key7 => Some(7),
-                VirtualKeyCode::Key8 => Some(8),
-                VirtualKeyCode::Key9 => Some(9),
-                _ => None
-            };
-        if let Some(key) = key {
-            return use_spell_hotkey(gs, key-1);
+    if let Some(key) = ctx.key {
+        if ctx.shift {
+            if let Some(n) = hotkey_number(key) {
+                return execute_command(gs, PlayerCommand::UseConsumable{ slot: n-1, target: None });
+            }
+        }
+        if ctx.control {
+            if let Some(n) = hotkey_number(key) {
+                return execute_command(gs, PlayerCommand::CastSpell{ slot: n-1, target: None });
+            }
         }
     }
-    match ctx.key {
+    let command = match ctx.key {
         None => { return RunState::AwaitingInput }
         Some(key) => match key {
-            VirtualKeyCode::Left | 
+            VirtualKeyCode::Left |
             VirtualKeyCode::Numpad4 |
-            VirtualKeyCode::H => return try_move_player(-1, 0, &mut gs.ecs),
+            VirtualKeyCode::H => PlayerCommand::Move{ dx: -1, dy: 0 },
 
             VirtualKeyCode::Right |
             VirtualKeyCode::Numpad6 |
-            VirtualKeyCode::L => return try_move_player(1, 0, &mut gs.ecs),
+            VirtualKeyCode::L => PlayerCommand::Move{ dx: 1, dy: 0 },
 
             VirtualKeyCode::Up |
             VirtualKeyCode::Numpad8 |
-            VirtualKeyCode::K => return try_move_player(0, -1, &mut gs.ecs),
+            VirtualKeyCode::K => PlayerCommand::Move{ dx: 0, dy: -1 },
 
             VirtualKeyCode::Down |
             VirtualKeyCode::Numpad2 |
-            VirtualKeyCode::J => return try_move_player(0, 1, &mut gs.ecs),
+            VirtualKeyCode::J => PlayerCommand::Move{ dx: 0, dy: 1 },
 
             VirtualKeyCode::Numpad9 |
-            VirtualKeyCode::Y => return try_move_player(1, -1, &mut gs.ecs),
+            VirtualKeyCode::Y => PlayerCommand::Move{ dx: 1, dy: -1 },
 
             VirtualKeyCode::Numpad7 |
-            VirtualKeyCode::U => return try_move_player(-1, -1, &mut gs.ecs),
+            VirtualKeyCode::U => PlayerCommand::Move{ dx: -1, dy: -1 },
 
             VirtualKeyCode::Numpad3 |
-            VirtualKeyCode::N => return try_move_player(1, 1, &mut gs.ecs),
+            VirtualKeyCode::N => PlayerCommand::Move{ dx: 1, dy: 1 },
 
             VirtualKeyCode::Numpad1 |
-            VirtualKeyCode::B => return try_move_player(-1, 1, &mut gs.ecs),
+            VirtualKeyCode::B => PlayerCommand::Move{ dx: -1, dy: 1 },
 
-            VirtualKeyCode::G => get_item(&mut gs.ecs),
+            VirtualKeyCode::G => PlayerCommand::PickUp,
 
             VirtualKeyCode::I => return RunState::ShowInventory,
 
             VirtualKeyCode::D => return RunState::ShowDropItem,
 
             VirtualKeyCode::Escape => return RunState::SaveGame,
-            
+
             VirtualKeyCode::Backslash => {
                 if CHEATS_ALLOWED {
                     return RunState::ShowCheatMenu;
                 }
+                return RunState::Ticking;
             }
 
-            VirtualKeyCode::Period => {
-                if try_next_level(&mut gs.ecs) {
-                    return RunState::NextLevel;
-                }
-            }
+            VirtualKeyCode::Period => PlayerCommand::NextLevel,
 
-            VirtualKeyCode::Comma => {
-                if try_previous_level(&mut gs.ecs) {
-                    return RunState::PreviousLevel;
-                }
-            }
+            VirtualKeyCode::Comma => PlayerCommand::PreviousLevel,
 
             VirtualKeyCode::Numpad5 |
-            VirtualKeyCode::Space => return skip_turn(&mut gs.ecs),
+            VirtualKeyCode::Space => PlayerCommand::Wait,
 
             VirtualKeyCode::R => return RunState::ShowRemoveItem,
 
-            VirtualKeyCode::V => {
-                cycle_target(&mut gs.ecs);
-                return RunState::AwaitingInput;
-            }
-            VirtualKeyCode::F => return fire_on_target(&mut gs.ecs),
+            VirtualKeyCode::V => PlayerCommand::CycleTarget,
+
+            VirtualKeyCode::F => PlayerCommand::Fire,
+
+            VirtualKeyCode::P => PlayerCommand::Reload,
+
+            VirtualKeyCode::S => PlayerCommand::Search,
+
+            VirtualKeyCode::M => return RunState::ShowCombatMenu,
 
             _ => { return RunState::AwaitingInput }
         },
+    };
+    let command = match command {
+        PlayerCommand::Move{ dx, dy } if ctx.shift => PlayerCommand::Run{ dx, dy },
+        command => command
+    };
+    execute_command(gs, command)
+}
+
+pub fn execute_command(gs: &mut State, command: PlayerCommand) -> RunState {
+    if let Some(recorder) = &mut gs.recorder {
+        recorder.record(&command);
+    }
+
+    match command {
+        PlayerCommand::Move{ dx, dy } => try_move_player(dx, dy, &mut gs.ecs),
+        PlayerCommand::Run{ dx, dy } => run_player(dx, dy, &mut gs.ecs),
+        PlayerCommand::Wait => skip_turn(&mut gs.ecs),
+        PlayerCommand::PickUp => {
+            get_item(&mut gs.ecs);
+            RunState::Ticking
+        }
+        PlayerCommand::UseConsumable{ slot, target } => use_consumable_hotkey(gs, slot, target),
+        PlayerCommand::CastSpell{ slot, target } => use_spell_hotkey(gs, slot, target),
+        PlayerCommand::CycleTarget => {
+            cycle_target(&mut gs.ecs);
+            RunState::AwaitingInput
+        }
+        PlayerCommand::Fire => fire_on_target(&mut gs.ecs),
+        PlayerCommand::Reload => reload_weapon(&mut gs.ecs),
+        PlayerCommand::Search => search(&mut gs.ecs),
+        PlayerCommand::NextLevel => {
+            if try_next_level(&mut gs.ecs) {
+                RunState::NextLevel
+            } else {
+                RunState::Ticking
+            }
+        }
+        PlayerCommand::PreviousLevel => {
+            if try_previous_level(&mut gs.ecs) {
+                RunState::PreviousLevel
+            } else {
+                RunState::Ticking
+            }
+        }
+        PlayerCommand::UseItem{ item } => {
+            match player_item(&gs.ecs, item) {
+                Some(item) => use_item(gs, item),
+                None => RunState::AwaitingInput
+            }
+        }
+        PlayerCommand::DropItem{ item } => {
+            use super::WantsToDropItem;
+            match player_item(&gs.ecs, item) {
+                Some(item) => {
+                    let mut intent = gs.ecs.write_storage::<WantsToDropItem>();
+                    intent.insert(*gs.ecs.fetch::<Entity>(), WantsToDropItem{ item }).expect("Unable to insert intent");
+                    RunState::Ticking
+                }
+                None => RunState::AwaitingInput
+            }
+        }
+        PlayerCommand::RemoveItem{ item } => {
+            use super::WantsToRemoveItem;
+            match player_item(&gs.ecs, item) {
+                Some(item) => {
+                    let mut intent = gs.ecs.write_storage::<WantsToRemoveItem>();
+                    intent.insert(*gs.ecs.fetch::<Entity>(), WantsToRemoveItem{ item }).expect("Unable to insert intent");
+                    RunState::Ticking
+                }
+                None => RunState::AwaitingInput
+            }
+        }
+        PlayerCommand::SelectTarget{ target } => select_target(gs, target),
+        PlayerCommand::Maneuver{ maneuver, target } => try_maneuver(&mut gs.ecs, maneuver, target),
+        PlayerCommand::SellItem{ item } => {
+            match player_item(&gs.ecs, item) {
+                Some(item) => sell_item(gs, item),
+                None => *gs.ecs.fetch::<RunState>()
+            }
+        }
+        PlayerCommand::BuyItem{ index } => buy_item(gs, index),
+        PlayerCommand::RemoveCurse{ item } => {
+            use super::CursedItem;
+            // Only with the scroll just read, and only on the player's own things
+            let runstate = *gs.ecs.fetch::<RunState>();
+            if runstate != RunState::ShowRemoveCurse { return runstate; }
+            match player_item(&gs.ecs, item) {
+                Some(item) => {
+                    gs.ecs.write_storage::<CursedItem>().remove(item);
+                    RunState::Ticking
+                }
+                None => runstate
+            }
+        }
+        PlayerCommand::Identify{ item } => {
+            use super::MasterDungeonMap;
+            let runstate = *gs.ecs.fetch::<RunState>();
+            if runstate != RunState::ShowIdentify { return runstate; }
+            match player_item(&gs.ecs, item) {
+                Some(item) => {
+                    if let Some(name) = gs.ecs.read_storage::<Name>().get(item) {
+                        let mut dm = gs.ecs.fetch_mut::<MasterDungeonMap>();
+                        dm.identified_items.insert(name.name.clone());
+                    }
+                    RunState::Ticking
+                }
+                None => runstate
+            }
+        }
+        PlayerCommand::Cheat{ cheat } => {
+            if CHEATS_ALLOWED {
+                use_cheat(gs, cheat)
+            } else {
+                RunState::AwaitingInput
+            }
+        }
+        PlayerCommand::Cancel => RunState::AwaitingInput
+    }
+}
+
+fn use_cheat(gs: &mut State, cheat: Cheat) -> RunState {
+    match cheat {
+        Cheat::TeleportToExit => {
+            let destination = {
+                let map = gs.ecs.fetch::<Map>();
+                map.tiles.iter().position(|tile| *tile == TileType::DownStairs)
+                    .and_then(|exit_idx| map.stairs_destination(exit_idx))
+                    .unwrap_or(map.id().below())
+            };
+            gs.goto_map(destination);
+            gs.mapgen_next_state = Some(RunState::PreRun);
+            return RunState::MapGeneration;
+        }
+        Cheat::Heal => {
+            let player = gs.ecs.fetch::<Entity>();
+            let mut pools = gs.ecs.write_storage::<Pools>();
+            let player_pools = pools.get_mut(*player).unwrap();
+            player_pools.hit_points.current = player_pools.hit_points.max;
+        }
+        Cheat::RevealMap => {
+            let mut map = gs.ecs.fetch_mut::<Map>();
+            for v in map.revealed_tiles.iter_mut() {
+                *v = true;
+            }
+        }
+        Cheat::GodMode => {
+            let player = gs.ecs.fetch::<Entity>();
+            let mut pools = gs.ecs.write_storage::<Pools>();
+            let player_pools = pools.get_mut(*player).unwrap();
+            player_pools.god_mode = true;
+        }
+        Cheat::ReloadRaws => gs.reload_game_data()
+    }
+    RunState::AwaitingInput
+}
+
+fn entity_from_id(ecs: &World, id: u32) -> Option<Entity> {
+    let entities = ecs.entities();
+    let entity = entities.entity(id);
+    if entities.is_alive(entity) {
+        Some(entity)
+    } else {
+        None
+    }
+}
+
+fn player_item(ecs: &World, id: u32) -> Option<Entity> {
+    use super::InBackpack;
+
+    let item = entity_from_id(ecs, id)?;
+    let player_entity = *ecs.fetch::<Entity>();
+    let carried = ecs.read_storage::<InBackpack>().get(item).is_some_and(|b| b.owner == player_entity);
+    let worn = ecs.read_storage::<Equipped>().get(item).is_some_and(|e| e.owner == player_entity);
+    if carried || worn {
+        Some(item)
+    } else {
+        None
+    }
+}
+
+fn use_item(gs: &mut State, item: Entity) -> RunState {
+    use super::{Ranged, WantsToUseItem};
+
+    if let Some(ranged) = gs.ecs.read_storage::<Ranged>().get(item) {
+        return RunState::ShowTargeting{ range: ranged.range, item };
+    }
+    let mut intent = gs.ecs.write_storage::<WantsToUseItem>();
+    intent.insert(*gs.ecs.fetch::<Entity>(), WantsToUseItem { item, target: None }).expect("Unable to insert intent");
+    RunState::Ticking
+}
+
+fn select_target(gs: &mut State, target: Point) -> RunState {
+    use super::{SpellTemplate, WantsToUseItem};
+
+    let item = match *gs.ecs.fetch::<RunState>() {
+        RunState::ShowTargeting{ item, .. } => item,
+        _ => return RunState::AwaitingInput
+    };
+    let player_entity = *gs.ecs.fetch::<Entity>();
+    if gs.ecs.read_storage::<SpellTemplate>().get(item).is_some() {
+        let mut intent = gs.ecs.write_storage::<WantsToCastSpell>();
+        intent.insert(player_entity, WantsToCastSpell{ spell: item, target: Some(target) }).expect("Unable to insert intent");
+    } else {
+        let mut intent = gs.ecs.write_storage::<WantsToUseItem>();
+        intent.insert(player_entity, WantsToUseItem { item, target: Some(target) }).expect("Unable to insert intent");
     }
     RunState::Ticking
 }
 
-fn use_consumable_hotkey(gs: &mut State, key: i32) -> RunState {
+// Maneuvers only reach creatures standing right next to Wieclaw
+fn try_maneuver(ecs: &mut World, maneuver: Maneuver, target: Point) -> RunState {
+    use super::WantsToManeuver;
+    let player_entity = *ecs.fetch::<Entity>();
+    let player_pos = *ecs.fetch::<Point>();
+    let map = ecs.fetch::<Map>();
+    let in_reach = rltk::DistanceAlg::Pythagoras.distance2d(player_pos, target) < 1.5
+        && target.x >= 0 && target.x < map.width && target.y >= 0 && target.y < map.height;
+    let victim = if in_reach {
+        let pools = ecs.read_storage::<Pools>();
+        crate::spatial::get_tile_content_clone(map.xy_idx(target.x, target.y)).into_iter()
+            .find(|entity| *entity != player_entity && pools.get(*entity).is_some())
+    } else {
+        None
+    };
+    match victim {
+        Some(victim) => {
+            ecs.write_storage::<WantsToManeuver>().insert(player_entity, WantsToManeuver{ maneuver, target: victim }).expect("Unable to insert intent");
+            RunState::Ticking
+        }
+        None => {
+            crate::gamelog::Logger::new().append("Nikogo tam nie ma.").log();
+            RunState::AwaitingInput
+        }
+    }
+}
+
+fn sell_item(gs: &mut State, item: Entity) -> RunState {
+    let runstate = *gs.ecs.fetch::<RunState>();
+    if let RunState::ShowVendor{ .. } = runstate {
+        let price = gs.ecs.read_storage::<Item>().get(item).unwrap().base_value * 0.8;
+        gs.ecs.write_storage::<Pools>().get_mut(*gs.ecs.fetch::<Entity>()).unwrap().gold += price;
+        gs.ecs.delete_entity(item).expect("Unable to delete");
+    }
+    runstate
+}
+
+fn buy_item(gs: &mut State, index: i32) -> RunState {
+    use super::IdentifiedItem;
+    use crate::raws::*;
+
+    let runstate = *gs.ecs.fetch::<RunState>();
+    let vendor = match runstate {
+        RunState::ShowVendor{ vendor, .. } => vendor,
+        _ => return runstate
+    };
+    let inventory = get_vendor_items(&gs.ecs.read_storage::<Vendor>().get(vendor).unwrap().categories, &RAWS.lock().unwrap());
+    if index < 0 || index as usize >= inventory.len() {
+        return runstate;
+    }
+    let (tag, price) = inventory[index as usize].clone();
+
+    let player_entity = *gs.ecs.fetch::<Entity>();
+    gs.ecs.write_storage::<IdentifiedItem>().insert(player_entity, IdentifiedItem{ name: tag.clone() }).expect("Unable to insert");
+    let can_afford = {
+        let mut pools = gs.ecs.write_storage::<Pools>();
+        let player_pools = pools.get_mut(player_entity).unwrap();
+        if player_pools.gold >= price {
+            player_pools.gold -= price;
+            true
+        } else {
+            false
+        }
+    };
+    if can_afford {
+        spawn_named_item(&RAWS.lock().unwrap(), &mut gs.ecs, &tag, SpawnType::Carried{ by: player_entity });
+    }
+    runstate
+}
+
+fn use_consumable_hotkey(gs: &mut State, key: i32, target: Option<Point>) -> RunState {
     use super::{Consumable, InBackpack, WantsToUseItem};
 
     let consumables = gs.ecs.read_storage::<Consumable>();
@@ -341,19 +716,21 @@ fn use_consumable_hotkey(gs: &mut State, key: i32) -> RunState {
     if (key as usize) < carried_consumables.len() {
         use crate::components::Ranged;
         if let Some(ranged) = gs.ecs.read_storage::<Ranged>().get(carried_consumables[key as usize]) {
-            return RunState::ShowTargeting{ range: ranged.range, item: carried_consumables[key as usize] };
+            if target.is_none() {
+                return RunState::ShowTargeting{ range: ranged.range, item: carried_consumables[key as usize] };
+            }
         }
         let mut intent = gs.ecs.write_storage::<WantsToUseItem>();
         intent.insert(
             *player_entity,
-            WantsToUseItem {item: carried_consumables[key as usize], target: None }
+            WantsToUseItem {item: carried_consumables[key as usize], target }
         ).expect("Unable to insert intent");
         return RunState::Ticking;
     }
     RunState::Ticking
 }
 
-fn use_spell_hotkey(gs: &mut State, key: i32) -> RunState {
+fn use_spell_hotkey(gs: &mut State, key: i32, target: Option<Point>) -> RunState {
     use super::KnownSpells;
 
     let player_entity = gs.ecs.fetch::<Entity>();
@@ -367,12 +744,14 @@ fn use_spell_hotkey(gs: &mut State, key: i32) -> RunState {
             if let Some(spell_entity) = find_spell_entity(&gs.ecs, &known_spells[key as usize].display_name) {
                 use crate::components::Ranged;
                 if let Some(ranged) = gs.ecs.read_storage::<Ranged>().get(spell_entity) {
-                    return RunState::ShowTargeting { range: ranged.range, item: spell_entity };
+                    if target.is_none() {
+                        return RunState::ShowTargeting { range: ranged.range, item: spell_entity };
+                    }
                 };
                 let mut intent = gs.ecs.write_storage::<WantsToCastSpell>();
                 intent.insert(
                     *player_entity,
-                    WantsToCastSpell{ spell: spell_entity, target: None }
+                    WantsToCastSpell{ spell: spell_entity, target }
                 ).expect("Unable to insert intent");
                 return RunState::Ticking;
             }
@@ -468,6 +847,10 @@ fn fire_on_target(ecs: &mut World) -> RunState {
 
     if let Some(target) = current_target {
         let player_entity = ecs.fetch::<Entity>();
+        if ready_magazine(ecs, *player_entity).is_some_and(|m| m.loaded < 1) {
+            crate::gamelog::Logger::new().append("Bron Wieclawa nie jest nabita (P - przeladuj).").log();
+            return RunState::AwaitingInput;
+        }
         let mut shoot_store = ecs.write_storage::<WantsToShoot>();
         let names = ecs.read_storage::<Name>();
         if let Some(name) = names.get(target) {
@@ -483,4 +866,45 @@ fn fire_on_target(ecs: &mut World) -> RunState {
         crate::gamelog::Logger::new().append("Wieclaw nie ma do kogo strzelac!").log();
         RunState::AwaitingInput
     }
+}
+
+fn ready_magazine(ecs: &World, owner: Entity) -> Option<Magazine> {
+    let equipped = ecs.read_storage::<Equipped>();
+    let magazines = ecs.read_storage::<Magazine>();
+    (&equipped, &magazines).join()
+        .find(|(e, _)| e.owner == owner && e.slot == EquipmentSlot::Ranged)
+        .map(|(_, magazine)| magazine.clone())
+}
+
+fn reload_weapon(ecs: &mut World) -> RunState {
+    let player_entity = *ecs.fetch::<Entity>();
+    let entities = ecs.entities();
+    let equipped = ecs.read_storage::<Equipped>();
+    let mut magazines = ecs.write_storage::<Magazine>();
+    let backpack = ecs.read_storage::<InBackpack>();
+    let mut ammunition = ecs.write_storage::<Ammunition>();
+    let mut reloading = ecs.write_storage::<Reloading>();
+
+    let magazine = (&equipped, &mut magazines).join()
+        .find(|(e, _)| e.owner == player_entity && e.slot == EquipmentSlot::Ranged)
+        .map(|(_, magazine)| magazine);
+    match magazine {
+        None => {
+            crate::gamelog::Logger::new().append("Wieclaw nie ma czego przeladowac.").log();
+            RunState::AwaitingInput
+        }
+        Some(magazine) if magazine.loaded >= magazine.capacity => {
+            crate::gamelog::Logger::new().append("Bron Wieclawa jest juz nabita.").log();
+            RunState::AwaitingInput
+        }
+        Some(magazine) => {
+            if combat::reload(player_entity, magazine, &entities, &backpack, &mut ammunition, &mut reloading) > 0 {
+                crate::gamelog::Logger::new().append("Wieclaw przeladowuje bron.").log();
+                RunState::Ticking
+            } else {
+                crate::gamelog::Logger::new().append("Wieclawowi skonczyla sie amunicja.").log();
+                RunState::AwaitingInput
+            }
+        }
+    }
 }
\ No newline at end of file
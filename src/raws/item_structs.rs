@@ -14,7 +14,10 @@ pub struct Item {
     pub vendor_category : Option<String>,
     pub magic : Option<MagicItem>,
     pub attributes : Option<ItemAttributeBonus>,
-    pub template_magic : Option<ItemMagicTemplate>
+    pub template_magic : Option<ItemMagicTemplate>,
+    pub digging : Option<i32>,
+    pub ammunition : Option<Ammunition>,
+    pub resistances : Option<HashMap<String, String>>
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -41,7 +44,25 @@ pub struct Weapon {
     pub hit_bonus: i32,
     pub proc_chance: Option<f32>,
     pub proc_target: Option<String>,
-    pub proc_effects: Option<HashMap<String, String>>
+    pub proc_effects: Option<HashMap<String, String>>,
+    pub ammo: Option<WeaponAmmo>,
+    pub damage_type: Option<String>,
+    pub crit_range: Option<i32>,
+    pub crit_multiplier: Option<i32>
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct WeaponAmmo {
+    pub kind: String,
+    pub capacity: i32,
+    pub reload_turns: i32
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Ammunition {
+    pub kind: String,
+    pub count: i32,
+    pub recovery: Option<f32>
 }
 
 #[derive(Deserialize, Debug, Clone)]
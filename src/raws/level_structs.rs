@@ -0,0 +1,26 @@
+use serde::{Deserialize};
+use std::collections::HashMap;
+use crate::map::{Theme, Branch};
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct LevelDefinition {
+    pub name : String,
+    pub map_name : String,
+    #[serde(default)]
+    pub branch : Branch,
+    pub min_depth : i32,
+    pub max_depth : i32,
+    #[serde(default)]
+    pub theme : Theme,
+    pub initial : BuilderStep,
+    #[serde(default)]
+    pub meta : Vec<BuilderStep>,
+    pub spawn_table : Option<String>
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct BuilderStep {
+    pub builder : String,
+    #[serde(default)]
+    pub params : HashMap<String, String>
+}
@@ -16,6 +16,7 @@ pub struct Mob {
     pub hp : Option<i32>,
     pub mana : Option<i32>,
     pub equipped : Option<Vec<String>>,
+    pub carried : Option<Vec<String>>,
     pub natural : Option<MobNatural>,
     pub loot_table : Option<String>,
     pub light : Option<MobLight>,
@@ -24,7 +25,9 @@ pub struct Mob {
     pub vendor : Option<Vec<String>>,
     pub abilities : Option<Vec<MobAbility>>,
     pub on_death : Option<Vec<MobAbility>>,
-    pub stationary : Option<bool>
+    pub stationary : Option<bool>,
+    pub resistances : Option<HashMap<String, String>>,
+    pub maneuvers : Option<Vec<MobManeuver>>
 }
 
 #[derive(Deserialize, Debug)]
@@ -45,7 +48,8 @@ pub struct MobNatural {
 pub struct NaturalAttack {
     pub name : String,
     pub hit_bonus : i32,
-    pub damage : String
+    pub damage : String,
+    pub damage_type : Option<String>
 }
 
 #[derive(Deserialize, Debug)]
@@ -60,4 +64,9 @@ pub struct MobAbility {
     pub chance : f32,
     pub range : f32,
     pub min_range : f32
-}
\ No newline at end of file
+}
+#[derive(Deserialize, Debug)]
+pub struct MobManeuver {
+    pub name : String,
+    pub chance : f32
+}
@@ -10,28 +10,43 @@ mod spawn_table_structs;
 use spawn_table_structs::*;
 mod loot_structs;
 use loot_structs::*;
+mod level_structs;
+pub use level_structs::*;
+mod prefab_structs;
+pub use prefab_structs::*;
 mod faction_structs;
 pub use faction_structs::*;
 mod spell_structs;
 pub use spell_structs::Spell;
 mod weapon_traits;
 pub use weapon_traits::*;
+mod validation;
+pub use validation::{RawProblem, validate_raws};
+mod reload;
+pub use reload::*;
 use std::sync::Mutex;
+use std::path::{Path, PathBuf};
 use serde::{Deserialize};
 
 rltk::embedded_resource!(RAW_FILE, "../../raws/spawns.json");
+rltk::embedded_resource!(LEVEL_FILE, "../../raws/levels.json");
+rltk::embedded_resource!(PREFAB_FILE, "../../raws/prefabs.json");
 
 
 lazy_static! {
     pub static ref RAWS : Mutex<RawMaster> = Mutex::new(RawMaster::empty());
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
 pub struct Raws {
     pub items : Vec<Item>,
     pub mobs : Vec<Mob>,
     pub props : Vec<Prop>,
     pub spawn_table : Vec<SpawnTableEntry>,
+    pub spawn_tables : Vec<SpawnTable>,
+    pub levels : Vec<LevelDefinition>,
+    pub prefabs : Vec<PrefabDefinition>,
     pub loot_tables : Vec<LootTable>,
     pub faction_table : Vec<FactionInfo>,
     pub spells : Vec<Spell>,
@@ -39,15 +54,116 @@ pub struct Raws {
 }
 
 
-pub fn load_raws() {
-    rltk::link_resource!(RAW_FILE, "../../raws/spawns.json");
+// Entries from later files replace earlier ones with the same name, new names are appended
+fn merge_by_name<T>(base : &mut Vec<T>, additions : Vec<T>, name : fn(&T) -> &str) {
+    for addition in additions {
+        match base.iter().position(|existing| name(existing) == name(&addition)) {
+            Some(i) => base[i] = addition,
+            None => base.push(addition)
+        }
+    }
+}
+
+impl Raws {
+    pub fn merge(&mut self, other : Raws) {
+        merge_by_name(&mut self.items, other.items, |i| &i.name);
+        merge_by_name(&mut self.mobs, other.mobs, |m| &m.name);
+        merge_by_name(&mut self.props, other.props, |p| &p.name);
+        merge_by_name(&mut self.spawn_table, other.spawn_table, |s| &s.name);
+        merge_by_name(&mut self.spawn_tables, other.spawn_tables, |s| &s.name);
+        merge_by_name(&mut self.levels, other.levels, |l| &l.name);
+        merge_by_name(&mut self.prefabs, other.prefabs, |p| &p.name);
+        merge_by_name(&mut self.loot_tables, other.loot_tables, |l| &l.name);
+        merge_by_name(&mut self.faction_table, other.faction_table, |f| &f.name);
+        merge_by_name(&mut self.spells, other.spells, |s| &s.name);
+        merge_by_name(&mut self.weapon_traits, other.weapon_traits, |w| &w.name);
+    }
+}
+
+pub fn raws_dir() -> Option<PathBuf> {
+    if let Some(dir) = std::env::var_os("WIECLAW_RAWS_DIR") {
+        return Some(PathBuf::from(dir));
+    }
+    let exe = std::env::current_exe().ok()?;
+    Some(exe.parent()?.join("raws"))
+}
+
+fn collect_raw_files(dir : &Path, files : &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else { return; };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_raw_files(&path, files);
+        } else if path.extension().is_some_and(|ext| ext == "json") {
+            files.push(path);
+        }
+    }
+}
 
+// Every .json file under the raws directory, mods in subdirectories included, in a stable order
+pub fn external_raw_files() -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    if let Some(dir) = raws_dir() {
+        collect_raw_files(&dir, &mut files);
+    }
+    files.sort();
+    files
+}
+
+fn embedded_raw_file(path : &str) -> Raws {
     let raw_data = rltk::embedding::EMBED
         .lock()
-        .get_resource("../../raws/spawns.json".to_string())
+        .get_resource(path.to_string())
         .unwrap();
-    let raw_string = std::str::from_utf8(&raw_data).expect("Unable to convert to a valid UTF-8 string.");
-    let decoder : Raws = serde_json::from_str(&raw_string).expect("Unable to parse JSON");
+    let raw_string = std::str::from_utf8(raw_data).expect("Unable to convert to a valid UTF-8 string.");
+    serde_json::from_str(raw_string).expect("Unable to parse JSON")
+}
+
+fn embedded_raws() -> Raws {
+    rltk::link_resource!(RAW_FILE, "../../raws/spawns.json");
+    rltk::link_resource!(LEVEL_FILE, "../../raws/levels.json");
+    rltk::link_resource!(PREFAB_FILE, "../../raws/prefabs.json");
+
+    let mut raws = embedded_raw_file("../../raws/spawns.json");
+    raws.merge(embedded_raw_file("../../raws/levels.json"));
+    raws.merge(embedded_raw_file("../../raws/prefabs.json"));
+    raws
+}
+
+fn read_raw_file(path : &Path) -> Result<Raws, String> {
+    let data = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    serde_json::from_str(&data).map_err(|e| e.to_string())
+}
+
+fn gather_raws() -> (Raws, Vec<RawProblem>) {
+    let mut raws = embedded_raws();
+    let mut problems = Vec::new();
+    for path in external_raw_files() {
+        match read_raw_file(&path) {
+            Ok(external) => raws.merge(external),
+            Err(e) => problems.push(RawProblem{
+                kind: "file",
+                name: path.display().to_string(),
+                field: "json".to_string(),
+                message: e
+            })
+        }
+    }
+    (raws, problems)
+}
+
+pub fn validate_all_raws() -> Vec<RawProblem> {
+    let (raws, mut problems) = gather_raws();
+    problems.extend(validate_raws(&raws));
+    problems
+}
+
+pub fn load_raws() {
+    let (raws, mut problems) = gather_raws();
+    problems.extend(validate_raws(&raws));
+    for problem in problems.iter() {
+        rltk::console::log(format!("WARNING - {}", problem));
+    }
 
-    RAWS.lock().unwrap().load(decoder);
+    RAWS.lock().unwrap().load(raws);
 }
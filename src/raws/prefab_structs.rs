@@ -0,0 +1,60 @@
+use serde::{Deserialize};
+use std::collections::HashMap;
+
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PrefabKind {
+    Level,
+    Section,
+    Room
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct PrefabDefinition {
+    pub name : String,
+    pub kind : PrefabKind,
+    #[serde(default)]
+    pub min_depth : i32,
+    #[serde(default = "deepest")]
+    pub max_depth : i32,
+    #[serde(default = "common")]
+    pub rarity : i32,
+    #[serde(default)]
+    pub rotate : bool,
+    #[serde(default)]
+    pub mirror : bool,
+    pub placement : Option<PrefabPlacement>,
+    #[serde(default)]
+    pub legend : HashMap<String, LegendEntry>,
+    pub template : Vec<String>
+}
+
+fn deepest() -> i32 { i32::MAX }
+
+fn common() -> i32 { 1 }
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct PrefabPlacement {
+    pub x : String,
+    pub y : String
+}
+
+// What a template character becomes: a tile, and at most one thing spawned on it
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct LegendEntry {
+    pub tile : Option<String>,
+    pub mob : Option<String>,
+    pub item : Option<String>,
+    pub prop : Option<String>,
+    pub roll : Option<String>
+}
+
+impl PrefabDefinition {
+    pub fn width(&self) -> usize {
+        self.template.iter().map(|row| row.chars().count()).max().unwrap_or(0)
+    }
+
+    pub fn height(&self) -> usize {
+        self.template.len()
+    }
+}
@@ -1,6 +1,6 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 use specs::{prelude::*, saveload::{MarkedBuilder, SimpleMarker}};
-use crate::components::*;
+use crate::{components::*, map::MapId};
 use super::{Raws, Reaction, super::{random_table::{RandomTable, MasterTable}, Attributes, attr_bonus, npc_hp, mana_at_level}};
 use regex::Regex;
 
@@ -49,8 +49,8 @@ fn find_slot_for_equippable_item(tag : &str, raws: &RawMaster) -> EquipmentSlot
     }
     let item_index = raws.item_index[tag];
     let item = &raws.raws.items[item_index];
-    if let Some(_wpn) = &item.weapon {
-        return EquipmentSlot::Melee;
+    if let Some(wpn) = &item.weapon {
+        return weapon_slot(wpn);
     } else if let Some(wearable) = &item.wearable {
         return string_to_slot(&wearable.slot);
     }
@@ -80,6 +80,9 @@ impl RawMaster {
                 mobs: Vec::new(),
                 props: Vec::new(),
                 spawn_table: Vec::new(),
+                spawn_tables: Vec::new(),
+                levels: Vec::new(),
+                prefabs: Vec::new(),
                 loot_tables: Vec::new(),
                 faction_table: Vec::new(),
                 spells : Vec::new(),
@@ -93,6 +96,35 @@ impl RawMaster {
         }
     }
 
+    // Later definitions win, so mods can take over a depth range without renaming the base levels
+    pub fn level_for(&self, id : MapId) -> Option<&super::LevelDefinition> {
+        self.raws.levels.iter().rev().find(|l| l.branch == id.branch && id.depth >= l.min_depth && id.depth <= l.max_depth)
+    }
+
+    // The item a spent shot of this kind turns back into, and the chance it survives the shot
+    pub fn ammunition_of_kind(&self, kind : &str) -> Option<(&str, f32)> {
+        self.raws.items.iter()
+            .find_map(|i| i.ammunition.as_ref().filter(|a| a.kind == kind).map(|a| (i.name.as_str(), a.recovery.unwrap_or(0.0))))
+    }
+
+    pub fn prefab(&self, name : &str) -> Option<&super::PrefabDefinition> {
+        self.raws.prefabs.iter().find(|p| p.name == name)
+    }
+
+    pub fn prefabs_of_kind(&self, kind : super::PrefabKind) -> impl Iterator<Item = &super::PrefabDefinition> {
+        self.raws.prefabs.iter().filter(move |p| p.kind == kind)
+    }
+
+    pub fn spawn_blocks_tile(&self, key : &str) -> bool {
+        if let Some(i) = self.mob_index.get(key) {
+            self.raws.mobs[*i].blocks_tile
+        } else if let Some(i) = self.prop_index.get(key) {
+            self.raws.props[*i].blocks_tile.unwrap_or(false)
+        } else {
+            false
+        }
+    }
+
     fn append_magic_template(items_to_build : &mut Vec<NewMagicItem>, item : &super::Item) {
         if let Some(template) = &item.template_magic {
             if item.weapon.is_some() || item.wearable.is_some() {
@@ -212,37 +244,17 @@ impl RawMaster {
     pub fn load(&mut self, raws : Raws) {
         self.raws = raws;
         self.item_index = HashMap::new();
-        let mut used_names : HashSet<String> = HashSet::new();
         let mut items_to_build = Vec::new();
 
         for (i,item) in self.raws.items.iter().enumerate() {
-            if used_names.contains(&item.name) {
-                rltk::console::log(format!("WARNING - duplicate item name in raws [{}]", item.name));
-            }
             self.item_index.insert(item.name.clone(), i);
-            used_names.insert(item.name.clone());
-
             RawMaster::append_magic_template(&mut items_to_build, item);
         }
         for (i,mob) in self.raws.mobs.iter().enumerate() {
-            if used_names.contains(&mob.name) {
-                rltk::console::log(format!("WARNING - duplicate mob name in raws [{}]", mob.name));
-            }
             self.mob_index.insert(mob.name.clone(), i);
-            used_names.insert(mob.name.clone());
         }
         for (i,prop) in self.raws.props.iter().enumerate() {
-            if used_names.contains(&prop.name) {
-                rltk::console::log(format!("WARNING - duplicate prop name in raws [{}]", prop.name));
-            }
             self.prop_index.insert(prop.name.clone(), i);
-            used_names.insert(prop.name.clone());
-        }
-
-        for spawn in self.raws.spawn_table.iter() {
-            if !used_names.contains(&spawn.name) {
-                rltk::console::log(format!("WARNING - Spawn tables references unspecified entity {}", spawn.name));
-            }
         }
 
         for (i,loot) in self.raws.loot_tables.iter().enumerate() {
@@ -295,6 +307,10 @@ fn get_renderable_component(renderable : &super::item_structs::Renderable) -> cr
     }
 }
 
+fn weapon_slot(weapon : &super::item_structs::Weapon) -> EquipmentSlot {
+    if weapon.range == "melee" { EquipmentSlot::Melee } else { EquipmentSlot::Ranged }
+}
+
 pub fn string_to_slot(slot : &str) -> EquipmentSlot {
     match slot{ 
         "Shield" => EquipmentSlot::Shield,
@@ -304,10 +320,51 @@ pub fn string_to_slot(slot : &str) -> EquipmentSlot {
         "Feet" => EquipmentSlot::Feet,
         "Hands" => EquipmentSlot::Hands,
         "Melee" => EquipmentSlot::Melee,
+        "Ranged" => EquipmentSlot::Ranged,
         _ => { rltk::console::log(format!("Warning: unknown equipment slot type [{}]", slot)); EquipmentSlot::Melee }
     }
 }
 
+pub fn string_to_damage_type(damage_type : &str) -> DamageType {
+    match damage_type {
+        "physical" => DamageType::Physical,
+        "fire" => DamageType::Fire,
+        "cold" => DamageType::Cold,
+        "poison" => DamageType::Poison,
+        "magic" => DamageType::Magic,
+        _ => { rltk::console::log(format!("Warning: unknown damage type [{}]", damage_type)); DamageType::Physical }
+    }
+}
+
+pub fn string_to_resistance(resistance : &str) -> Resistance {
+    match resistance {
+        "resistant" => Resistance::Resistant,
+        "immune" => Resistance::Immune,
+        "vulnerable" => Resistance::Vulnerable,
+        _ => { rltk::console::log(format!("Warning: unknown resistance [{}]", resistance)); Resistance::Resistant }
+    }
+}
+
+pub fn string_to_maneuver(maneuver : &str) -> Maneuver {
+    match maneuver {
+        "shove" => Maneuver::Shove,
+        "disarm" => Maneuver::Disarm,
+        "trip" => Maneuver::Trip,
+        _ => { rltk::console::log(format!("Warning: unknown maneuver [{}]", maneuver)); Maneuver::Shove }
+    }
+}
+
+fn parse_resistances(raw : &HashMap<String, String>) -> Resistances {
+    Resistances{
+        damage : raw.iter().map(|(dt, r)| (string_to_damage_type(dt), string_to_resistance(r))).collect()
+    }
+}
+
+// Effects that hurt read their type from a "damage_type" entry next to them
+fn effect_damage_type(effects : &HashMap<String, String>) -> DamageType {
+    effects.get("damage_type").map_or(DamageType::Physical, |dt| string_to_damage_type(dt))
+}
+
 fn parse_particle_line(n : &str) -> SpawnParticleLine {
     let tokens : Vec<_> = n.split(';').collect();
     SpawnParticleLine{
@@ -334,7 +391,8 @@ macro_rules! apply_effects {
                 "provides_healing" => $eb = $eb.with(ProvidesHealing{ heal_amount: effect.1.parse::<i32>().unwrap() }),
                 "provides_mana" => $eb = $eb.with(ProvidesMana{ mana_amount: effect.1.parse::<i32>().unwrap() }),
                 "ranged" => $eb = $eb.with(Ranged{ range: effect.1.parse::<i32>().unwrap() }),
-                "damage" => $eb = $eb.with(InflictsDamage{ damage : effect.1.parse::<i32>().unwrap() }),
+                "damage" => $eb = $eb.with(InflictsDamage{ damage : effect.1.parse::<i32>().unwrap(), damage_type : effect_damage_type(&$effects) }),
+                "damage_type" => {},
                 "area_of_effect" => $eb = $eb.with(AreaOfEffect{ radius: effect.1.parse::<i32>().unwrap() }),
                 "confusion" => {
                     $eb = $eb.with(Confusion{});
@@ -350,7 +408,9 @@ macro_rules! apply_effects {
                 "identify" => $eb = $eb.with(ProvidesIdentification{}),
                 "teach_spell" => $eb = $eb.with(TeachesSpell{ spell: effect.1.to_string() }),
                 "slow" => $eb = $eb.with(Slow{ initiative_penalty: effect.1.parse::<f32>().unwrap() }),
-                "damage_over_time" => $eb = $eb.with(DamageOverTime{ damage : effect.1.parse::<i32>().unwrap() }),
+                "damage_over_time" => $eb = $eb.with(DamageOverTime{ damage : effect.1.parse::<i32>().unwrap(), damage_type : effect_damage_type(&$effects) }),
+                "dig" => $eb = $eb.with(Digs{ power : effect.1.parse::<i32>().unwrap() }),
+                "poison_gas" => $eb = $eb.with(PoisonGas{ strength : effect.1.parse::<i32>().unwrap() }),
                 "target_self" => $eb = $eb.with(AlwaysTargetsSelf{}),
                 _ => rltk::console::log(format!("Warning: consumable effect {} not implemented.", effect_name))
             }
@@ -389,7 +449,7 @@ pub fn spawn_named_item(raws: &RawMaster, ecs: &mut World, key : &str, pos : Spa
         }
 
         if let Some(weapon) = &item_template.weapon {
-            eb = eb.with(Equippable{ slot: EquipmentSlot::Melee });
+            eb = eb.with(Equippable{ slot: weapon_slot(weapon) });
             let (n_dice, die_type, bonus) = parse_dice_string(&weapon.base_damage);
             let mut wpn = Weapon{
                 range : if weapon.range == "melee" { None } else { Some(weapon.range.parse::<i32>().expect("Not a number")) },
@@ -399,7 +459,10 @@ pub fn spawn_named_item(raws: &RawMaster, ecs: &mut World, key : &str, pos : Spa
                 damage_bonus : bonus,
                 hit_bonus : weapon.hit_bonus,
                 proc_chance : weapon.proc_chance,
-                proc_target : weapon.proc_target.clone()
+                proc_target : weapon.proc_target.clone(),
+                damage_type : weapon.damage_type.as_deref().map_or(DamageType::Physical, string_to_damage_type),
+                crit_range : weapon.crit_range.unwrap_or(20),
+                crit_multiplier : weapon.crit_multiplier.unwrap_or(2)
             };
             match weapon.attribute.as_str() {
                 "Quickness" => wpn.attribute = WeaponAttribute::Quickness,
@@ -409,6 +472,22 @@ pub fn spawn_named_item(raws: &RawMaster, ecs: &mut World, key : &str, pos : Spa
             if let Some(proc_effects) =& weapon.proc_effects {
                 apply_effects!(proc_effects, eb);
             }
+            if let Some(ammo) = &weapon.ammo {
+                eb = eb.with(Magazine{
+                    ammo : ammo.kind.clone(),
+                    capacity : ammo.capacity,
+                    loaded : ammo.capacity,
+                    reload_turns : ammo.reload_turns
+                });
+            }
+        }
+
+        if let Some(ammo) = &item_template.ammunition {
+            eb = eb.with(Ammunition{ kind: ammo.kind.clone(), count: ammo.count });
+        }
+
+        if let Some(power) = item_template.digging {
+            eb = eb.with(Digs{ power });
         }
 
         if let Some(wearable) = &item_template.wearable {
@@ -417,6 +496,10 @@ pub fn spawn_named_item(raws: &RawMaster, ecs: &mut World, key : &str, pos : Spa
             eb = eb.with(Wearable{ slot, armor_class: wearable.armor_class });
         }
 
+        if let Some(resistances) = &item_template.resistances {
+            eb = eb.with(parse_resistances(resistances));
+        }
+
         if let Some(magic) = &item_template.magic {
             let class = match magic.class.as_str() {
                 "rare" => MagicItemClass::Rare,
@@ -540,12 +623,16 @@ pub fn spawn_named_mob(raws: &RawMaster, ecs: &mut World, key : &str, pos : Spaw
         skills.skills.insert(Skill::Melee, 1);
         skills.skills.insert(Skill::Defense, 1);
         skills.skills.insert(Skill::Magic, 1);
+        skills.skills.insert(Skill::Search, 1);
+        skills.skills.insert(Skill::Stealth, 1);
         if let Some(mobskills) = &mob_template.skills {
             for sk in mobskills.iter() {
                 match sk.0.as_str() {
                     "Melee" => { skills.skills.insert(Skill::Melee, *sk.1); }
                     "Defense" => { skills.skills.insert(Skill::Defense, *sk.1); }
                     "Magic" => { skills.skills.insert(Skill::Magic, *sk.1); }
+                    "Search" => { skills.skills.insert(Skill::Search, *sk.1); }
+                    "Stealth" => { skills.skills.insert(Skill::Stealth, *sk.1); }
                     _ => { rltk::console::log(format!("Unknown skill referenced: [{}]", sk.0)); }
                 }
             }
@@ -566,7 +653,8 @@ pub fn spawn_named_mob(raws: &RawMaster, ecs: &mut World, key : &str, pos : Spaw
                         hit_bonus : nattack.hit_bonus,
                         damage_n_dice: n,
                         damage_die_type: d,
-                        damage_bonus: b
+                        damage_bonus: b,
+                        damage_type: nattack.damage_type.as_deref().map_or(DamageType::Physical, string_to_damage_type)
                     };
                     nature.attacks.push(attack);
                 }
@@ -574,6 +662,10 @@ pub fn spawn_named_mob(raws: &RawMaster, ecs: &mut World, key : &str, pos : Spaw
             eb = eb.with(nature);
         }
 
+        if let Some(resistances) = &mob_template.resistances {
+            eb = eb.with(parse_resistances(resistances));
+        }
+
         if let Some(loot) = &mob_template.loot_table {
             eb = eb.with(LootTable{table: loot.clone()});
         }
@@ -592,6 +684,12 @@ pub fn spawn_named_mob(raws: &RawMaster, ecs: &mut World, key : &str, pos : Spaw
             eb = eb.with(Faction{ name: "Mindless".to_string() })
         }
 
+        // Only those out to get the player need to notice them first
+        let faction = mob_template.faction.as_deref().unwrap_or("Mindless");
+        if faction_reaction(faction, "Player", raws) == Reaction::Attack {
+            eb = eb.with(Awareness{ state: AwarenessState::Unaware, investigate: None, suspicion: 0 });
+        }
+
         eb = eb.with(EquipmentChanged{});
 
         if let Some(vendor) = &mob_template.vendor {
@@ -628,6 +726,19 @@ pub fn spawn_named_mob(raws: &RawMaster, ecs: &mut World, key : &str, pos : Spaw
             eb = eb.with(a);
         }
 
+        if let Some(maneuver_list) = &mob_template.maneuvers {
+            let mut m = Maneuvers{ maneuvers : Vec::new() };
+            for maneuver in maneuver_list.iter() {
+                m.maneuvers.push(
+                    ManeuverAbility{
+                        maneuver : string_to_maneuver(&maneuver.name),
+                        chance : maneuver.chance
+                    }
+                );
+            }
+            eb = eb.with(m);
+        }
+
         if mob_template.stationary.is_some() {
             eb = eb.with(Stationary{});
         }
@@ -640,6 +751,12 @@ pub fn spawn_named_mob(raws: &RawMaster, ecs: &mut World, key : &str, pos : Spaw
             }
         }
 
+        if let Some(carrying) = &mob_template.carried {
+            for tag in carrying.iter() {
+                spawn_named_entity(raws, ecs, tag, SpawnType::Carried{ by: new_mob });
+            }
+        }
+
         return Some(new_mob);
     }
     None
@@ -703,7 +820,9 @@ pub fn spawn_named_spell(raws: &RawMaster, ecs: &mut World, key : &str) -> Optio
 pub fn spawn_all_spells(ecs : &mut World) {
     let raws = &super::RAWS.lock().unwrap();
     for spell in raws.raws.spells.iter() {
-        spawn_named_spell(raws, ecs, &spell.name);
+        if find_spell_entity(ecs, &spell.name).is_none() {
+            spawn_named_spell(raws, ecs, &spell.name);
+        }
     }
 }
 
@@ -732,10 +851,29 @@ pub fn spawn_named_entity(raws: &RawMaster, ecs: &mut World, key : &str, pos : S
     None
 }
 
-pub fn get_spawn_table_for_depth(raws: &RawMaster, depth: i32) -> MasterTable {
+pub fn get_level_name(raws: &RawMaster, id: MapId) -> String {
+    raws.level_for(id).map_or(id.to_string(), |level| level.map_name.clone())
+}
+
+pub fn get_spawn_table_for_level(raws: &RawMaster, id: MapId) -> MasterTable {
+    let table = raws.level_for(id)
+        .and_then(|level| level.spawn_table.as_ref())
+        .and_then(|name| raws.raws.spawn_tables.iter().find(|t| &t.name == name))
+        .map(|t| &t.entries)
+        .unwrap_or(&raws.raws.spawn_table);
+    spawn_table_at_depth(raws, table, id.depth)
+}
+
+pub fn get_named_spawn_table(raws: &RawMaster, name: &str, depth: i32) -> Option<MasterTable> {
+    raws.raws.spawn_tables.iter()
+        .find(|t| t.name == name)
+        .map(|t| spawn_table_at_depth(raws, &t.entries, depth))
+}
+
+fn spawn_table_at_depth(raws: &RawMaster, table: &[super::SpawnTableEntry], depth: i32) -> MasterTable {
     use super::SpawnTableEntry;
 
-    let available_options : Vec<&SpawnTableEntry> = raws.raws.spawn_table
+    let available_options : Vec<&SpawnTableEntry> = table
         .iter()
         .filter(|a| depth >= a.min_depth && depth <= a.max_depth)
         .collect();
@@ -0,0 +1,52 @@
+use std::path::PathBuf;
+use std::time::SystemTime;
+use super::{RawMaster, RawProblem, RAWS, gather_raws, validate_raws, external_raw_files};
+
+const WATCH_INTERVAL_MS : f32 = 1000.0;
+
+// Any problem keeps the old data loaded
+pub fn reload_raws() -> Result<(), Vec<RawProblem>> {
+    let (raws, mut problems) = gather_raws();
+    problems.extend(validate_raws(&raws));
+    if !problems.is_empty() {
+        return Err(problems);
+    }
+
+    let mut raw_master = RawMaster::empty();
+    raw_master.load(raws);
+    *RAWS.lock().unwrap() = raw_master;
+    Ok(())
+}
+
+pub struct RawsWatcher {
+    files : Vec<(PathBuf, Option<SystemTime>)>,
+    timer : f32
+}
+
+fn scan_raw_files() -> Vec<(PathBuf, Option<SystemTime>)> {
+    external_raw_files().into_iter()
+        .map(|path| {
+            let modified = std::fs::metadata(&path).and_then(|m| m.modified()).ok();
+            (path, modified)
+        })
+        .collect()
+}
+
+impl RawsWatcher {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> RawsWatcher {
+        RawsWatcher{ files : scan_raw_files(), timer : 0.0 }
+    }
+
+    pub fn has_changed(&mut self, frame_time_ms : f32) -> bool {
+        self.timer += frame_time_ms;
+        if self.timer < WATCH_INTERVAL_MS {
+            return false;
+        }
+        self.timer = 0.0;
+        let files = scan_raw_files();
+        let changed = files != self.files;
+        self.files = files;
+        changed
+    }
+}
@@ -8,3 +8,9 @@ pub struct SpawnTableEntry {
     pub max_depth: i32,
     pub add_map_depth_to_weight : Option<bool>
 }
+
+#[derive(Deserialize, Debug)]
+pub struct SpawnTable {
+    pub name : String,
+    pub entries : Vec<SpawnTableEntry>
+}
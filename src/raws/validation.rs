@@ -0,0 +1,557 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use regex::Regex;
+use super::Raws;
+
+pub struct RawProblem {
+    pub kind : &'static str,
+    pub name : String,
+    pub field : String,
+    pub message : String
+}
+
+impl fmt::Display for RawProblem {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} [{}] {}: {}", self.kind, self.name, self.field, self.message)
+    }
+}
+
+const EQUIPMENT_SLOTS : &[&str] = &["Shield", "Head", "Torso", "Legs", "Feet", "Hands", "Melee", "Ranged"];
+const WEAPON_ATTRIBUTES : &[&str] = &["Might", "Quickness"];
+const MOVEMENTS : &[&str] = &["static", "random", "random_waypoint"];
+const SKILLS : &[&str] = &["Melee", "Defense", "Magic", "Search", "Stealth"];
+const MAGIC_CLASSES : &[&str] = &["common", "rare", "legendary"];
+const REACTIONS : &[&str] = &["ignore", "flee", "attack"];
+const PROC_TARGETS : &[&str] = &["Self", "Target"];
+const DAMAGE_TYPES : &[&str] = &["physical", "fire", "cold", "poison", "magic"];
+const RESISTANCES : &[&str] = &["resistant", "immune", "vulnerable"];
+const MANEUVERS : &[&str] = &["shove", "disarm", "trip"];
+const PLACEMENTS_X : &[&str] = &["left", "center", "right"];
+const PLACEMENTS_Y : &[&str] = &["top", "center", "bottom"];
+
+struct Validator<'a> {
+    raws : &'a Raws,
+    items : HashSet<String>,
+    equippable : HashSet<String>,
+    spawnables : HashSet<String>,
+    spells : HashSet<&'a str>,
+    factions : HashSet<&'a str>,
+    loot_tables : HashSet<&'a str>,
+    vendor_categories : HashSet<&'a str>,
+    problems : Vec<RawProblem>
+}
+
+fn is_dice(dice : &str) -> bool {
+    lazy_static!{
+        static ref STRICT_DICE_RE : Regex = Regex::new(r"^\d+d\d+([\+\-]\d+)?$").unwrap();
+    }
+    STRICT_DICE_RE.is_match(dice)
+}
+
+fn is_color(color : &str) -> bool {
+    rltk::RGB::from_hex(color).is_ok()
+}
+
+// Names RawMaster::load adds for magic templates and weapon traits
+fn templated_names(raws : &Raws, item : &super::Item) -> Vec<String> {
+    let mut names = Vec::new();
+    if let Some(template) = &item.template_magic {
+        if item.weapon.is_none() && item.wearable.is_none() { return names; }
+        if template.include_cursed {
+            names.push(format!("{} -1", item.name));
+        }
+        for bonus in template.bonus_min ..= template.bonus_max {
+            names.push(format!("{} +{}", item.name, bonus));
+            if item.weapon.is_some() && bonus > 0 {
+                for weapon_trait in raws.weapon_traits.iter() {
+                    names.push(format!("{} +{} {}", item.name, bonus, weapon_trait.name));
+                }
+            }
+        }
+    }
+    names
+}
+
+impl<'a> Validator<'a> {
+    fn new(raws : &'a Raws) -> Validator<'a> {
+        let mut items = HashSet::new();
+        let mut equippable = HashSet::new();
+        for item in raws.items.iter() {
+            let mut names = templated_names(raws, item);
+            names.push(item.name.clone());
+            if item.weapon.is_some() || item.wearable.is_some() {
+                equippable.extend(names.iter().cloned());
+            }
+            items.extend(names);
+        }
+        let spawnables = items.iter().cloned()
+            .chain(raws.mobs.iter().map(|m| m.name.clone()))
+            .chain(raws.props.iter().map(|p| p.name.clone()))
+            .collect();
+        Validator{
+            raws,
+            items,
+            equippable,
+            spawnables,
+            spells : raws.spells.iter().map(|s| s.name.as_str()).collect(),
+            factions : raws.faction_table.iter().map(|f| f.name.as_str()).collect(),
+            loot_tables : raws.loot_tables.iter().map(|l| l.name.as_str()).collect(),
+            vendor_categories : raws.items.iter().filter_map(|i| i.vendor_category.as_deref()).collect(),
+            problems : Vec::new()
+        }
+    }
+
+    fn report(&mut self, kind : &'static str, name : &str, field : &str, message : String) {
+        self.problems.push(RawProblem{ kind, name: name.to_string(), field: field.to_string(), message });
+    }
+
+    fn check_one_of(&mut self, kind : &'static str, name : &str, field : &str, value : &str, allowed : &[&str]) {
+        if !allowed.contains(&value) {
+            self.report(kind, name, field, format!("unknown value [{}], expected one of {:?}", value, allowed));
+        }
+    }
+
+    fn check_dice(&mut self, kind : &'static str, name : &str, field : &str, dice : &str) {
+        if !is_dice(dice) {
+            self.report(kind, name, field, format!("[{}] is not a dice string like 1d6+2", dice));
+        }
+    }
+
+    fn check_color(&mut self, kind : &'static str, name : &str, field : &str, color : &str) {
+        if !is_color(color) {
+            self.report(kind, name, field, format!("[{}] is not a #RRGGBB color", color));
+        }
+    }
+
+    fn check_renderable(&mut self, kind : &'static str, name : &str, renderable : &Option<super::Renderable>) {
+        if let Some(renderable) = renderable {
+            if renderable.glyph.is_empty() {
+                self.report(kind, name, "renderable.glyph", "glyph is empty".to_string());
+            }
+            self.check_color(kind, name, "renderable.fg", &renderable.fg);
+            self.check_color(kind, name, "renderable.bg", &renderable.bg);
+        }
+    }
+
+    fn check_particle(&mut self, kind : &'static str, name : &str, field : &str, value : &str) {
+        let tokens : Vec<&str> = value.split(';').collect();
+        let valid = tokens.len() == 3
+            && !tokens[0].is_empty()
+            && is_color(tokens[1])
+            && tokens[2].parse::<f32>().is_ok();
+        if !valid {
+            self.report(kind, name, field, format!("[{}] is not in glyph;#RRGGBB;lifetime_ms form", value));
+        }
+    }
+
+    fn check_effects(&mut self, kind : &'static str, name : &str, field : &str, effects : &HashMap<String, String>) {
+        let mut keys : Vec<&String> = effects.keys().collect();
+        keys.sort();
+        for key in keys {
+            let value = &effects[key];
+            let effect_field = format!("{}.{}", field, key);
+            match key.as_str() {
+                "provides_healing" | "provides_mana" | "ranged" | "damage" | "area_of_effect" | "confusion" | "damage_over_time" | "dig" |
+                "poison_gas" => {
+                    if value.parse::<i32>().is_err() {
+                        self.report(kind, name, &effect_field, format!("[{}] is not a whole number", value));
+                    }
+                }
+                "slow" => {
+                    if value.parse::<f32>().is_err() {
+                        self.report(kind, name, &effect_field, format!("[{}] is not a number", value));
+                    }
+                }
+                "damage_type" => {
+                    self.check_one_of(kind, name, &effect_field, value, DAMAGE_TYPES);
+                    if !effects.contains_key("damage") && !effects.contains_key("damage_over_time") {
+                        self.report(kind, name, &effect_field, "there is no damage to type".to_string());
+                    }
+                }
+                "particle_line" | "particle" => self.check_particle(kind, name, &effect_field, value),
+                "teach_spell" => {
+                    if !self.spells.contains(value.as_str()) {
+                        self.report(kind, name, &effect_field, format!("unknown spell [{}]", value));
+                    }
+                }
+                "magic_mapping" | "town_portal" | "food" | "single_activation" | "remove_curse" | "identify" | "target_self" => {}
+                _ => self.report(kind, name, &effect_field, "unknown effect".to_string())
+            }
+        }
+    }
+
+    fn check_resistances(&mut self, kind : &'static str, name : &str, resistances : &Option<HashMap<String, String>>) {
+        let Some(resistances) = resistances else { return; };
+        let mut damage_types : Vec<&String> = resistances.keys().collect();
+        damage_types.sort();
+        for damage_type in damage_types {
+            self.check_one_of(kind, name, "resistances", damage_type, DAMAGE_TYPES);
+            self.check_one_of(kind, name, &format!("resistances.{}", damage_type), &resistances[damage_type], RESISTANCES);
+        }
+    }
+
+    fn check_names(&mut self) {
+        let mut seen : HashMap<&str, &'static str> = HashMap::new();
+        let raws = self.raws;
+        let named = raws.items.iter().map(|i| ("item", i.name.as_str()))
+            .chain(raws.mobs.iter().map(|m| ("mob", m.name.as_str())))
+            .chain(raws.props.iter().map(|p| ("prop", p.name.as_str())));
+        for (kind, name) in named {
+            if let Some(first) = seen.insert(name, kind) {
+                self.report(kind, name, "name", format!("name already used by another {}", first));
+            }
+        }
+    }
+
+    fn check_items(&mut self) {
+        let raws = self.raws;
+        for item in raws.items.iter() {
+            let name = item.name.as_str();
+            self.check_renderable("item", name, &item.renderable);
+            if let Some(consumable) = &item.consumable {
+                self.check_effects("item", name, "consumable.effects", &consumable.effects);
+            }
+            if let Some(weapon) = &item.weapon {
+                if weapon.range != "melee" && weapon.range.parse::<i32>().is_err() {
+                    self.report("item", name, "weapon.range", format!("[{}] is neither melee nor a number", weapon.range));
+                }
+                self.check_one_of("item", name, "weapon.attribute", &weapon.attribute, WEAPON_ATTRIBUTES);
+                self.check_dice("item", name, "weapon.base_damage", &weapon.base_damage);
+                if let Some(damage_type) = &weapon.damage_type {
+                    self.check_one_of("item", name, "weapon.damage_type", damage_type, DAMAGE_TYPES);
+                }
+                if let Some(crit_range) = weapon.crit_range {
+                    if !(2..=20).contains(&crit_range) {
+                        self.report("item", name, "weapon.crit_range", format!("[{}] is not a d20 roll between 2 and 20", crit_range));
+                    }
+                }
+                if let Some(multiplier) = weapon.crit_multiplier {
+                    if multiplier < 2 {
+                        self.report("item", name, "weapon.crit_multiplier", format!("[{}] is below 2", multiplier));
+                    }
+                }
+                if let Some(target) = &weapon.proc_target {
+                    self.check_one_of("item", name, "weapon.proc_target", target, PROC_TARGETS);
+                }
+                if let Some(effects) = &weapon.proc_effects {
+                    self.check_effects("item", name, "weapon.proc_effects", effects);
+                }
+                if let Some(ammo) = &weapon.ammo {
+                    if weapon.range == "melee" {
+                        self.report("item", name, "weapon.ammo", "melee weapons take no ammunition".to_string());
+                    }
+                    if !raws.items.iter().any(|i| i.ammunition.as_ref().is_some_and(|a| a.kind == ammo.kind)) {
+                        self.report("item", name, "weapon.ammo.kind", format!("no item is ammunition of kind [{}]", ammo.kind));
+                    }
+                    if ammo.capacity < 1 {
+                        self.report("item", name, "weapon.ammo.capacity", format!("[{}] is not a positive capacity", ammo.capacity));
+                    }
+                    if ammo.reload_turns < 0 {
+                        self.report("item", name, "weapon.ammo.reload_turns", format!("[{}] is negative", ammo.reload_turns));
+                    }
+                }
+            }
+            if let Some(ammo) = &item.ammunition {
+                if ammo.count < 1 {
+                    self.report("item", name, "ammunition.count", format!("[{}] is not a positive count", ammo.count));
+                }
+                if let Some(recovery) = ammo.recovery {
+                    if !(0.0..=1.0).contains(&recovery) {
+                        self.report("item", name, "ammunition.recovery", format!("[{}] is not a chance between 0 and 1", recovery));
+                    }
+                }
+            }
+            if let Some(wearable) = &item.wearable {
+                self.check_one_of("item", name, "wearable.slot", &wearable.slot, EQUIPMENT_SLOTS);
+            }
+            if item.resistances.is_some() && item.weapon.is_none() && item.wearable.is_none() {
+                self.report("item", name, "resistances", "only equipment protects its wearer".to_string());
+            }
+            self.check_resistances("item", name, &item.resistances);
+            if let Some(power) = item.digging {
+                if power < 1 {
+                    self.report("item", name, "digging", format!("[{}] is not a positive power", power));
+                }
+                if item.weapon.is_none() && item.wearable.is_none() {
+                    self.report("item", name, "digging", "only equipment can be dug with".to_string());
+                }
+            }
+            if let Some(magic) = &item.magic {
+                self.check_one_of("item", name, "magic.class", &magic.class, MAGIC_CLASSES);
+            }
+            if let Some(template) = &item.template_magic {
+                if item.weapon.is_none() && item.wearable.is_none() {
+                    self.report("item", name, "template_magic", "only weapons and armor can be templated".to_string());
+                }
+                if template.bonus_min > template.bonus_max {
+                    self.report("item", name, "template_magic", format!("bonus_min {} is above bonus_max {}", template.bonus_min, template.bonus_max));
+                }
+            }
+        }
+    }
+
+    fn check_mobs(&mut self) {
+        let raws = self.raws;
+        for mob in raws.mobs.iter() {
+            let name = mob.name.as_str();
+            self.check_renderable("mob", name, &mob.renderable);
+            self.check_one_of("mob", name, "movement", &mob.movement, MOVEMENTS);
+            if let Some(skills) = &mob.skills {
+                let mut skill_names : Vec<&String> = skills.keys().collect();
+                skill_names.sort();
+                for skill in skill_names {
+                    self.check_one_of("mob", name, "skills", skill, SKILLS);
+                }
+            }
+            if let Some(equipped) = &mob.equipped {
+                for tag in equipped.iter() {
+                    if !self.items.contains(tag) {
+                        self.report("mob", name, "equipped", format!("unknown item [{}]", tag));
+                    } else if !self.equippable.contains(tag) {
+                        self.report("mob", name, "equipped", format!("[{}] is neither a weapon nor armor", tag));
+                    }
+                }
+            }
+            if let Some(carried) = &mob.carried {
+                for tag in carried.iter() {
+                    if !self.items.contains(tag) {
+                        self.report("mob", name, "carried", format!("unknown item [{}]", tag));
+                    }
+                }
+            }
+            if let Some(attacks) = mob.natural.as_ref().and_then(|n| n.attacks.as_ref()) {
+                for attack in attacks.iter() {
+                    self.check_dice("mob", name, &format!("natural.attacks.{}.damage", attack.name), &attack.damage);
+                    if let Some(damage_type) = &attack.damage_type {
+                        self.check_one_of("mob", name, &format!("natural.attacks.{}.damage_type", attack.name), damage_type, DAMAGE_TYPES);
+                    }
+                }
+            }
+            self.check_resistances("mob", name, &mob.resistances);
+            if let Some(maneuvers) = &mob.maneuvers {
+                for maneuver in maneuvers.iter() {
+                    self.check_one_of("mob", name, "maneuvers", &maneuver.name, MANEUVERS);
+                    if !(0.0..=1.0).contains(&maneuver.chance) {
+                        self.report("mob", name, &format!("maneuvers.{}.chance", maneuver.name), format!("[{}] is not a chance between 0 and 1", maneuver.chance));
+                    }
+                }
+            }
+            if let Some(loot) = &mob.loot_table {
+                if !self.loot_tables.contains(loot.as_str()) {
+                    self.report("mob", name, "loot_table", format!("unknown loot table [{}]", loot));
+                }
+            }
+            if let Some(light) = &mob.light {
+                self.check_color("mob", name, "light.color", &light.color);
+            }
+            let faction = mob.faction.as_deref().unwrap_or("Mindless");
+            if !self.factions.contains(faction) {
+                self.report("mob", name, "faction", format!("unknown faction [{}]", faction));
+            }
+            if let Some(gold) = &mob.gold {
+                self.check_dice("mob", name, "gold", gold);
+            }
+            if let Some(vendor) = &mob.vendor {
+                for category in vendor.iter() {
+                    if !self.vendor_categories.contains(category.as_str()) {
+                        self.report("mob", name, "vendor", format!("no item has vendor category [{}]", category));
+                    }
+                }
+            }
+            for (field, abilities) in [("abilities", &mob.abilities), ("on_death", &mob.on_death)] {
+                for ability in abilities.iter().flatten() {
+                    if !self.spells.contains(ability.spell.as_str()) {
+                        self.report("mob", name, field, format!("unknown spell [{}]", ability.spell));
+                    }
+                }
+            }
+        }
+    }
+
+    fn check_props(&mut self) {
+        let raws = self.raws;
+        for prop in raws.props.iter() {
+            let name = prop.name.as_str();
+            self.check_renderable("prop", name, &prop.renderable);
+            if let Some(trigger) = &prop.entry_trigger {
+                self.check_effects("prop", name, "entry_trigger.effects", &trigger.effects);
+            }
+            if let Some(light) = &prop.light {
+                self.check_color("prop", name, "light.color", &light.color);
+            }
+        }
+    }
+
+    fn check_spawn_entries(&mut self, entries : &[super::SpawnTableEntry]) {
+        for spawn in entries.iter() {
+            let name = spawn.name.as_str();
+            if !self.spawnables.contains(name) {
+                self.report("spawn_table", name, "name", "matches no item, mob or prop".to_string());
+            }
+            if spawn.min_depth > spawn.max_depth {
+                self.report("spawn_table", name, "min_depth", format!("{} is above max_depth {}", spawn.min_depth, spawn.max_depth));
+            }
+        }
+    }
+
+    fn check_tables(&mut self) {
+        let raws = self.raws;
+        self.check_spawn_entries(&raws.spawn_table);
+        for table in raws.spawn_tables.iter() {
+            self.check_spawn_entries(&table.entries);
+        }
+        for loot in raws.loot_tables.iter() {
+            for drop in loot.drops.iter() {
+                if !self.items.contains(&drop.name) {
+                    self.report("loot_table", &loot.name, "drops", format!("unknown item [{}]", drop.name));
+                }
+            }
+        }
+        for faction in raws.faction_table.iter() {
+            let mut others : Vec<(&String, &String)> = faction.responses.iter().collect();
+            others.sort();
+            for (other, reaction) in others {
+                if other != "Default" && !self.factions.contains(other.as_str()) {
+                    self.report("faction", &faction.name, "responses", format!("unknown faction [{}]", other));
+                }
+                self.check_one_of("faction", &faction.name, &format!("responses.{}", other), reaction, REACTIONS);
+            }
+        }
+        for spell in raws.spells.iter() {
+            self.check_effects("spell", &spell.name, "effects", &spell.effects);
+        }
+        for weapon_trait in raws.weapon_traits.iter() {
+            self.check_effects("weapon_trait", &weapon_trait.name, "effects", &weapon_trait.effects);
+        }
+    }
+
+    fn check_legend_entry(&mut self, name : &str, field : &str, entry : &super::LegendEntry) {
+        if let Some(tile) = &entry.tile {
+            if crate::map::tile_from_key(tile).is_none() {
+                self.report("prefab", name, field, format!("unknown tile [{}]", tile));
+            }
+        }
+        let spawns = [&entry.mob, &entry.item, &entry.prop, &entry.roll].iter().filter(|s| s.is_some()).count();
+        if spawns > 1 {
+            self.report("prefab", name, field, "can spawn only one of mob, item, prop or roll".to_string());
+        }
+        let raws = self.raws;
+        if let Some(mob) = &entry.mob {
+            if !raws.mobs.iter().any(|m| &m.name == mob) {
+                self.report("prefab", name, field, format!("unknown mob [{}]", mob));
+            }
+        }
+        if let Some(item) = &entry.item {
+            if !self.items.contains(item) {
+                self.report("prefab", name, field, format!("unknown item [{}]", item));
+            }
+        }
+        if let Some(prop) = &entry.prop {
+            if !raws.props.iter().any(|p| &p.name == prop) {
+                self.report("prefab", name, field, format!("unknown prop [{}]", prop));
+            }
+        }
+        if let Some(table) = &entry.roll {
+            if table != "level" && !raws.spawn_tables.iter().any(|t| &t.name == table) {
+                self.report("prefab", name, field, format!("[{}] is neither level nor a spawn table", table));
+            }
+        }
+    }
+
+    fn check_prefabs(&mut self) {
+        let raws = self.raws;
+        for prefab in raws.prefabs.iter() {
+            let name = prefab.name.as_str();
+            if prefab.min_depth > prefab.max_depth {
+                self.report("prefab", name, "min_depth", format!("{} is above max_depth {}", prefab.min_depth, prefab.max_depth));
+            }
+            if prefab.rarity < 1 {
+                self.report("prefab", name, "rarity", format!("{} is below 1", prefab.rarity));
+            }
+            if prefab.template.is_empty() {
+                self.report("prefab", name, "template", "template is empty".to_string());
+            }
+            let width = prefab.width();
+            for (i, row) in prefab.template.iter().enumerate() {
+                if row.chars().count() != width {
+                    self.report("prefab", name, &format!("template[{}]", i), format!("row is {} wide, the widest is {}", row.chars().count(), width));
+                }
+            }
+            let mut glyphs : Vec<&String> = prefab.legend.keys().collect();
+            glyphs.sort();
+            for glyph in glyphs {
+                let field = format!("legend.{}", glyph);
+                if glyph.chars().count() != 1 {
+                    self.report("prefab", name, &field, "legend keys are single characters".to_string());
+                }
+                self.check_legend_entry(name, &field, &prefab.legend[glyph]);
+            }
+            let mut unknown : Vec<char> = prefab.template.iter().flat_map(|row| row.chars())
+                .filter(|ch| !prefab.legend.contains_key(&ch.to_string()) && crate::map_builders::prefab_tile(*ch, &prefab.legend).is_none())
+                .collect();
+            unknown.sort();
+            unknown.dedup();
+            for ch in unknown {
+                self.report("prefab", name, "template", format!("[{}] is not in the legend", ch));
+            }
+            if let Some(placement) = &prefab.placement {
+                self.check_one_of("prefab", name, "placement.x", &placement.x, PLACEMENTS_X);
+                self.check_one_of("prefab", name, "placement.y", &placement.y, PLACEMENTS_Y);
+            }
+        }
+    }
+
+    fn check_prefab_reference(&mut self, level : &str, field : &str, step : &super::BuilderStep) {
+        let (key, kinds) = match step.builder.as_str() {
+            "prefab_level" => ("level", &[super::PrefabKind::Level][..]),
+            "prefab_section" => ("section", &[super::PrefabKind::Section][..]),
+            "waveform_collapse" => ("level", &[super::PrefabKind::Level, super::PrefabKind::Section, super::PrefabKind::Room][..]),
+            _ => return
+        };
+        let Some(prefab) = step.params.get(key) else { return; };
+        match self.raws.prefabs.iter().find(|p| &p.name == prefab) {
+            None => self.report("level", level, field, format!("unknown prefab [{}]", prefab)),
+            Some(found) if !kinds.contains(&found.kind) => {
+                self.report("level", level, field, format!("prefab [{}] is a {:?}, {} needs a {:?}", prefab, found.kind, step.builder, kinds[0]))
+            }
+            _ => {}
+        }
+    }
+
+    fn check_levels(&mut self) {
+        let raws = self.raws;
+        for level in raws.levels.iter() {
+            let name = level.name.as_str();
+            if level.min_depth > level.max_depth {
+                self.report("level", name, "min_depth", format!("{} is above max_depth {}", level.min_depth, level.max_depth));
+            }
+            if let Err(e) = crate::map_builders::initial_builder(&level.initial) {
+                self.report("level", name, "initial", e);
+            }
+            self.check_prefab_reference(name, "initial", &level.initial);
+            for (i, step) in level.meta.iter().enumerate() {
+                if let Err(e) = crate::map_builders::meta_builder(step) {
+                    self.report("level", name, &format!("meta[{}]", i), e);
+                }
+                self.check_prefab_reference(name, &format!("meta[{}]", i), step);
+            }
+            if let Some(table) = &level.spawn_table {
+                if !raws.spawn_tables.iter().any(|t| &t.name == table) {
+                    self.report("level", name, "spawn_table", format!("unknown spawn table [{}]", table));
+                }
+            }
+        }
+    }
+}
+
+pub fn validate_raws(raws : &Raws) -> Vec<RawProblem> {
+    let mut validator = Validator::new(raws);
+    validator.check_names();
+    validator.check_items();
+    validator.check_mobs();
+    validator.check_props();
+    validator.check_tables();
+    validator.check_prefabs();
+    validator.check_levels();
+    validator.problems
+}
@@ -0,0 +1,179 @@
+use std::fmt;
+use std::fs::File;
+use std::io::Write;
+use rltk::Point;
+use crate::{PlayerCommand, Maneuver, Cheat};
+
+pub struct Recorder {
+    file : File
+}
+
+impl Recorder {
+    pub fn create(path : &str, seed : u64) -> std::io::Result<Recorder> {
+        let mut file = File::create(path)?;
+        writeln!(file, "seed {}", seed)?;
+        Ok(Recorder{ file })
+    }
+
+    pub fn record(&mut self, command : &PlayerCommand) {
+        // Written straight away, so the file survives a crash in the command itself
+        if let Err(e) = writeln!(self.file, "{}", command) {
+            rltk::console::log(format!("Unable to record command: {}", e));
+        }
+    }
+
+    // Leaves a comment saying why the recording stops here, replays skip it
+    pub fn finish(&mut self, reason : &str) {
+        if let Err(e) = writeln!(self.file, "# recording ended: {}", reason) {
+            rltk::console::log(format!("Unable to record command: {}", e));
+        }
+        rltk::console::log(format!("Recording ended: {}", reason));
+    }
+}
+
+pub struct Replay {
+    pub seed : Option<u64>,
+    pub commands : Vec<PlayerCommand>
+}
+
+pub fn load_replay(path : &str) -> Result<Replay, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("unable to read {}: {}", path, e))?;
+    parse_replay(&text)
+}
+
+pub fn parse_replay(text : &str) -> Result<Replay, String> {
+    let mut replay = Replay{ seed: None, commands: Vec::new() };
+    for line in text.lines() {
+        let mut tokens = line.split_whitespace();
+        if tokens.next() == Some("seed") {
+            let seed = tokens.next()
+                .and_then(|s| s.parse::<u64>().ok())
+                .ok_or_else(|| format!("bad seed in [{}]", line))?;
+            replay.seed = Some(seed);
+        } else if let Some(command) = parse_command(line)? {
+            replay.commands.push(command);
+        }
+    }
+    Ok(replay)
+}
+
+pub fn parse_command(line : &str) -> Result<Option<PlayerCommand>, String> {
+    let tokens : Vec<&str> = line.split_whitespace().collect();
+    if tokens.is_empty() || tokens[0].starts_with('#') {
+        return Ok(None);
+    }
+
+    let number = |i : usize| -> Result<i32, String> {
+        tokens.get(i)
+            .ok_or_else(|| format!("missing argument in [{}]", line))?
+            .parse::<i32>()
+            .map_err(|_| format!("not a number in [{}]", line))
+    };
+    let entity = |i : usize| -> Result<u32, String> {
+        tokens.get(i)
+            .ok_or_else(|| format!("missing argument in [{}]", line))?
+            .parse::<u32>()
+            .map_err(|_| format!("not an entity id in [{}]", line))
+    };
+    let target = |i : usize| -> Result<Option<Point>, String> {
+        if tokens.len() > i {
+            Ok(Some(Point::new(number(i)?, number(i+1)?)))
+        } else {
+            Ok(None)
+        }
+    };
+
+    let command = match tokens[0] {
+        "move" => PlayerCommand::Move{ dx: number(1)?, dy: number(2)? },
+        "run" => PlayerCommand::Run{ dx: number(1)?, dy: number(2)? },
+        "wait" => PlayerCommand::Wait,
+        "pickup" => PlayerCommand::PickUp,
+        "use" => PlayerCommand::UseConsumable{ slot: number(1)?, target: target(2)? },
+        "cast" => PlayerCommand::CastSpell{ slot: number(1)?, target: target(2)? },
+        "target" => PlayerCommand::CycleTarget,
+        "fire" => PlayerCommand::Fire,
+        "reload" => PlayerCommand::Reload,
+        "search" => PlayerCommand::Search,
+        "descend" => PlayerCommand::NextLevel,
+        "ascend" => PlayerCommand::PreviousLevel,
+        "use-item" => PlayerCommand::UseItem{ item: entity(1)? },
+        "drop" => PlayerCommand::DropItem{ item: entity(1)? },
+        "remove" => PlayerCommand::RemoveItem{ item: entity(1)? },
+        "aim" => PlayerCommand::SelectTarget{ target: Point::new(number(1)?, number(2)?) },
+        "maneuver" => {
+            let maneuver = match tokens.get(1).copied() {
+                Some("shove") => Maneuver::Shove,
+                Some("disarm") => Maneuver::Disarm,
+                Some("trip") => Maneuver::Trip,
+                _ => return Err(format!("unknown maneuver in [{}]", line))
+            };
+            PlayerCommand::Maneuver{ maneuver, target: Point::new(number(2)?, number(3)?) }
+        }
+        "sell" => PlayerCommand::SellItem{ item: entity(1)? },
+        "buy" => PlayerCommand::BuyItem{ index: number(1)? },
+        "uncurse" => PlayerCommand::RemoveCurse{ item: entity(1)? },
+        "identify" => PlayerCommand::Identify{ item: entity(1)? },
+        "cheat" => {
+            let cheat = match tokens.get(1).copied() {
+                Some("exit") => Cheat::TeleportToExit,
+                Some("heal") => Cheat::Heal,
+                Some("reveal") => Cheat::RevealMap,
+                Some("god") => Cheat::GodMode,
+                Some("reload-raws") => Cheat::ReloadRaws,
+                _ => return Err(format!("unknown cheat in [{}]", line))
+            };
+            PlayerCommand::Cheat{ cheat }
+        }
+        "cancel" => PlayerCommand::Cancel,
+        _ => return Err(format!("unknown command [{}]", tokens[0]))
+    };
+    Ok(Some(command))
+}
+
+impl fmt::Display for PlayerCommand {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PlayerCommand::Move{ dx, dy } => write!(f, "move {} {}", dx, dy),
+            PlayerCommand::Run{ dx, dy } => write!(f, "run {} {}", dx, dy),
+            PlayerCommand::Wait => write!(f, "wait"),
+            PlayerCommand::PickUp => write!(f, "pickup"),
+            PlayerCommand::UseConsumable{ slot, target: None } => write!(f, "use {}", slot),
+            PlayerCommand::UseConsumable{ slot, target: Some(t) } => write!(f, "use {} {} {}", slot, t.x, t.y),
+            PlayerCommand::CastSpell{ slot, target: None } => write!(f, "cast {}", slot),
+            PlayerCommand::CastSpell{ slot, target: Some(t) } => write!(f, "cast {} {} {}", slot, t.x, t.y),
+            PlayerCommand::CycleTarget => write!(f, "target"),
+            PlayerCommand::Fire => write!(f, "fire"),
+            PlayerCommand::Reload => write!(f, "reload"),
+            PlayerCommand::Search => write!(f, "search"),
+            PlayerCommand::NextLevel => write!(f, "descend"),
+            PlayerCommand::PreviousLevel => write!(f, "ascend"),
+            PlayerCommand::UseItem{ item } => write!(f, "use-item {}", item),
+            PlayerCommand::DropItem{ item } => write!(f, "drop {}", item),
+            PlayerCommand::RemoveItem{ item } => write!(f, "remove {}", item),
+            PlayerCommand::SelectTarget{ target } => write!(f, "aim {} {}", target.x, target.y),
+            PlayerCommand::Maneuver{ maneuver, target } => {
+                let name = match maneuver {
+                    Maneuver::Shove => "shove",
+                    Maneuver::Disarm => "disarm",
+                    Maneuver::Trip => "trip"
+                };
+                write!(f, "maneuver {} {} {}", name, target.x, target.y)
+            }
+            PlayerCommand::SellItem{ item } => write!(f, "sell {}", item),
+            PlayerCommand::BuyItem{ index } => write!(f, "buy {}", index),
+            PlayerCommand::RemoveCurse{ item } => write!(f, "uncurse {}", item),
+            PlayerCommand::Identify{ item } => write!(f, "identify {}", item),
+            PlayerCommand::Cheat{ cheat } => {
+                let name = match cheat {
+                    Cheat::TeleportToExit => "exit",
+                    Cheat::Heal => "heal",
+                    Cheat::RevealMap => "reveal",
+                    Cheat::GodMode => "god",
+                    Cheat::ReloadRaws => "reload-raws"
+                };
+                write!(f, "cheat {}", name)
+            }
+            PlayerCommand::Cancel => write!(f, "cancel")
+        }
+    }
+}
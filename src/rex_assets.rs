@@ -8,15 +8,20 @@ pub struct RexAssets {
     pub menu : XpFile
 }
 
+// Map builders read the embedded files too, so they can be linked without the menu assets
+pub fn link_resources() {
+    rltk::link_resource!(MENU, "../resources/menu.xp");
+    rltk::link_resource!(WFC_DEMO_IMAGE1, "../resources/wfc-demo1.xp");
+    rltk::link_resource!(WFC_POPULATED, "../resources/wfc-populated.xp");
+}
+
 impl RexAssets {
     #[allow(clippy::new_without_default)]
     pub fn new() -> RexAssets {
-        rltk::link_resource!(MENU, "../resources/menu.xp");
-        rltk::link_resource!(WFC_DEMO_IMAGE1, "../resources/wfc-demo1.xp");
-        rltk::link_resource!(WFC_POPULATED, "../resources/wfc-populated.xp");
+        link_resources();
 
         RexAssets{
             menu : XpFile::from_resource("../resources/menu.xp").unwrap()
         }
     }
-}
\ No newline at end of file
+}
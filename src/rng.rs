@@ -9,10 +9,26 @@ pub fn reseed(seed: u64) {
     *RNG.lock().unwrap() = RandomNumberGenerator::seeded(seed);
 }
 
+pub fn new_run_seed() -> u64 {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos() as u64).unwrap_or(0)
+}
+
+pub fn level_seed(run_seed: u64, depth: i32) -> u64 {
+    let mut z = run_seed ^ (depth as i64 as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
 pub fn roll_dice(n:i32, die_type: i32) -> i32 {
     RNG.lock().unwrap().roll_dice(n, die_type)
 }
 
 pub fn range(min: i32, max: i32) -> i32 {
     RNG.lock().unwrap().range(min, max)
-}
\ No newline at end of file
+}
+// Lends the run's generator to code that takes its own, like attack resolution
+pub fn with_rng<R>(f: impl FnOnce(&mut RandomNumberGenerator) -> R) -> R {
+    f(&mut RNG.lock().unwrap())
+}
@@ -1,22 +1,415 @@
-use specs::{prelude::*, saveload::{SimpleMarker, SimpleMarkerAllocator, SerializeComponents, DeserializeComponents, MarkedBuilder}};
+use specs::{prelude::*, saveload::{SimpleMarker, SimpleMarkerAllocator, SerializeComponents, DeserializeComponents, MarkedBuilder,
+    MarkerAllocator, ConvertSaveload, EntityData}};
 use super::components::*;
-use std::{fs::File, path::Path, fs::{self}, convert::Infallible};
-
-macro_rules! serialize_individually {
-    ($ecs:expr, $ser:expr, $data:expr, $( $type:ty),*) => {
-        $(
-        SerializeComponents::<Infallible, SimpleMarker<SerializeMe>>::serialize(
-            &( $ecs.read_storage::<$type>(), ),
-            &$data.0,
-            &$data.1,
-            &mut $ser,
-        )
-        .unwrap();
-        )*
+use std::{fs::File, io::{BufWriter, Write}, path::{Path, PathBuf}, fs::{self}, convert::Infallible, marker::PhantomData,
+    collections::BTreeMap};
+use serde::{Serialize, Deserialize, Serializer, ser::{SerializeMap, SerializeSeq}, de::IgnoredAny};
+use serde_json::{json, Value, Map as JsonMap};
+use crate::map::{MapId, Branch};
+
+const LEGACY_SAVE_FILE : &str = "./savegame.json";
+const AUTOSAVE_NAME : &str = "autosave";
+pub const SAVE_FORMAT_VERSION : u64 = 12;
+pub const SAVE_SLOTS : i32 = 5;
+
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum SaveEncoding { Json, Binary }
+
+const ENCODINGS : [SaveEncoding; 2] = [SaveEncoding::Binary, SaveEncoding::Json];
+
+impl SaveEncoding {
+    fn extension(self) -> &'static str {
+        match self {
+            SaveEncoding::Json => "json",
+            SaveEncoding::Binary => "sav"
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct SaveMetadata {
+    pub level : i32,
+    pub depth : i32,
+    pub map_name : String,
+    pub turns : i32,
+    pub timestamp : u64
+}
+
+#[derive(Serialize, Deserialize)]
+struct SaveHeader {
+    format_version : u64,
+    #[serde(default)]
+    game_version : String,
+    #[serde(default)]
+    meta : Option<SaveMetadata>
+}
+
+#[derive(Deserialize)]
+struct SaveHeaderOnly {
+    header : SaveHeader
+}
+
+// Format 1 wrote the storages back to back in this order, without names or a header
+const LEGACY_COMPONENTS : &[&str] = &[
+    "Position", "Renderable", "Player", "Viewshed", "Name", "BlocksTile", "SpawnParticleLine", "WantsToMelee", "Item",
+    "Consumable", "Ranged", "InflictsDamage", "AreaOfEffect", "Confusion", "ProvidesHealing", "InBackpack",
+    "SpawnParticleBurst", "WantsToPickupItem", "WantsToUseItem", "WantsToDropItem", "SerializationHelper", "Equippable",
+    "Equipped", "Weapon", "Wearable", "TownPortal", "WantsToRemoveItem", "ParticleLifetime", "HungerClock",
+    "ProvidesFood", "MagicMapper", "Hidden", "EntryTrigger", "EntityMoved", "SingleActivation", "BlocksVisibility",
+    "Door", "Quips", "Attributes", "Skills", "Pools", "NaturalAttackDefense", "LootTable", "EquipmentChanged", "Vendor",
+    "TeleportTo", "OtherLevelPosition", "DMSerializationHelper", "LightSource", "Initiative", "MyTurn", "Faction",
+    "WantsToApproach", "WantsToFlee", "MoveMode", "Chasing", "ApplyMove", "ApplyTeleport", "MagicItem",
+    "ObfuscatedName", "IdentifiedItem", "CursedItem", "ProvidesRemoveCurse", "ProvidesIdentification", "AttributeBonus",
+    "Duration", "StatusEffect", "KnownSpells", "SpellTemplate", "WantsToCastSpell", "ProvidesMana", "TeachesSpell",
+    "Slow", "DamageOverTime", "SpecialAbilities", "TileSize", "OnDeath", "AlwaysTargetsSelf", "Stationary", "Target",
+    "WantsToShoot"
+];
+
+type Migration = fn(&mut Value) -> Result<(), String>;
+
+// Each entry upgrades a save by one format version, the first one from version 2
+const MIGRATIONS : &[Migration] = &[migrate_plain_map_layers, migrate_map_themes, migrate_map_ids, migrate_tile_damage, migrate_gas,
+    migrate_ranged_slot, migrate_damage_types, migrate_critical_ranges,
+    migrate_stealth_skills, migrate_compact_saves];
+
+// Format 3 run-length encodes the map layers, but the plain arrays of format 2 still deserialize
+fn migrate_plain_map_layers(save : &mut Value) -> Result<(), String> {
+    save["header"]["format_version"] = json!(3);
+    Ok(())
+}
+
+fn add_map_themes(value : &mut Value) {
+    match value {
+        Value::Object(fields) => {
+            if fields.contains_key("tiles") && !fields.contains_key("theme") {
+                if let Some(depth) = fields.get("depth").and_then(|d| d.as_i64()) {
+                    let theme = crate::raws::RAWS.lock().unwrap().level_for(MapId::new(Branch::Main, depth as i32))
+                        .map(|level| level.theme)
+                        .unwrap_or_default();
+                    fields.insert("theme".to_string(), json!(theme));
+                }
+            }
+            fields.values_mut().for_each(add_map_themes);
+        }
+        Value::Array(values) => values.iter_mut().for_each(add_map_themes),
+        _ => {}
+    }
+}
+
+// Format 4 stores the level theme on each map instead of deriving it from the depth
+fn migrate_map_themes(save : &mut Value) -> Result<(), String> {
+    add_map_themes(&mut save["components"]);
+    save["header"]["format_version"] = json!(4);
+    Ok(())
+}
+
+fn add_map_branches(value : &mut Value) {
+    match value {
+        Value::Object(fields) => {
+            if fields.contains_key("tiles") && !fields.contains_key("branch") {
+                fields.insert("branch".to_string(), json!(Branch::Main));
+                fields.insert("connections".to_string(), json!({}));
+            }
+            fields.values_mut().for_each(add_map_branches);
+        }
+        Value::Array(values) => values.iter_mut().for_each(add_map_branches),
+        _ => {}
+    }
+}
+
+// Every stored component of the given type, skipping the entities that lack it
+fn stored_components<'a>(save : &'a mut Value, component : &str) -> impl Iterator<Item = &'a mut JsonMap<String, Value>> {
+    save["components"][component].as_array_mut().into_iter().flatten()
+        .filter_map(|entry| entry["components"][0].as_object_mut())
+}
+
+fn depth_to_map_id(fields : &mut JsonMap<String, Value>, depth_key : &str, map_key : &str) {
+    if let Some(depth) = fields.remove(depth_key).and_then(|depth| depth.as_i64()) {
+        fields.insert(map_key.to_string(), json!(MapId::new(Branch::Main, depth as i32)));
+    }
+}
+
+// Format 5 tells maps apart by branch as well as depth, everything saved before lives in the main branch
+fn migrate_map_ids(save : &mut Value) -> Result<(), String> {
+    add_map_branches(&mut save["components"]);
+    for fields in stored_components(save, "OtherLevelPosition") {
+        depth_to_map_id(fields, "depth", "map");
+    }
+    for fields in stored_components(save, "TeleportTo") {
+        depth_to_map_id(fields, "depth", "map");
+    }
+    for fields in stored_components(save, "ApplyTeleport") {
+        depth_to_map_id(fields, "dest_depth", "dest_map");
+    }
+    for fields in stored_components(save, "DMSerializationHelper") {
+        if let Some(Value::Object(maps)) = fields.get_mut("map").and_then(|dungeon| dungeon.get_mut("maps")) {
+            let by_depth = std::mem::take(maps);
+            for (depth, map) in by_depth {
+                let depth = depth.parse::<i32>().map_err(|_| format!("nieznany poziom lochu [{}]", depth))?;
+                maps.insert(MapId::new(Branch::Main, depth).to_string(), map);
+            }
+        }
+    }
+    save["header"]["format_version"] = json!(5);
+    Ok(())
+}
+
+fn add_tile_damage(value : &mut Value) {
+    match value {
+        Value::Object(fields) => {
+            if fields.contains_key("tiles") && !fields.contains_key("tile_damage") {
+                fields.insert("tile_damage".to_string(), json!({}));
+            }
+            fields.values_mut().for_each(add_tile_damage);
+        }
+        Value::Array(values) => values.iter_mut().for_each(add_tile_damage),
+        _ => {}
+    }
+}
+
+// Format 6 remembers how far each tile has been dug into
+fn migrate_tile_damage(save : &mut Value) -> Result<(), String> {
+    add_tile_damage(&mut save["components"]);
+    save["header"]["format_version"] = json!(6);
+    Ok(())
+}
+
+fn add_gas(value : &mut Value) {
+    match value {
+        Value::Object(fields) => {
+            if fields.contains_key("tiles") && !fields.contains_key("gas") {
+                fields.insert("gas".to_string(), json!({}));
+            }
+            fields.values_mut().for_each(add_gas);
+        }
+        Value::Array(values) => values.iter_mut().for_each(add_gas),
+        _ => {}
+    }
+}
+
+// Format 7 keeps the gas clouds hanging over each map
+fn migrate_gas(save : &mut Value) -> Result<(), String> {
+    add_gas(&mut save["components"]);
+    save["header"]["format_version"] = json!(7);
+    Ok(())
+}
+
+// Format 8 wields ranged weapons in their own slot, they used to share the melee one
+fn migrate_ranged_slot(save : &mut Value) -> Result<(), String> {
+    let ranged : Vec<Value> = save["components"]["Weapon"].as_array().into_iter().flatten()
+        .filter(|entry| !entry["components"][0]["range"].is_null())
+        .map(|entry| entry["marker"].clone())
+        .collect();
+    for component in ["Equippable", "Equipped"] {
+        for entry in save["components"][component].as_array_mut().into_iter().flatten() {
+            if ranged.contains(&entry["marker"]) {
+                entry["components"][0]["slot"] = json!(EquipmentSlot::Ranged);
+            }
+        }
+    }
+    save["header"]["format_version"] = json!(8);
+    Ok(())
+}
+
+// Format 9 types all damage, everything that hurt before was physical
+fn migrate_damage_types(save : &mut Value) -> Result<(), String> {
+    for component in ["InflictsDamage", "DamageOverTime", "Weapon"] {
+        for fields in stored_components(save, component) {
+            fields.entry("damage_type").or_insert(json!(DamageType::Physical));
+        }
+    }
+    for fields in stored_components(save, "NaturalAttackDefense") {
+        for attack in fields.get_mut("attacks").and_then(|attacks| attacks.as_array_mut()).into_iter().flatten() {
+            if let Some(attack) = attack.as_object_mut() {
+                attack.entry("damage_type").or_insert(json!(DamageType::Physical));
+            }
+        }
+    }
+    save["header"]["format_version"] = json!(9);
+    Ok(())
+}
+
+// Format 10 lets weapons crit on more than a natural 20 and for more than double dice
+fn migrate_critical_ranges(save : &mut Value) -> Result<(), String> {
+    for fields in stored_components(save, "Weapon") {
+        fields.entry("crit_range").or_insert(json!(20));
+        fields.entry("crit_multiplier").or_insert(json!(2));
+    }
+    save["header"]["format_version"] = json!(10);
+    Ok(())
+}
+
+// Format 11 adds the Stealth skill, and mobs start using Search to spot the player
+fn migrate_stealth_skills(save : &mut Value) -> Result<(), String> {
+    for fields in stored_components(save, "Skills") {
+        if let Some(skills) = fields.get_mut("skills").and_then(|skills| skills.as_object_mut()) {
+            skills.entry("Search").or_insert(json!(1));
+            skills.entry("Stealth").or_insert(json!(1));
+        }
+    }
+    save["header"]["format_version"] = json!(11);
+    Ok(())
+}
+
+// Format 12 leaves out the entities a storage has nothing for and packs the visibility layers,
+// the old layouts still deserialize
+fn migrate_compact_saves(save : &mut Value) -> Result<(), String> {
+    save["header"]["format_version"] = json!(12);
+    Ok(())
+}
+
+type Marker = SimpleMarker<SerializeMe>;
+type StoredEntity<C> = EntityData<Marker, (Option<<C as ConvertSaveload<Marker>>::Data>,)>;
+
+// One storage straight into the save, only the entities that have the component
+struct StoredComponents<'a, C> {
+    ecs : &'a World,
+    component : PhantomData<C>
+}
+
+impl<C> Serialize for StoredComponents<'_, C>
+where C : Component + ConvertSaveload<Marker>, Infallible : From<<C as ConvertSaveload<Marker>>::Error>
+{
+    fn serialize<S : Serializer>(&self, serializer : S) -> Result<S::Ok, S::Error> {
+        let entities = self.ecs.entities();
+        let markers = self.ecs.read_storage::<Marker>();
+        let storage = (self.ecs.read_storage::<C>(),);
+        let mut stored = serializer.serialize_seq(Some((&storage.0, &markers).join().count()))?;
+        for (entity, _, marker) in (&entities, &storage.0, &markers).join() {
+            let components = SerializeComponents::<Infallible, Marker>::serialize_entity(&storage, entity, |e| markers.get(e).cloned())
+                .map_err(serde::ser::Error::custom)?;
+            stored.serialize_element(&EntityData{ marker: *marker, components })?;
+        }
+        stored.end()
+    }
+}
+
+macro_rules! stored_components {
+    ($( $type:ident ),*) => {
+        struct SavedComponents<'a> {
+            ecs : &'a World
+        }
+
+        impl Serialize for SavedComponents<'_> {
+            fn serialize<S : Serializer>(&self, serializer : S) -> Result<S::Ok, S::Error> {
+                let mut components = serializer.serialize_map(None)?;
+                $(
+                components.serialize_entry(stringify!($type), &StoredComponents::<$type>{ ecs: self.ecs, component: PhantomData })?;
+                )*
+                components.end()
+            }
+        }
+
+        #[allow(non_snake_case)]
+        #[derive(Deserialize, Default)]
+        #[serde(default)]
+        struct LoadedComponents {
+            $( $type : Vec<StoredEntity<$type>>, )*
+            #[serde(flatten)]
+            unknown : BTreeMap<String, IgnoredAny>
+        }
+
+        impl LoadedComponents {
+            fn insert_into(self, ecs : &World) -> Result<(), String> {
+                let entities = ecs.entities();
+                let mut markers = ecs.write_storage::<Marker>();
+                let mut allocator = ecs.write_resource::<SimpleMarkerAllocator<SerializeMe>>();
+                $(
+                {
+                    let mut storage = (ecs.write_storage::<$type>(),);
+                    for stored in self.$type {
+                        let entity = allocator.retrieve_entity(stored.marker, &mut markers, &entities);
+                        DeserializeComponents::<Infallible, Marker>::deserialize_entity(&mut storage, entity, stored.components,
+                            |marker| Some(allocator.retrieve_entity(marker, &mut markers, &entities)))
+                            .map_err(|e| format!("uszkodzony komponent {}: {}", stringify!($type), e))?;
+                    }
+                }
+                )*
+                for unknown in self.unknown.keys() {
+                    rltk::console::log(format!("Skipping unknown component in save: {}", unknown));
+                }
+                Ok(())
+            }
+        }
     };
 }
 
-pub fn save_game(ecs : &mut World) {
+stored_components!(Position, Renderable, Player, Viewshed, Name, BlocksTile, SpawnParticleLine,
+    WantsToMelee, Item, Consumable, Ranged, InflictsDamage, AreaOfEffect, Confusion, ProvidesHealing, InBackpack, SpawnParticleBurst,
+    WantsToPickupItem, WantsToUseItem, WantsToDropItem, SerializationHelper, Equippable, Equipped, Weapon, Wearable, TownPortal,
+    WantsToRemoveItem, ParticleLifetime, HungerClock, ProvidesFood, MagicMapper, Hidden, EntryTrigger, EntityMoved, SingleActivation,
+    BlocksVisibility, Door, Quips, Attributes, Skills, Pools, NaturalAttackDefense, LootTable, EquipmentChanged, Vendor, TeleportTo,
+    OtherLevelPosition, DMSerializationHelper, LightSource, Initiative, MyTurn, Faction, WantsToApproach, WantsToFlee, MoveMode, Chasing,
+    ApplyMove, ApplyTeleport, MagicItem, ObfuscatedName, IdentifiedItem, CursedItem, ProvidesRemoveCurse, ProvidesIdentification,
+    AttributeBonus, Duration, StatusEffect, KnownSpells, SpellTemplate, WantsToCastSpell, ProvidesMana, TeachesSpell, Slow, DamageOverTime,
+    SpecialAbilities, TileSize, OnDeath, AlwaysTargetsSelf, Stationary, Target, WantsToShoot, Digs, PoisonGas,
+    Ammunition, Magazine, Reloading, Resistances, WantsToManeuver, Maneuvers, Prone, Awareness
+);
+
+#[derive(Serialize)]
+struct SaveFile<'a> {
+    header : SaveHeader,
+    components : SavedComponents<'a>
+}
+
+#[derive(Deserialize)]
+struct LoadedSave {
+    components : LoadedComponents
+}
+
+fn save_dir() -> PathBuf {
+    if let Some(dir) = std::env::var_os("WIECLAW_SAVE_DIR") {
+        return PathBuf::from(dir);
+    }
+    let base = std::env::var_os("XDG_DATA_HOME").map(PathBuf::from)
+        .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".local").join("share")))
+        .unwrap_or_else(|| PathBuf::from("."));
+    base.join("wieclaw_rl")
+}
+
+fn save_path(name : &str, encoding : SaveEncoding) -> PathBuf {
+    save_dir().join(format!("{}.{}", name, encoding.extension()))
+}
+
+fn slot_name(slot : i32) -> String {
+    format!("slot{}", slot + 1)
+}
+
+fn slot_path(slot : i32, encoding : SaveEncoding) -> PathBuf {
+    save_path(&slot_name(slot), encoding)
+}
+
+fn existing_save_path(name : &str) -> Option<(PathBuf, SaveEncoding)> {
+    ENCODINGS.iter()
+        .map(|encoding| (save_path(name, *encoding), *encoding))
+        .find(|(path, _)| path.exists())
+}
+
+fn existing_slot_path(slot : i32) -> Option<(PathBuf, SaveEncoding)> {
+    existing_save_path(&slot_name(slot))
+}
+
+fn save_metadata(ecs : &World) -> SaveMetadata {
+    let map = ecs.fetch::<super::map::Map>();
+    let player_entity = ecs.fetch::<Entity>();
+    let level = ecs.read_storage::<Pools>().get(*player_entity).map_or(1, |pools| pools.level);
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    SaveMetadata{
+        level,
+        depth : map.depth,
+        map_name : map.name.clone(),
+        turns : crate::gamelog::get_event_count("Turn"),
+        timestamp
+    }
+}
+
+// The map and the dungeon state ride along in helper entities for as long as the save is written
+fn with_save<R>(ecs : &mut World, write : impl FnOnce(&SaveFile) -> R) -> R {
+    let metadata = save_metadata(ecs);
     let mapcopy = ecs.get_mut::<super::map::Map>().unwrap().clone();
     let dungeon_master = ecs.get_mut::<super::map::dungeon::MasterDungeonMap>().unwrap().clone();
     let savehelper = ecs
@@ -30,47 +423,182 @@ pub fn save_game(ecs : &mut World) {
         .marked::<SimpleMarker<SerializeMe>>()
         .build();
 
-    {
-        let data = ( ecs.entities(), ecs.read_storage::<SimpleMarker<SerializeMe>>() );
-
-        let writer = File::create("./savegame.json").unwrap();
-        let mut serializer = serde_json::Serializer::new(writer);
-        serialize_individually!(ecs, serializer, data, Position, Renderable, Player, Viewshed, Name, BlocksTile, SpawnParticleLine,
-            WantsToMelee, Item, Consumable, Ranged, InflictsDamage, AreaOfEffect, Confusion, ProvidesHealing, InBackpack, SpawnParticleBurst,
-            WantsToPickupItem, WantsToUseItem, WantsToDropItem, SerializationHelper, Equippable, Equipped, Weapon, Wearable, TownPortal,
-            WantsToRemoveItem, ParticleLifetime, HungerClock, ProvidesFood, MagicMapper, Hidden, EntryTrigger, EntityMoved, SingleActivation,
-            BlocksVisibility, Door, Quips, Attributes, Skills, Pools, NaturalAttackDefense, LootTable, EquipmentChanged, Vendor, TeleportTo,
-            OtherLevelPosition, DMSerializationHelper, LightSource, Initiative, MyTurn, Faction, WantsToApproach, WantsToFlee, MoveMode, Chasing,
-            ApplyMove, ApplyTeleport, MagicItem, ObfuscatedName, IdentifiedItem, CursedItem, ProvidesRemoveCurse, ProvidesIdentification,
-            AttributeBonus, Duration, StatusEffect, KnownSpells, SpellTemplate, WantsToCastSpell, ProvidesMana, TeachesSpell, Slow, DamageOverTime,
-            SpecialAbilities, TileSize, OnDeath, AlwaysTargetsSelf, Stationary, Target, WantsToShoot
-        );
-    }
+    let result = write(&SaveFile{
+        header : SaveHeader{
+            format_version : SAVE_FORMAT_VERSION,
+            game_version : env!("CARGO_PKG_VERSION").to_string(),
+            meta : Some(metadata)
+        },
+        components : SavedComponents{ ecs }
+    });
 
     ecs.delete_entity(savehelper).expect("Crash on cleanup");
     ecs.delete_entity(savehelper2).expect("Crash on cleanup");
+    result
+}
+
+fn write_save(path : &Path, save : &SaveFile, encoding : SaveEncoding) -> Result<(), String> {
+    // Written next to the target and renamed over it, so a crash mid-write leaves the old save intact
+    let temp_path = path.with_extension(format!("{}.tmp", encoding.extension()));
+    let file = File::create(&temp_path).map_err(|e| format!("nie mozna utworzyc zapisu: {}", e))?;
+    let mut writer = BufWriter::new(file);
+    match encoding {
+        SaveEncoding::Json => serde_json::to_writer(&mut writer, save).map_err(|e| e.to_string())?,
+        SaveEncoding::Binary => ciborium::ser::into_writer(save, &mut writer).map_err(|e| e.to_string())?
+    }
+    writer.flush().map_err(|e| e.to_string())?;
+    writer.get_ref().sync_all().map_err(|e| e.to_string())?;
+    fs::rename(&temp_path, path).map_err(|e| format!("nie mozna zapisac gry: {}", e))
+}
+
+fn save_as(ecs : &mut World, name : &str, encoding : SaveEncoding) -> Result<(), String> {
+    fs::create_dir_all(save_dir()).map_err(|e| format!("nie mozna utworzyc katalogu zapisów: {}", e))?;
+    with_save(ecs, |save| write_save(&save_path(name, encoding), save, encoding))?;
+    for other in ENCODINGS.iter().filter(|e| **e != encoding) {
+        let path = save_path(name, *other);
+        if path.exists() { fs::remove_file(path).map_err(|e| e.to_string())?; }
+    }
+    Ok(())
+}
+
+pub fn save_game(ecs : &mut World, slot : i32, encoding : SaveEncoding) -> Result<(), String> {
+    save_as(ecs, &slot_name(slot), encoding)
+}
+
+pub fn autosave(ecs : &mut World, encoding : SaveEncoding) -> Result<(), String> {
+    save_as(ecs, AUTOSAVE_NAME, encoding)
+}
+
+pub fn does_autosave_exist() -> bool {
+    existing_save_path(AUTOSAVE_NAME).is_some()
 }
 
 pub fn does_save_exist() -> bool {
-    Path::new("./savegame.json").exists()
-}
-
-macro_rules! deserialize_individually {
-    ($ecs:expr, $de:expr, $data:expr, $( $type:ty),*) => {
-        $(
-        DeserializeComponents::<Infallible, _>::deserialize(
-            &mut ( &mut $ecs.write_storage::<$type>(), ),
-            &$data.0,
-            &mut $data.1,
-            &mut $data.2,
-            &mut $de,
-        )
-        .unwrap();
-        )*
+    (0..SAVE_SLOTS).any(|slot| existing_slot_path(slot).is_some())
+}
+
+pub fn list_save_slots() -> Vec<Option<SaveMetadata>> {
+    (0..SAVE_SLOTS).map(|slot| {
+        let (path, encoding) = existing_slot_path(slot)?;
+        let data = fs::read(path).ok()?;
+        let header = match encoding {
+            SaveEncoding::Json => serde_json::from_slice::<SaveHeaderOnly>(&data).ok(),
+            SaveEncoding::Binary => ciborium::de::from_reader::<SaveHeaderOnly, _>(data.as_slice()).ok()
+        };
+        Some(header.and_then(|save| save.header.meta).unwrap_or_default())
+    }).collect()
+}
+
+pub fn import_legacy_save() {
+    if !Path::new(LEGACY_SAVE_FILE).exists() { return; }
+    if let Some(slot) = (0..SAVE_SLOTS).find(|slot| existing_slot_path(*slot).is_none()) {
+        let imported = fs::create_dir_all(save_dir())
+            .and_then(|_| fs::copy(LEGACY_SAVE_FILE, slot_path(slot, SaveEncoding::Json)))
+            .and_then(|_| fs::remove_file(LEGACY_SAVE_FILE));
+        if let Err(e) = imported {
+            rltk::console::log(format!("Unable to import {}: {}", LEGACY_SAVE_FILE, e));
+        }
+    }
+}
+
+fn upgrade_legacy_save(data : &str) -> Result<Value, String> {
+    let mut components = JsonMap::new();
+    let stream = serde_json::Deserializer::from_str(data).into_iter::<Value>();
+    for (name, stored) in LEGACY_COMPONENTS.iter().zip(stream) {
+        let stored = stored.map_err(|e| format!("nieczytelny zapis: {}", e))?;
+        components.insert(name.to_string(), stored);
+    }
+    Ok(json!({
+        "header" : { "format_version" : 1, "game_version" : "0.1.0" },
+        "components" : components
+    }))
+}
+
+// Binary saves key some maps by number, JSON only by string
+fn cbor_to_json(value : ciborium::Value) -> Value {
+    use ciborium::Value as Cbor;
+    match value {
+        Cbor::Null => Value::Null,
+        Cbor::Bool(b) => json!(b),
+        Cbor::Integer(n) => i64::try_from(n).map(|n| json!(n)).unwrap_or_else(|_| json!(u64::try_from(n).unwrap_or(u64::MAX))),
+        Cbor::Float(f) => json!(f),
+        Cbor::Text(text) => json!(text),
+        Cbor::Bytes(bytes) => json!(bytes),
+        Cbor::Tag(_, value) => cbor_to_json(*value),
+        Cbor::Array(values) => Value::Array(values.into_iter().map(cbor_to_json).collect()),
+        Cbor::Map(entries) => Value::Object(entries.into_iter().map(|(key, value)| {
+            let key = match key {
+                Cbor::Text(text) => text,
+                key => cbor_to_json(key).to_string()
+            };
+            (key, cbor_to_json(value))
+        }).collect()),
+        _ => Value::Null
+    }
+}
+
+fn decode_save(data : &[u8], encoding : SaveEncoding) -> Result<Value, String> {
+    match encoding {
+        SaveEncoding::Binary => ciborium::de::from_reader::<ciborium::Value, _>(data)
+            .map(cbor_to_json)
+            .map_err(|e| format!("nieczytelny zapis: {}", e)),
+        SaveEncoding::Json => match serde_json::from_slice::<Value>(data) {
+            Ok(save) if save.get("header").is_some() => Ok(save),
+            _ => upgrade_legacy_save(std::str::from_utf8(data).map_err(|e| format!("nieczytelny zapis: {}", e))?)
+        }
+    }
+}
+
+// Brings a save of any format up to the current one, as a tree
+fn read_save(data : &[u8], encoding : SaveEncoding) -> Result<JsonMap<String, Value>, String> {
+    let mut save = decode_save(data, encoding)?;
+
+    let version = save["header"]["format_version"].as_u64().ok_or("brak wersji formatu zapisu")?;
+    if version > SAVE_FORMAT_VERSION {
+        let game_version = save["header"]["game_version"].as_str().unwrap_or("?");
+        return Err(format!("zapis pochodzi z nowszej wersji gry ({})", game_version));
+    }
+    for migration in MIGRATIONS.iter().skip(version.saturating_sub(2) as usize) {
+        migration(&mut save)?;
+    }
+
+    match save["components"].take() {
+        Value::Object(components) => Ok(components),
+        _ => Err("zapis nie zawiera komponentów".to_string())
+    }
+}
+
+// A save in the current format decodes straight into the components, an older one is migrated first
+fn load_save(data : &[u8], encoding : SaveEncoding) -> Result<LoadedComponents, String> {
+    let header = match encoding {
+        SaveEncoding::Json => serde_json::from_slice::<SaveHeaderOnly>(data).ok(),
+        SaveEncoding::Binary => ciborium::de::from_reader::<SaveHeaderOnly, _>(data).ok()
+    };
+    let loaded = if header.is_some_and(|save| save.header.format_version == SAVE_FORMAT_VERSION) {
+        match encoding {
+            SaveEncoding::Json => serde_json::from_slice::<LoadedSave>(data).map_err(|e| e.to_string()),
+            SaveEncoding::Binary => ciborium::de::from_reader::<LoadedSave, _>(data).map_err(|e| e.to_string())
+        }.map(|save| save.components)
+    } else {
+        serde_json::from_value(Value::Object(read_save(data, encoding)?)).map_err(|e| e.to_string())
     };
+    loaded.map_err(|e| format!("uszkodzony zapis: {}", e))
 }
 
-pub fn load_game(ecs: &mut World) {
+pub fn load_game(ecs: &mut World, slot : i32) -> Result<(), String> {
+    let (path, encoding) = existing_slot_path(slot).ok_or("zapis nie istnieje")?;
+    load_from_file(ecs, &path, encoding)
+}
+
+pub fn load_autosave(ecs : &mut World) -> Result<(), String> {
+    let (path, encoding) = existing_save_path(AUTOSAVE_NAME).ok_or("autozapis nie istnieje")?;
+    load_from_file(ecs, &path, encoding)
+}
+
+fn load_from_file(ecs : &mut World, path : &Path, encoding : SaveEncoding) -> Result<(), String> {
+    let data = fs::read(path).map_err(|e| format!("nie mozna odczytac zapisu: {}", e))?;
+    let components = load_save(&data, encoding)?;
+
     {
         let mut to_delete = Vec::new();
         for e in ecs.entities().join() {
@@ -80,24 +608,7 @@ pub fn load_game(ecs: &mut World) {
             ecs.delete_entity(*del).expect("Deletion failed");
         }
     }
-
-    let data = fs::read_to_string("./savegame.json").unwrap();
-    let mut de = serde_json::Deserializer::from_str(&data);
-
-    {
-        let mut d = (&mut ecs.entities(), &mut ecs.write_storage::<SimpleMarker<SerializeMe>>(), &mut ecs.write_resource::<SimpleMarkerAllocator<SerializeMe>>());
-        
-        deserialize_individually!(ecs, de, d, Position, Renderable, Player, Viewshed, Name, BlocksTile, SpawnParticleLine,
-            WantsToMelee, Item, Consumable, Ranged, InflictsDamage, AreaOfEffect, Confusion, ProvidesHealing, InBackpack, SpawnParticleBurst,
-            WantsToPickupItem, WantsToUseItem, WantsToDropItem, SerializationHelper, Equippable, Equipped, Weapon, Wearable, TownPortal,
-            WantsToRemoveItem, ParticleLifetime, HungerClock, ProvidesFood, MagicMapper, Hidden, EntryTrigger, EntityMoved, SingleActivation,
-            BlocksVisibility, Door, Quips, Attributes, Skills, Pools, NaturalAttackDefense, LootTable, EquipmentChanged, Vendor, TeleportTo,
-            OtherLevelPosition, DMSerializationHelper, LightSource, Initiative, MyTurn, Faction, WantsToApproach, WantsToFlee, MoveMode, Chasing,
-            ApplyMove, ApplyTeleport, MagicItem, ObfuscatedName, IdentifiedItem, CursedItem, ProvidesRemoveCurse, ProvidesIdentification,
-            AttributeBonus, Duration, StatusEffect, KnownSpells, SpellTemplate, WantsToCastSpell, ProvidesMana, TeachesSpell, Slow, DamageOverTime,
-            SpecialAbilities, TileSize, OnDeath, AlwaysTargetsSelf, Stationary, Target, WantsToShoot
-        );
-    }
+    components.insert_into(ecs)?;
 
     let mut deleteme : Option<Entity> = None;
     let mut deleteme2 : Option<Entity> = None;
@@ -127,10 +638,197 @@ pub fn load_game(ecs: &mut World) {
             *player_resource = e;
         }
     }
-    ecs.delete_entity(deleteme.unwrap()).expect("Unable to delete helper");
-    ecs.delete_entity(deleteme2.unwrap()).expect("Unable to delete marker");
+    ecs.delete_entity(deleteme.ok_or("zapis nie zawiera mapy")?).expect("Unable to delete helper");
+    ecs.delete_entity(deleteme2.ok_or("zapis nie zawiera stanu lochu")?).expect("Unable to delete marker");
+    Ok(())
+}
+
+pub fn delete_save(slot : i32) -> Result<(), String> {
+    for encoding in ENCODINGS.iter() {
+        let path = slot_path(slot, *encoding);
+        if path.exists() { fs::remove_file(path).map_err(|e| format!("nie mozna usunac zapisu: {}", e))?; }
+    }
+    Ok(())
 }
 
-pub fn delete_save() {
-    if Path::new("./savegame.json").exists() { std::fs::remove_file("./savegame.json").expect("Unable to delete file"); }
-}
\ No newline at end of file
+pub fn world_snapshot(ecs : &World) -> Value {
+    // Loading recreates entities in a different order, so storages are compared by marker
+    let mut components = serde_json::to_value(SavedComponents{ ecs }).unwrap();
+    for stored in components.as_object_mut().unwrap().values_mut() {
+        if let Value::Array(entries) = stored {
+            entries.sort_by_key(|entry| entry["marker"].to_string());
+        }
+    }
+    json!({
+        "components" : components,
+        "map" : *ecs.fetch::<super::map::Map>(),
+        "dungeon" : *ecs.fetch::<super::map::dungeon::MasterDungeonMap>(),
+        "log" : crate::gamelog::clone_log(),
+        "events" : crate::gamelog::clone_events()
+    })
+}
+
+// Saves the world in every encoding and loads it back, returning the size of each file
+pub fn verify_round_trip(ecs : &mut World) -> Result<Vec<(SaveEncoding, u64)>, String> {
+    let expected = world_snapshot(ecs);
+    let dir = std::env::temp_dir().join(format!("wieclaw_rl_verify_{}", std::process::id()));
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+
+    let mut sizes = Vec::new();
+    let mut result = Ok(());
+    for encoding in ENCODINGS.iter() {
+        let path = dir.join(format!("verify.{}", encoding.extension()));
+        result = with_save(ecs, |save| write_save(&path, save, *encoding))
+            .and_then(|_| load_from_file(ecs, &path, *encoding))
+            .and_then(|_| if world_snapshot(ecs) == expected {
+                Ok(())
+            } else {
+                Err(format!("swiat wczytany z zapisu {:?} rozni sie od oryginalu", encoding))
+            });
+        if result.is_err() { break; }
+        sizes.push((*encoding, fs::metadata(&path).map(|m| m.len()).unwrap_or(0)));
+    }
+
+    let _ = fs::remove_dir_all(&dir);
+    result.map(|_| sizes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{headless::Simulation, PlayerCommand};
+
+    #[test]
+    fn binary_and_json_saves_load_into_identical_worlds() {
+        let _lock = crate::headless::TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut sim = Simulation::with_seed(Some(11));
+        for _ in 0..30 {
+            sim.step(PlayerCommand::Wait);
+        }
+
+        let sizes = verify_round_trip(sim.ecs_mut()).expect("save round trip failed");
+        let encodings : Vec<SaveEncoding> = sizes.iter().map(|(encoding, _)| *encoding).collect();
+        assert!(encodings.contains(&SaveEncoding::Binary));
+        assert!(encodings.contains(&SaveEncoding::Json));
+        assert!(sizes.iter().all(|(_, size)| *size > 0));
+    }
+
+    #[test]
+    fn saves_only_store_the_components_entities_have() {
+        let _lock = crate::headless::TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut sim = Simulation::with_seed(Some(12));
+        sim.step(PlayerCommand::Wait);
+
+        let save = with_save(sim.ecs_mut(), |save| serde_json::to_value(save).unwrap());
+        assert_eq!(save["header"]["format_version"], json!(SAVE_FORMAT_VERSION));
+        for (name, stored) in save["components"].as_object().unwrap() {
+            for entry in stored.as_array().unwrap() {
+                assert!(!entry["components"][0].is_null(), "empty {} stored", name);
+            }
+        }
+        assert_eq!(save["components"]["Player"].as_array().unwrap().len(), 1);
+        let map = &save["components"]["SerializationHelper"][0]["components"][0]["map"];
+        assert!(map["visible_tiles"].get("rle").is_some());
+        assert!(map.get("view_blocked").is_none());
+    }
+
+    fn stored(marker : i64, component : Value) -> Value {
+        json!([{ "marker" : marker, "components" : [component] }])
+    }
+
+    // A format 2 save as the game wrote it, a 2x2 map with the plain layer arrays and depths instead of map ids
+    fn format_two_save() -> Value {
+        let black = json!({ "r" : 0.0, "g" : 0.0, "b" : 0.0 });
+        json!({
+            "header" : { "format_version" : 2, "game_version" : "0.1.0" },
+            "components" : {
+                "SerializationHelper" : stored(1, json!({ "map" : {
+                    "tiles" : ["Wall", "Floor", "Floor", "DownStairs"],
+                    "width" : 2, "height" : 2,
+                    "revealed_tiles" : [true, true, false, false],
+                    "visible_tiles" : [false, false, false, false],
+                    "depth" : 3,
+                    "bloodstains" : [], "view_blocked" : [],
+                    "name" : "Piwnica", "outdoors" : false,
+                    "light" : [black.clone(), black.clone(), black.clone(), black]
+                }})),
+                "DMSerializationHelper" : stored(2, json!({ "map" : { "maps" : { "1" : {}, "3" : {} } } })),
+                "OtherLevelPosition" : stored(3, json!({ "x" : 4, "y" : 5, "depth" : 2 })),
+                "TeleportTo" : stored(4, json!({ "x" : 1, "y" : 1, "depth" : 1, "player_only" : true })),
+                "ApplyTeleport" : stored(5, json!({ "dest_x" : 1, "dest_y" : 1, "dest_depth" : 1 })),
+                "Weapon" : json!([
+                    { "marker" : 6, "components" : [{ "range" : 6, "damage_n_dice" : 1, "damage_die_type" : 6 }] },
+                    { "marker" : 7, "components" : [{ "range" : null, "damage_n_dice" : 1, "damage_die_type" : 8 }] }
+                ]),
+                "Equipped" : json!([
+                    { "marker" : 6, "components" : [{ "owner" : 8, "slot" : "Melee" }] },
+                    { "marker" : 7, "components" : [{ "owner" : 8, "slot" : "Melee" }] }
+                ]),
+                "InflictsDamage" : stored(9, json!({ "damage" : 4 })),
+                "NaturalAttackDefense" : stored(10, json!({ "armor_class" : null, "attacks" : [{ "name" : "Ugryzienie" }] })),
+                "Skills" : stored(8, json!({ "skills" : { "Melee" : 1 } }))
+            }
+        })
+    }
+
+    fn first<'a>(components : &'a JsonMap<String, Value>, name : &str) -> &'a Value {
+        &components[name][0]["components"][0]
+    }
+
+    #[test]
+    fn format_two_saves_migrate_to_the_current_format() {
+        let data = serde_json::to_vec(&format_two_save()).unwrap();
+        let components = read_save(&data, SaveEncoding::Json).expect("migration failed");
+
+        let map = &first(&components, "SerializationHelper")["map"];
+        assert_eq!(map["branch"], json!(Branch::Main));
+        assert_eq!(map["tile_damage"], json!({}));
+        assert_eq!(map["gas"], json!({}));
+        assert!(map.get("theme").is_some());
+        let map : crate::map::Map = serde_json::from_value(map.clone()).expect("migrated map does not load");
+        assert_eq!(map.id(), MapId::new(Branch::Main, 3));
+        assert!(map.tiles[3] == crate::TileType::DownStairs);
+
+        let dungeon_maps = first(&components, "DMSerializationHelper")["map"]["maps"].as_object().unwrap();
+        assert!(dungeon_maps.contains_key("main:1") && dungeon_maps.contains_key("main:3"));
+        assert_eq!(first(&components, "OtherLevelPosition")["map"], json!("main:2"));
+        assert!(first(&components, "OtherLevelPosition").get("depth").is_none());
+        assert_eq!(first(&components, "TeleportTo")["map"], json!("main:1"));
+        assert_eq!(first(&components, "ApplyTeleport")["dest_map"], json!("main:1"));
+
+        // Only the weapon with a range moves to the ranged slot
+        assert_eq!(components["Equipped"][0]["components"][0]["slot"], json!(EquipmentSlot::Ranged));
+        assert_eq!(components["Equipped"][1]["components"][0]["slot"], json!(EquipmentSlot::Melee));
+
+        let physical = json!(DamageType::Physical);
+        assert_eq!(first(&components, "InflictsDamage")["damage_type"], physical);
+        assert_eq!(first(&components, "NaturalAttackDefense")["attacks"][0]["damage_type"], physical);
+        for weapon in components["Weapon"].as_array().unwrap() {
+            let weapon = &weapon["components"][0];
+            assert_eq!(weapon["damage_type"], physical);
+            assert_eq!((&weapon["crit_range"], &weapon["crit_multiplier"]), (&json!(20), &json!(2)));
+        }
+        assert_eq!(first(&components, "Skills")["skills"]["Stealth"], json!(1));
+    }
+
+    #[test]
+    fn older_binary_saves_migrate_too() {
+        let mut data = Vec::new();
+        ciborium::ser::into_writer(&format_two_save(), &mut data).unwrap();
+        let components = read_save(&data, SaveEncoding::Binary).expect("migration failed");
+
+        assert_eq!(first(&components, "TeleportTo")["map"], json!("main:1"));
+        assert_eq!(first(&components, "SerializationHelper")["map"]["gas"], json!({}));
+    }
+
+    #[test]
+    fn legacy_saves_are_read_as_a_stream_of_storages() {
+        // Format 1 wrote Position, then Renderable, and so on without a header
+        let position = stored(1, json!({ "x" : 3, "y" : 4 }));
+        let data = format!("{}{}", position, json!([]));
+        let components = read_save(data.as_bytes(), SaveEncoding::Json).expect("legacy import failed");
+
+        assert_eq!(components["Position"], position);
+        assert_eq!(components["Renderable"], json!([]));
+    }
+}
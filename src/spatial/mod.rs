@@ -1,6 +1,6 @@
 use std::sync::Mutex;
 use specs::prelude::*;
-use crate::{Map, tile_walkable, RunState};
+use crate::{Map, TileType, tile_blocks_monsters, RunState};
 
 struct SpatialMap {
     blocked : Vec<(bool, bool)>,
@@ -37,10 +37,16 @@ pub fn clear() {
 pub fn populate_blocked_from_map(map: &Map) {
     let mut lock = SPATIAL_MAP.lock().unwrap();
     for (i,tile) in map.tiles.iter().enumerate() {
-        lock.blocked[i].0 = !tile_walkable(*tile);
+        lock.blocked[i].0 = tile_blocks_monsters(*tile);
     }
 }
 
+// Terrain changed under the index, the entities on the tile still block as they did
+pub fn set_tile_blocked(idx: usize, tile : TileType) {
+    let mut lock = SPATIAL_MAP.lock().unwrap();
+    lock.blocked[idx].0 = tile_blocks_monsters(tile);
+}
+
 pub fn index_entity(entity: Entity, idx: usize, blocks_tile: bool) {
     let mut lock = SPATIAL_MAP.lock().unwrap();
     lock.tile_content[idx].push((entity, blocks_tile));
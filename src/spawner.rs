@@ -1,15 +1,16 @@
 use rltk::RGB;
 use specs::{prelude::*, saveload::{MarkedBuilder, SimpleMarker}};
-use super::{Player, Map, TileType, Renderable, Name, Position, Viewshed, Rect, MasterDungeonMap, OtherLevelPosition,
+use super::{Player, Map, MapId, TileType, Renderable, Name, Position, Viewshed, Rect, MasterDungeonMap, OtherLevelPosition,
             SerializeMe, random_table::MasterTable, HungerState, HungerClock, raws::*, Attributes, EntryTrigger, SingleActivation,
             Attribute, attr_bonus, Skills, Skill, Pools, Pool, player_hp_at_level, mana_at_level, LightSource, TeleportTo,
-            Initiative, Faction, EquipmentChanged, StatusEffect, Duration, AttributeBonus, KnownSpells};
-use std::collections::HashMap;
+            Initiative, Faction, EquipmentChanged, StatusEffect, Duration, AttributeBonus, KnownSpells, Awareness, AwarenessState};
+use std::collections::{HashMap, BTreeMap};
 
 const MAX_MONSTERS : i32 = 4;
+const ASLEEP_CHANCE : i32 = 30;
 
-fn room_table(map_depth: i32) -> MasterTable {
-    get_spawn_table_for_depth(&RAWS.lock().unwrap(), map_depth)
+fn room_table(map: &Map, map_depth: i32) -> MasterTable {
+    get_spawn_table_for_level(&RAWS.lock().unwrap(), MapId::new(map.branch, map_depth))
 }
 
 pub fn player(ecs : &mut World, player_x : i32, player_y : i32) -> Entity {
@@ -18,6 +19,8 @@ pub fn player(ecs : &mut World, player_x : i32, player_y : i32) -> Entity {
     skills.skills.insert(Skill::Melee, 1);
     skills.skills.insert(Skill::Defense, 1);
     skills.skills.insert(Skill::Magic, 1);
+    skills.skills.insert(Skill::Search, 1);
+    skills.skills.insert(Skill::Stealth, 1);
 
     let player = ecs
         .create_entity()
@@ -106,9 +109,9 @@ pub fn spawn_room(map: &Map, room : &Rect, map_depth: i32, spawn_list : &mut Vec
 }
 
 
-pub fn spawn_region(_map: &Map, area : &[usize], map_depth: i32, spawn_list : &mut Vec<(usize, String)>) {
-    let spawn_table = room_table(map_depth);
-    let mut spawn_points : HashMap<usize, String> = HashMap::new();
+pub fn spawn_region(map: &Map, area : &[usize], map_depth: i32, spawn_list : &mut Vec<(usize, String)>) {
+    let spawn_table = room_table(map, map_depth);
+    let mut spawn_points : BTreeMap<usize, String> = BTreeMap::new();
     let mut areas : Vec<usize> = Vec::from(area);
 
     {
@@ -138,7 +141,8 @@ pub fn spawn_entity(ecs: &mut World, spawn : &(&usize, &String)) {
     std::mem::drop(map);
 
     let spawn_result = spawn_named_entity(&RAWS.lock().unwrap(), ecs, &spawn.1, SpawnType::AtPosition { x, y });
-    if spawn_result.is_some() {
+    if let Some(entity) = spawn_result {
+        put_to_sleep(ecs, entity);
         return;
     }
     
@@ -147,9 +151,18 @@ pub fn spawn_entity(ecs: &mut World, spawn : &(&usize, &String)) {
     }
 }
 
+// Some of the monsters waiting on a level are asleep until something wakes them
+fn put_to_sleep(ecs: &mut World, entity: Entity) {
+    if let Some(awareness) = ecs.write_storage::<Awareness>().get_mut(entity) {
+        if crate::rng::roll_dice(1, 100) <= ASLEEP_CHANCE {
+            awareness.state = AwarenessState::Asleep;
+        }
+    }
+}
+
 pub fn spawn_town_portal(ecs: &mut World) {
     let map = ecs.fetch::<Map>();
-    let player_depth = map.depth;
+    let player_map = map.id();
     let player_pos = ecs.fetch::<rltk::Point>();
     let player_x = player_pos.x;
     let player_y = player_pos.y;
@@ -157,7 +170,7 @@ pub fn spawn_town_portal(ecs: &mut World) {
     std::mem::drop(map);
 
     let dm = ecs.fetch::<MasterDungeonMap>();
-    let town_map = dm.get_map(1).unwrap();
+    let town_map = dm.get_map(MapId::TOWN).unwrap();
     let mut stairs_idx = 0;
     for (idx, tt) in town_map.tiles.iter().enumerate() {
         if *tt == TileType::DownStairs {
@@ -170,7 +183,7 @@ pub fn spawn_town_portal(ecs: &mut World) {
     std::mem::drop(dm);
 
     ecs.create_entity()
-        .with(OtherLevelPosition { x: portal_x, y: portal_y, depth: 1 })
+        .with(OtherLevelPosition { x: portal_x, y: portal_y, map: MapId::TOWN })
         .with(Renderable {
             glyph: rltk::to_cp437('♥'),
             fg: RGB::named(rltk::CYAN),
@@ -178,7 +191,7 @@ pub fn spawn_town_portal(ecs: &mut World) {
             render_order: 0
         })
         .with(EntryTrigger{})
-        .with(TeleportTo{ x: player_x, y: player_y, depth: player_depth, player_only: true })
+        .with(TeleportTo{ x: player_x, y: player_y, map: player_map, player_only: true })
         .with(Name{ name: "Drzwi bez domu".to_string() })
         .with(SingleActivation{})
         .build();
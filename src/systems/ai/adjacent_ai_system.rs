@@ -1,5 +1,5 @@
 use specs::prelude::*;
-use crate::{MyTurn, Faction, Position, Map, raws::Reaction, WantsToMelee, TileSize};
+use crate::{MyTurn, Faction, Position, Map, raws::Reaction, WantsToMelee, TileSize, Maneuvers, WantsToManeuver, Awareness, AwarenessState};
 
 pub struct AdjacentAI {}
 
@@ -13,11 +13,14 @@ impl<'a> System<'a> for AdjacentAI {
         WriteStorage<'a, WantsToMelee>,
         Entities<'a>,
         ReadExpect<'a, Entity>,
-        ReadStorage<'a, TileSize>
+        ReadStorage<'a, TileSize>,
+        ReadStorage<'a, Maneuvers>,
+        WriteStorage<'a, WantsToManeuver>,
+        ReadStorage<'a, Awareness>
     );
 
     fn run(&mut self, data : Self::SystemData) {
-        let (mut turns, factions, positions, map, mut want_melee, entities, player, sizes) = data;
+        let (mut turns, factions, positions, map, mut want_melee, entities, player, sizes, maneuvers, mut want_maneuver, awareness) = data;
 
         let mut turn_done : Vec<Entity> = Vec::new();
         for (entity, _turn, my_faction, pos) in (&entities, &turns, &factions, &positions).join() {
@@ -48,11 +51,24 @@ impl<'a> System<'a> for AdjacentAI {
                     if pos.y < h-1 && pos.x < w-1 { evaluate((idx+w as usize)+1, &map, &factions, &my_faction.name, &mut reactions); }
                 }
 
+                // Only a mob that is sure the player is there goes for them
+                if awareness.get(entity).is_some_and(|a| a.state != AwarenessState::Alert) {
+                    reactions.retain(|reaction| reaction.0 != *player);
+                }
+
                 let mut done = false;
                 for reaction in reactions.iter() {
                     if let Reaction::Attack = reaction.1 {
-                        want_melee.insert(entity, WantsToMelee{ target: reaction.0 }).expect("Error inserting melee");
+                        // A mob that knows a maneuver sometimes tries it instead of a plain attack
+                        let maneuver = maneuvers.get(entity).and_then(|m| m.maneuvers.iter()
+                            .find(|ability| crate::rng::roll_dice(1, 100) <= (ability.chance * 100.0) as i32));
+                        if let Some(ability) = maneuver {
+                            want_maneuver.insert(entity, WantsToManeuver{ maneuver: ability.maneuver, target: reaction.0 }).expect("Error inserting maneuver");
+                        } else {
+                            want_melee.insert(entity, WantsToMelee{ target: reaction.0 }).expect("Error inserting melee");
+                        }
                         done = true;
+                        break;
                     }
                 }
 
@@ -1,5 +1,5 @@
 use specs::prelude::*;
-use crate::{MyTurn, WantsToApproach, Position, Map, ApplyMove};
+use crate::{MyTurn, WantsToApproach, Position, Map, ApplyMove, map::AvoidHazards};
 
 pub struct ApproachAI;
 
@@ -23,11 +23,13 @@ impl<'a> System<'a> for ApproachAI {
             (&entities, &positions, &want_approach, &turns).join() 
         {
             turn_done.push(entity);
-            let path = rltk::a_star_search(
-                map.xy_idx(pos.x, pos.y),
-                map.xy_idx(approach.idx % map.width, approach.idx / map.width),
-                &mut *map
-            );
+            let start = map.xy_idx(pos.x, pos.y);
+            let end = map.xy_idx(approach.idx % map.width, approach.idx / map.width);
+            // Around the hazards if there is a way, straight through them if there is not
+            let mut path = rltk::a_star_search(start, end, &AvoidHazards(&map));
+            if !path.success {
+                path = rltk::a_star_search(start, end, &mut *map);
+            }
             if path.success && path.steps.len()>1 {
                 apply_move.insert(entity, ApplyMove{ dest_idx: path.steps[1] }).expect("Unable to insert");
             }
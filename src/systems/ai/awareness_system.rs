@@ -0,0 +1,131 @@
+use specs::prelude::*;
+use rltk::RandomNumberGenerator;
+use crate::{MyTurn, Awareness, AwarenessState, Viewshed, Position, Map, Skills, Skill, skill_bonus, Attributes, Name,
+            WantsToApproach, Stationary, effects::SUSPICION_TURNS};
+
+pub struct AwarenessSystem {}
+
+impl<'a> System<'a> for AwarenessSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        ReadStorage<'a, MyTurn>,
+        WriteStorage<'a, Awareness>,
+        ReadStorage<'a, Viewshed>,
+        ReadStorage<'a, Position>,
+        ReadExpect<'a, Map>,
+        Entities<'a>,
+        ReadExpect<'a, Entity>,
+        ReadExpect<'a, rltk::Point>,
+        ReadStorage<'a, Skills>,
+        ReadStorage<'a, Attributes>,
+        ReadStorage<'a, Name>,
+        WriteStorage<'a, WantsToApproach>,
+        ReadStorage<'a, Stationary>
+    );
+
+    fn run(&mut self, data : Self::SystemData) {
+        let (turns, mut awareness, viewsheds, positions, map, entities, player, player_pos, skills, attributes,
+            names, mut want_approach, stationary) = data;
+
+        let player_idx = map.xy_idx(player_pos.x, player_pos.y);
+        for (entity, _turn, awareness, viewshed, pos) in (&entities, &turns, &mut awareness, &viewsheds, &positions).join() {
+            if entity == *player || awareness.state == AwarenessState::Alert || awareness.state == AwarenessState::Asleep { continue; }
+
+            let noticed = viewshed.visible_tiles.contains(&player_pos) && {
+                let distance = rltk::DistanceAlg::Pythagoras.distance2d(*player_pos, rltk::Point::new(pos.x, pos.y));
+                crate::rng::with_rng(|rng| notices(rng, entity, *player, distance, &skills, &attributes))
+            };
+            if noticed {
+                awareness.state = if awareness.state == AwarenessState::Unaware { AwarenessState::Suspicious } else { AwarenessState::Alert };
+                awareness.investigate = Some(player_idx);
+                awareness.suspicion = SUSPICION_TURNS;
+                let seen = map.visible_tiles[map.xy_idx(pos.x, pos.y)];
+                if let (true, Some(name)) = (seen, names.get(entity)) {
+                    let logger = crate::gamelog::Logger::new().npc_name(&name.name);
+                    if awareness.state == AwarenessState::Alert {
+                        logger.append("zauwaza Wieclawa!").log();
+                    } else {
+                        logger.append("rozglada sie podejrzliwie.").log();
+                    }
+                }
+            }
+
+            if awareness.state != AwarenessState::Suspicious { continue; }
+
+            // A suspicious mob goes to have a look, and in time decides it was nothing
+            awareness.suspicion -= 1;
+            if awareness.suspicion < 1 {
+                awareness.state = AwarenessState::Unaware;
+                awareness.investigate = None;
+                continue;
+            }
+            if let Some(idx) = awareness.investigate {
+                if idx == map.xy_idx(pos.x, pos.y) {
+                    awareness.investigate = None;
+                } else if stationary.get(entity).is_none() {
+                    want_approach.insert(entity, WantsToApproach{ idx: idx as i32 }).expect("Unable to insert");
+                }
+            }
+        }
+    }
+}
+
+// The observer's d20 and Search against the player's Stealth, which gets easier the further away they are
+fn notices(rng : &mut RandomNumberGenerator, observer : Entity, player : Entity, distance : f32, skills : &ReadStorage<Skills>,
+    attributes : &ReadStorage<Attributes>) -> bool {
+    let perception = rng.roll_dice(1, 20)
+        + skills.get(observer).map_or(0, |s| skill_bonus(Skill::Search, s))
+        + attributes.get(observer).map_or(0, |a| a.intelligence.bonus);
+    let stealth = 10
+        + skills.get(player).map_or(0, |s| skill_bonus(Skill::Stealth, s))
+        + attributes.get(player).map_or(0, |a| a.quickness.bonus)
+        + distance as i32 / 2;
+    perception > stealth
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use crate::Attribute;
+
+    fn creature(ecs : &mut World, search : i32, stealth : i32) -> Entity {
+        let attribute = Attribute{ base: 11, modifiers: 0, bonus: 0 };
+        let attributes = Attributes{ might: attribute.clone(), fitness: attribute.clone(), quickness: attribute.clone(), intelligence: attribute };
+        let skills = Skills{ skills: [(Skill::Search, search), (Skill::Stealth, stealth)].into_iter().collect::<HashMap<Skill, i32>>() };
+        ecs.create_entity().with(attributes).with(skills).build()
+    }
+
+    // How many of 200 seeded looks spot the player
+    fn times_noticed(search : i32, stealth : i32, distance : f32) -> usize {
+        let mut ecs = World::new();
+        ecs.register::<Skills>();
+        ecs.register::<Attributes>();
+        let observer = creature(&mut ecs, search, 0);
+        let player = creature(&mut ecs, 0, stealth);
+        let mut rng = RandomNumberGenerator::seeded(5);
+        (0..200).filter(|_| notices(&mut rng, observer, player, distance, &ecs.read_storage(), &ecs.read_storage())).count()
+    }
+
+    #[test]
+    fn the_d20_decides_an_even_match() {
+        // Nothing on either side leaves the d20 against 10
+        let mut rng = RandomNumberGenerator::seeded(5);
+        let expected = (0..200).filter(|_| rng.roll_dice(1, 20) > 10).count();
+        assert_eq!(times_noticed(0, 0, 1.0), expected);
+    }
+
+    #[test]
+    fn search_and_stealth_tip_the_roll() {
+        assert_eq!(times_noticed(20, 0, 1.0), 200);
+        assert_eq!(times_noticed(0, 20, 1.0), 0);
+        assert!(times_noticed(4, 0, 1.0) > times_noticed(0, 0, 1.0));
+        assert!(times_noticed(0, 4, 1.0) < times_noticed(0, 0, 1.0));
+    }
+
+    #[test]
+    fn distance_makes_the_player_harder_to_spot() {
+        assert!(times_noticed(0, 0, 12.0) < times_noticed(0, 0, 1.0));
+        assert_eq!(times_noticed(0, 0, 40.0), 0);
+    }
+}
@@ -1,5 +1,5 @@
 use specs::prelude::*;
-use crate::{MyTurn, Chasing, Position, Map, ApplyMove, TileSize};
+use crate::{MyTurn, Chasing, Position, Map, ApplyMove, TileSize, map::AvoidHazards};
 use std::collections::HashMap;
 
 pub struct ChaseAI {}
@@ -42,22 +42,22 @@ impl<'a> System<'a> for ChaseAI {
         {
             turn_done.push(entity);
             let target_pos = targets[&entity];
-            let path;
+            let start = map.xy_idx(pos.x, pos.y) as i32;
+            let end = map.xy_idx(target_pos.0, target_pos.1) as i32;
+            let mut path;
 
             if let Some(size) = sizes.get(entity) {
                 let mut map_copy = map.clone();
                 map_copy.populate_blocked_multi(size.x, size.y);
-                path = rltk::a_star_search(
-                    map_copy.xy_idx(pos.x, pos.y) as i32,
-                    map_copy.xy_idx(target_pos.0, target_pos.1) as i32,
-                    &mut map_copy
-                );
+                path = rltk::a_star_search(start, end, &AvoidHazards(&map_copy));
+                if !path.success {
+                    path = rltk::a_star_search(start, end, &mut map_copy);
+                }
             } else {
-                path = rltk::a_star_search(
-                    map.xy_idx(pos.x, pos.y) as i32,
-                    map.xy_idx(target_pos.0, target_pos.1) as i32,
-                    &mut *map
-                );
+                path = rltk::a_star_search(start, end, &AvoidHazards(&map));
+                if !path.success {
+                    path = rltk::a_star_search(start, end, &mut *map);
+                }
             }
             if path.success && path.steps.len()>1 && path.steps.len()<15 {
                 apply_move.insert(entity, ApplyMove{ dest_idx: path.steps[1] }).expect("Unable to insert");
@@ -1,5 +1,5 @@
 use specs::prelude::*;
-use crate::{MyTurn, MoveMode, Movement, Position, Map, map::tile_walkable, ApplyMove};
+use crate::{MyTurn, MoveMode, Movement, Position, Map, map::{tile_walkable, tile_hazard, AvoidHazards}, ApplyMove};
 
 pub struct DefaultMoveAI {}
 
@@ -9,13 +9,13 @@ impl<'a> System<'a> for DefaultMoveAI {
         WriteStorage<'a, MyTurn>,
         WriteStorage<'a, MoveMode>,
         ReadStorage<'a, Position>,
-        WriteExpect<'a, Map>,
+        ReadExpect<'a, Map>,
         WriteStorage<'a, ApplyMove>,
         Entities<'a>
     );
 
     fn run(&mut self, data : Self::SystemData) {
-        let (mut turns, mut move_mode, positions, mut map,
+        let (mut turns, mut move_mode, positions, map,
             mut apply_move, entities) = data;
 
         let mut turn_done : Vec<Entity> = Vec::new();
@@ -40,7 +40,7 @@ impl<'a> System<'a> for DefaultMoveAI {
 
                     if x > 0 && x < map.width-1 && y > 0 && y < map.height-1 {
                         let dest_idx = map.xy_idx(x, y);
-                        if !crate::spatial::is_blocked(dest_idx) {
+                        if !crate::spatial::is_blocked(dest_idx) && !tile_hazard(map.tiles[dest_idx]) {
                             apply_move.insert(entity, ApplyMove { dest_idx })
                                 .expect("Unable to insert");
                             turn_done.push(entity);
@@ -68,7 +68,7 @@ impl<'a> System<'a> for DefaultMoveAI {
                             let path = rltk::a_star_search(
                                 map.xy_idx(pos.x, pos.y) as i32,
                                 map.xy_idx(target_x, target_y) as i32,
-                                &mut *map
+                                &AvoidHazards(&map)
                             );
                             if path.success && path.steps.len()>1 {
                                 mode.mode = Movement::RandomWaypoint{
@@ -1,5 +1,6 @@
 use specs::prelude::*;
-use crate::{Initiative, Position, MyTurn, Attributes, RunState, Pools, Duration, EquipmentChanged, StatusEffect, DamageOverTime};
+use crate::{Initiative, Position, MyTurn, Attributes, RunState, Pools, Duration, EquipmentChanged, StatusEffect, DamageOverTime, Reloading, Prone,
+    Awareness, AwarenessState};
 
 pub struct InitiativeSystem {}
 
@@ -17,16 +18,23 @@ impl<'a> System<'a> for InitiativeSystem {
                         WriteStorage<'a, Duration>,
                         WriteStorage<'a, EquipmentChanged>,
                         ReadStorage<'a, StatusEffect>,
-                        ReadStorage<'a, DamageOverTime>);
+                        ReadStorage<'a, DamageOverTime>,
+                        WriteStorage<'a, Reloading>,
+                        ReadStorage<'a, Prone>,
+                        ReadStorage<'a, Awareness>);
 
     fn run(&mut self, data : Self::SystemData) {
         let (mut initiatives, positions, mut turns, entities, attributes,
-            mut runstate, player, player_pos, pools, mut durations, mut dirty, statuses, dots) = data;
+            mut runstate, player, player_pos, pools, mut durations, mut dirty, statuses, dots, mut reloading, prone, awareness) = data;
 
         if *runstate != RunState::Ticking { return; }
 
         turns.clear();
 
+        let mut knocked_down : Vec<(Entity, Entity)> = (&entities, &prone, &statuses).join()
+            .map(|(status_entity, _, status)| (status_entity, status.target))
+            .collect();
+
         for (entity, initiative, pos) in (&entities, &mut initiatives, &positions).join() {
             initiative.current -= 1;
             if initiative.current < 1 {
@@ -42,6 +50,33 @@ impl<'a> System<'a> for InitiativeSystem {
                     initiative.current += f32::floor(pools.total_initiative_penalty) as i32;
                 }
 
+                // Reloading uses up the whole turn, the player included
+                if let Some(reload) = reloading.get_mut(entity) {
+                    reload.turns -= 1;
+                    if reload.turns < 1 {
+                        reloading.remove(entity);
+                        if entity == *player {
+                            crate::gamelog::Logger::new().append("Wieclaw konczy przeladowywac.").log();
+                        }
+                    }
+                    continue;
+                }
+
+                // Getting back up takes the whole turn
+                if let Some(i) = knocked_down.iter().position(|(_, target)| *target == entity) {
+                    let (status_entity, _) = knocked_down.remove(i);
+                    entities.delete(status_entity).expect("Unable to delete");
+                    if entity == *player {
+                        crate::gamelog::Logger::new().append("Wieclaw podnosi sie z ziemi.").log();
+                    }
+                    continue;
+                }
+
+                // Sleepers sleep through their turns until something wakes them
+                if awareness.get(entity).is_some_and(|a| a.state == AwarenessState::Asleep) {
+                    continue;
+                }
+
                 if entity == *player {
                     *runstate = RunState::AwaitingInput;
                 } else {
@@ -65,7 +100,7 @@ impl<'a> System<'a> for InitiativeSystem {
                     if let Some(dot) = dots.get(effect_entity) {
                         add_effect(
                             None,
-                            EffectType::Damage{ amount : dot.damage },
+                            EffectType::Damage{ amount : dot.damage, damage_type : dot.damage_type },
                             Targets::Single{ target : status.target }
                         );
                     }
@@ -1,6 +1,7 @@
 mod initiative_system;
 mod turn_status;
 mod quipping;
+mod awareness_system;
 mod adjacent_ai_system;
 mod visible_ai_system;
 mod approach_ai_system;
@@ -11,6 +12,7 @@ mod encumbrance_system;
 pub use initiative_system::InitiativeSystem;
 pub use turn_status::TurnStatusSystem;
 pub use quipping::QuipSystem;
+pub use awareness_system::AwarenessSystem;
 pub use adjacent_ai_system::AdjacentAI;
 pub use visible_ai_system::VisibleAI;
 pub use approach_ai_system::ApproachAI;
@@ -1,6 +1,7 @@
 use specs::prelude::*;
 use crate::{MyTurn, Faction, Position, Map, raws::Reaction, Viewshed, WantsToFlee, WantsToApproach, Chasing,
-            SpecialAbilities, WantsToCastSpell, Name, SpellTemplate, Stationary, Weapon, WantsToShoot, Equipped};
+            SpecialAbilities, WantsToCastSpell, Name, SpellTemplate, Stationary, Weapon, WantsToShoot, Equipped,
+            Magazine, Ammunition, InBackpack, Reloading, Awareness, AwarenessState, combat};
 
 pub struct VisibleAI {}
 
@@ -24,12 +25,18 @@ impl<'a> System<'a> for VisibleAI {
         ReadStorage<'a, Stationary>,
         ReadStorage<'a, Weapon>,
         ReadStorage<'a, Equipped>,
-        WriteStorage<'a, WantsToShoot>
+        WriteStorage<'a, WantsToShoot>,
+        WriteStorage<'a, Magazine>,
+        WriteStorage<'a, Ammunition>,
+        ReadStorage<'a, InBackpack>,
+        WriteStorage<'a, Reloading>,
+        ReadStorage<'a, Awareness>
     );
 
     fn run(&mut self, data : Self::SystemData) {
         let (turns, factions, positions, map, mut want_approach, mut want_flee, entities, player, viewsheds, mut chasing,
-            abilities, mut casting, names, spells, stationary, weapons, equipped, mut wants_shoot) = data;
+            abilities, mut casting, names, spells, stationary, weapons, equipped, mut wants_shoot,
+            mut magazines, mut ammunition, backpack, mut reloading, awareness) = data;
 
         for (entity, _turn, my_faction, pos, viewshed) in (&entities, &turns, &factions, &positions, &viewsheds).join() {
             if entity != *player {
@@ -43,6 +50,11 @@ impl<'a> System<'a> for VisibleAI {
                     }
                 }
 
+                // Seeing the player is not the same as noticing them, that is up to the awareness system
+                if awareness.get(entity).is_some_and(|a| a.state != AwarenessState::Alert) {
+                    reactions.retain(|reaction| reaction.2 != *player);
+                }
+
                 let mut done = false;
                 for reaction in reactions.iter() {
                     match reaction.1 {
@@ -68,14 +80,25 @@ impl<'a> System<'a> for VisibleAI {
                                 }
                             }
                             if !done {
-                                for (weapon, equip) in (&weapons, &equipped).join() {
+                                for (weapon_entity, weapon, equip) in (&entities, &weapons, &equipped).join() {
                                     if let Some(wrange) = weapon.range {
                                         if equip.owner == entity {
                                             //rltk::console::log(format!("Owner found. Ranges: {}/{}", wrange, range));
                                             if wrange >= range as i32 {
-                                                //rltk::console::log("Inserting shoot");
-                                                wants_shoot.insert(entity, WantsToShoot{ target: reaction.2 }).expect("Insert fail");
-                                                done = true;
+                                                match magazines.get_mut(weapon_entity) {
+                                                    // Out of shots: reload if there is anything to load, otherwise close in
+                                                    Some(magazine) if magazine.loaded < 1 => {
+                                                        if combat::has_ammo_for(entity, magazine, &backpack, &ammunition) {
+                                                            combat::reload(entity, magazine, &entities, &backpack, &mut ammunition, &mut reloading);
+                                                            done = true;
+                                                        }
+                                                    }
+                                                    _ => {
+                                                        //rltk::console::log("Inserting shoot");
+                                                        wants_shoot.insert(entity, WantsToShoot{ target: reaction.2 }).expect("Insert fail");
+                                                        done = true;
+                                                    }
+                                                }
                                             }
                                         }
                                     }
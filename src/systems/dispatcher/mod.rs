@@ -26,6 +26,7 @@ construct_dispatcher!(
     (InitiativeSystem, "initiative", &[]),
     (TurnStatusSystem, "turnstatus", &[]),
     (QuipSystem, "quips", &[]),
+    (AwarenessSystem, "awareness", &[]),
     (AdjacentAI, "adjacent", &[]),
     (VisibleAI, "visible", &[]),
     (ApproachAI, "approach", &[]),
@@ -33,8 +34,10 @@ construct_dispatcher!(
     (ChaseAI, "chase", &[]),
     (DefaultMoveAI, "default_move", &[]),
     (MovementSystem, "movement", &[]),
+    (HazardSystem, "hazards", &[]),
     (TriggerSystem, "triggers", &[]),
     (MeleeCombatSystem, "melee", &[]),
+    (ManeuverSystem, "maneuvers", &[]),
     (RangedCombatSystem, "ranged", &[]),
     (ItemCollectionSystem, "pickup", &[]),
     (ItemEquipOnUse, "equip", &[]),
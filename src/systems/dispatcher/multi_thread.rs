@@ -17,6 +17,7 @@ macro_rules! construct_dispatcher {
             let dispatcher = DispatcherBuilder::new()
                 $(
                     .with($type{}, $name, $deps)
+                    .with_barrier()
                 )*
                 .build();
 
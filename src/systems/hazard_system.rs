@@ -0,0 +1,88 @@
+use specs::prelude::*;
+use std::collections::BTreeMap;
+use crate::{Map, TileType, Position, EntityMoved, Pools, RunState, DamageType, map::tile_walkable, effects::*};
+
+const LAVA_DICE : (i32, i32) = (2, 6);
+
+pub struct HazardSystem {}
+
+impl<'a> System<'a> for HazardSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = ( WriteExpect<'a, Map>,
+                        ReadExpect<'a, RunState>,
+                        ReadExpect<'a, Entity>,
+                        Entities<'a>,
+                        ReadStorage<'a, Position>,
+                        ReadStorage<'a, EntityMoved>,
+                        ReadStorage<'a, Pools>);
+
+    fn run(&mut self, data : Self::SystemData) {
+        let (mut map, runstate, player_entity, entities, positions, moved, pools) = data;
+
+        // Stepping onto a hazard sets it off straight away
+        for (entity, pos, moved, _pools) in (&entities, &positions, &moved, &pools).join() {
+            let idx = map.xy_idx(pos.x, pos.y);
+            match map.tiles[idx] {
+                TileType::Chasm if entity != *player_entity || crate::map_builders::has_deeper_level(map.id().below()) => {
+                    add_effect(None, EffectType::Damage{ amount: crate::rng::roll_dice(1, 6), damage_type: DamageType::Physical }, Targets::Single{ target: entity });
+                    add_effect(None, EffectType::Fall, Targets::Single{ target: entity });
+                }
+                TileType::Ice => {
+                    let dx = pos.x - moved.from as i32 % map.width;
+                    let dy = pos.y - moved.from as i32 / map.width;
+                    if entity == *player_entity && map.tiles[moved.from] != TileType::Ice {
+                        crate::gamelog::Logger::new().append("Wieclaw slizga sie po lodzie.").log();
+                    }
+                    add_effect(None, EffectType::Slide{ dx: dx.signum(), dy: dy.signum() }, Targets::Single{ target: entity });
+                }
+                _ => burn_and_choke(&map, entity, idx, entity == *player_entity)
+            }
+        }
+
+        // Standing in lava or gas keeps hurting, once for every turn of the player's
+        if *runstate != RunState::AwaitingInput { return; }
+
+        for (entity, pos, _pools, ()) in (&entities, &positions, &pools, !&moved).join() {
+            let idx = map.xy_idx(pos.x, pos.y);
+            burn_and_choke(&map, entity, idx, entity == *player_entity);
+        }
+        spread_gas(&mut map);
+    }
+}
+
+fn burn_and_choke(map : &Map, entity : Entity, idx : usize, is_player : bool) {
+    if map.tiles[idx] == TileType::Lava {
+        add_effect(None, EffectType::Damage{ amount: crate::rng::roll_dice(LAVA_DICE.0, LAVA_DICE.1), damage_type: DamageType::Fire }, Targets::Single{ target: entity });
+        if is_player {
+            crate::gamelog::Logger::new().color(rltk::ORANGE).append("Wieclaw sie parzy!").log();
+        }
+    }
+    if let Some(strength) = map.gas.get(&idx) {
+        add_effect(None, EffectType::Damage{ amount: i32::max(1, strength / 3), damage_type: DamageType::Poison }, Targets::Single{ target: entity });
+        if is_player {
+            crate::gamelog::Logger::new().color(rltk::GREEN).append("Wieclaw krztusi sie gazem.").log();
+        }
+    }
+}
+
+// Every cloud thins out, and the thick ones drift into the open tiles around them
+fn spread_gas(map : &mut Map) {
+    let mut gas : BTreeMap<usize, i32> = BTreeMap::new();
+    for (idx, strength) in map.gas.iter() {
+        if *strength > 1 {
+            let kept = gas.entry(*idx).or_insert(0);
+            *kept = i32::max(*kept, strength - 1);
+        }
+        if *strength < 3 { continue; }
+        let x = *idx as i32 % map.width;
+        let y = *idx as i32 / map.width;
+        for (nx, ny) in [(x-1, y), (x+1, y), (x, y-1), (x, y+1)] {
+            if nx < 1 || nx > map.width-2 || ny < 1 || ny > map.height-2 { continue; }
+            let next = map.xy_idx(nx, ny);
+            if !tile_walkable(map.tiles[next]) || map.gas.get(&next).copied().unwrap_or(0) >= strength - 2 { continue; }
+            let drifted = gas.entry(next).or_insert(0);
+            *drifted = i32::max(*drifted, strength - 2);
+        }
+    }
+    map.gas = gas;
+}
@@ -1,5 +1,5 @@
 use specs::prelude::*;
-use crate::{HungerClock, RunState, HungerState, MyTurn, effects::{add_effect, EffectType, Targets}};
+use crate::{HungerClock, RunState, HungerState, MyTurn, DamageType, effects::{add_effect, EffectType, Targets}};
 
 pub struct HungerSystem {}
 
@@ -59,7 +59,7 @@ impl<'a> System<'a> for HungerSystem {
                         }
                         add_effect(
                             None,
-                            EffectType::Damage{ amount: 1 },
+                            EffectType::Damage{ amount: 1, damage_type: DamageType::Physical },
                             Targets::Single{ target: entity }
                         );
                     }
@@ -1,5 +1,5 @@
 use specs::prelude::*;
-use super::{WantsToPickupItem, Name, InBackpack, Position, EquipmentChanged, ObfuscatedName, MagicItem, MasterDungeonMap};
+use super::{WantsToPickupItem, Name, InBackpack, Position, EquipmentChanged, ObfuscatedName, MagicItem, MasterDungeonMap, Ammunition};
 
 
 pub struct ItemCollectionSystem {}
@@ -14,16 +14,20 @@ impl<'a> System<'a> for ItemCollectionSystem {
                         WriteStorage<'a, EquipmentChanged>,
                         ReadStorage<'a, MagicItem>,
                         ReadStorage<'a, ObfuscatedName>,
-                        ReadExpect<'a, MasterDungeonMap>
+                        ReadExpect<'a, MasterDungeonMap>,
+                        Entities<'a>,
+                        WriteStorage<'a, Ammunition>
                     );
 
     fn run(&mut self, data : Self::SystemData) {
         let (player_entity, mut wants_pickup, mut positions, names, mut backpack, mut dirty,
-            magic_items, obfuscated_names, dm) = data;
+            magic_items, obfuscated_names, dm, entities, mut ammunition) = data;
 
         for pickup in wants_pickup.join() {
             positions.remove(pickup.item);
-            backpack.insert(pickup.item, InBackpack{ owner: pickup.collected_by }).expect("Unable to insert backpack entry");
+            if !stack_ammunition(pickup.item, pickup.collected_by, &entities, &backpack, &names, &mut ammunition) {
+                backpack.insert(pickup.item, InBackpack{ owner: pickup.collected_by }).expect("Unable to insert backpack entry");
+            }
             dirty.insert(pickup.collected_by, EquipmentChanged{}).expect("Unable to insert ");
 
             if pickup.collected_by == *player_entity {
@@ -40,3 +44,23 @@ impl<'a> System<'a> for ItemCollectionSystem {
     }
 }
 
+// Ammunition joins a stack of the same name already in the pack instead of taking up a slot of its own
+fn stack_ammunition(item : Entity, owner : Entity, entities : &Entities, backpack : &WriteStorage<InBackpack>,
+    names : &ReadStorage<Name>, ammunition : &mut WriteStorage<Ammunition>) -> bool
+{
+    let (count, name) = match (ammunition.get(item), names.get(item)) {
+        (Some(stack), Some(name)) => (stack.count, &name.name),
+        _ => return false
+    };
+    let existing = (entities, backpack, names).join()
+        .find(|(entity, carried, other)| *entity != item && carried.owner == owner && other.name == *name && ammunition.contains(*entity))
+        .map(|(entity, _, _)| entity);
+    match existing.and_then(|entity| ammunition.get_mut(entity)) {
+        None => false,
+        Some(stack) => {
+            stack.count += count;
+            entities.delete(item).expect("Unable to delete");
+            true
+        }
+    }
+}
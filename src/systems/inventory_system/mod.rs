@@ -1,7 +1,7 @@
 use specs::prelude::*;
 use crate::{WantsToPickupItem, Name, InBackpack, Position, WantsToUseItem, EquipmentChanged, ObfuscatedName, MagicItem,
             WantsToDropItem, Map, AreaOfEffect, IdentifiedItem, Item, MasterDungeonMap, CursedItem, WantsToCastSpell,
-            Equippable, Equipped, WantsToRemoveItem};
+            Equippable, Equipped, WantsToRemoveItem, Ammunition};
 
 mod collection_system;
 pub use collection_system::ItemCollectionSystem;
@@ -0,0 +1,129 @@
+use specs::prelude::*;
+use crate::{Attributes, Skills, WantsToManeuver, Maneuver, Name, Weapon, HungerClock, Pools, Equipped, EquipmentSlot, Wearable,
+            NaturalAttackDefense, Position, TileSize, DamageType, Map, map::tile_walkable, effects::*, Awareness, AwarenessState,
+            combat::{self, AttackKind, Attacker, Defender}};
+
+// What the log says when the maneuver works and when it does not
+fn maneuver_verbs(maneuver : Maneuver) -> (&'static str, &'static str) {
+    match maneuver {
+        Maneuver::Shove => ("pcha:", "probuje pchnac:"),
+        Maneuver::Disarm => ("wytraca bron:", "probuje rozbroic:"),
+        Maneuver::Trip => ("podcina:", "probuje podciac:")
+    }
+}
+
+pub struct ManeuverSystem {}
+
+impl<'a> System<'a> for ManeuverSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = ( Entities<'a>,
+                        WriteStorage<'a, WantsToManeuver>,
+                        ReadStorage<'a, Name>,
+                        ReadStorage<'a, Attributes>,
+                        ReadStorage<'a, Skills>,
+                        ReadStorage<'a, HungerClock>,
+                        ReadStorage<'a, Pools>,
+                        ReadStorage<'a, Equipped>,
+                        ReadStorage<'a, Weapon>,
+                        ReadStorage<'a, Wearable>,
+                        ReadStorage<'a, NaturalAttackDefense>,
+                        ReadStorage<'a, Position>,
+                        ReadStorage<'a, TileSize>,
+                        ReadExpect<'a, Map>,
+                        ReadStorage<'a, Awareness>
+                    );
+
+    fn run(&mut self, data : Self::SystemData) {
+        let (entities, mut wants_maneuver, names, attributes, skills, hunger_clock, pools, equipped_items, weapons,
+            wearables, natural, positions, sizes, map, awareness) = data;
+
+        for (entity, wants, name, attacker_attributes, attacker_skills, attacker_pools) in (&entities, &wants_maneuver, &names, &attributes, &skills, &pools).join() {
+            let target = wants.target;
+            let (target_pools, target_attributes, target_skills, target_name) =
+                match (pools.get(target), attributes.get(target), skills.get(target), names.get(target)) {
+                    (Some(p), Some(a), Some(s), Some(n)) => (p, a, s, n),
+                    _ => continue
+                };
+            if attacker_pools.hit_points.current < 1 || target_pools.hit_points.current < 1 { continue; }
+
+            let attacker = Attacker{
+                attributes: attacker_attributes,
+                skills: attacker_skills,
+                well_fed: combat::is_well_fed(entity, &hunger_clock)
+            };
+            let defender = Defender{
+                attributes: target_attributes,
+                skills: target_skills,
+                natural_armor: natural.get(target).and_then(|nat| nat.armor_class),
+                item_armor: combat::worn_armor(target, &equipped_items, &wearables),
+                unaware: awareness.get(target).is_some_and(|a| a.state == AwarenessState::Asleep || a.state == AwarenessState::Unaware)
+            };
+            let outcome = crate::rng::with_rng(|rng| combat::resolve_maneuver(rng, &attacker, &defender, wants.maneuver));
+            let (succeeds, fails) = maneuver_verbs(wants.maneuver);
+            if let Some(pos) = positions.get(entity) {
+                add_effect(Some(entity), EffectType::Noise{ volume: combat::attack_noise(AttackKind::Melee) }, Targets::Tile{ tile_idx: map.xy_idx(pos.x, pos.y) as i32 });
+            }
+
+            // Big creatures do not budge however the roll goes
+            if !outcome.success || (wants.maneuver == Maneuver::Shove && sizes.get(target).is_some()) {
+                crate::gamelog::Logger::new()
+                    .npc_name(&name.name)
+                    .append(fails)
+                    .npc_name(format!("{},", target_name.name))
+                    .append("ale bez skutku.")
+                    .log();
+                continue;
+            }
+
+            match wants.maneuver {
+                Maneuver::Shove => {
+                    let (from, to) = match (positions.get(entity), positions.get(target)) {
+                        (Some(from), Some(to)) => (from, to),
+                        _ => continue
+                    };
+                    let (dx, dy) = ((to.x - from.x).signum(), (to.y - from.y).signum());
+                    let (x, y) = (to.x + dx, to.y + dy);
+                    let free = x > 0 && x < map.width-1 && y > 0 && y < map.height-1 && {
+                        let dest = map.xy_idx(x, y);
+                        tile_walkable(map.tiles[dest]) && !crate::spatial::is_blocked(dest)
+                    };
+                    let logger = crate::gamelog::Logger::new().npc_name(&name.name).append(succeeds).npc_name(&target_name.name);
+                    if free {
+                        logger.append("i odrzuca go do tylu.").log();
+                        add_effect(Some(entity), EffectType::Slide{ dx, dy }, Targets::Single{ target });
+                    } else {
+                        // Nowhere to go, so the shove slams the target into whatever is behind it
+                        let damage = crate::rng::roll_dice(1, 4);
+                        logger.append("na przeszkode, za").damage(damage).append("HP.").log();
+                        add_effect(Some(entity), EffectType::Damage{ amount: damage, damage_type: DamageType::Physical }, Targets::Single{ target });
+                    }
+                }
+                Maneuver::Disarm => {
+                    let weapon = (&entities, &equipped_items, &weapons).join()
+                        .find(|(_, wielded, _)| wielded.owner == target && wielded.slot == EquipmentSlot::Melee)
+                        .map(|(item, _, _)| item);
+                    match weapon {
+                        Some(item) => {
+                            crate::gamelog::Logger::new().npc_name(&name.name).append(succeeds).npc_name(format!("{}.", target_name.name)).log();
+                            add_effect(Some(entity), EffectType::DropWeapon{ item }, Targets::Single{ target });
+                        }
+                        None => {
+                            crate::gamelog::Logger::new()
+                                .npc_name(&name.name)
+                                .append(fails)
+                                .npc_name(format!("{},", target_name.name))
+                                .append("ale nie ma czego wytracic.")
+                                .log();
+                        }
+                    }
+                }
+                Maneuver::Trip => {
+                    crate::gamelog::Logger::new().npc_name(&name.name).append(succeeds).npc_name(format!("{}.", target_name.name)).log();
+                    add_effect(Some(entity), EffectType::KnockDown, Targets::Single{ target });
+                }
+            }
+        }
+
+        wants_maneuver.clear();
+    }
+}
@@ -1,6 +1,6 @@
 use specs::prelude::*;
-use crate::{Attributes, Skills, WantsToMelee, Name, Weapon, WeaponAttribute, EquipmentSlot,
-            HungerClock, HungerState, Pools, skill_bonus, Skill, Equipped, Wearable, NaturalAttackDefense, effects::*};
+use crate::{Attributes, Skills, WantsToMelee, Name, Weapon, HungerClock, Pools, Equipped, Wearable, NaturalAttackDefense,
+            Position, Map, Awareness, AwarenessState, combat::{self, AttackKind, Attacker, Defender}};
 
 pub struct MeleeCombatSystem {}
 
@@ -17,11 +17,14 @@ impl<'a> System<'a> for MeleeCombatSystem {
                         ReadStorage<'a, Weapon>,
                         ReadStorage<'a, Wearable>,
                         ReadStorage<'a, NaturalAttackDefense>,
+                        ReadStorage<'a, Position>,
+                        ReadExpect<'a, Map>,
+                        ReadStorage<'a, Awareness>
                     );
 
     fn run(&mut self, data : Self::SystemData) {
         let (entities, mut wants_melee, names, attributes, skills, hunger_clock, 
-            pools, equipped_items, meleeweapons, wearables, natural) = data;
+            pools, equipped_items, meleeweapons, wearables, natural, positions, map, awareness) = data;
 
         for (entity, wants_melee, name, attacker_attributes, attacker_skills, attacker_pools) in (&entities, &wants_melee, &names, &attributes, &skills, &pools).join() {
             let target_pools = pools.get(wants_melee.target).unwrap();
@@ -30,138 +33,27 @@ impl<'a> System<'a> for MeleeCombatSystem {
             if attacker_pools.hit_points.current > 0 && target_pools.hit_points.current > 0 {
                 let target_name = names.get(wants_melee.target).unwrap();
 
-                let mut weapon_info = Weapon{
-                    range: None,
-                    attribute: WeaponAttribute::Might,
-                    hit_bonus: 0,
-                    damage_n_dice: 1,
-                    damage_die_type: 4,
-                    damage_bonus: 0,
-                    proc_chance: None,
-                    proc_target: None
+                let attacker = Attacker{
+                    attributes: attacker_attributes,
+                    skills: attacker_skills,
+                    well_fed: combat::is_well_fed(entity, &hunger_clock)
                 };
-
-                if let Some(nat) = natural.get(entity) {
-                    if !nat.attacks.is_empty() {
-                        let attack_index = if nat.attacks.len()==1 { 0 } else { crate::rng::roll_dice(1, nat.attacks.len() as i32) as usize -1 };
-                        weapon_info.hit_bonus = nat.attacks[attack_index].hit_bonus;
-                        weapon_info.damage_n_dice = nat.attacks[attack_index].damage_n_dice;
-                        weapon_info.damage_die_type = nat.attacks[attack_index].damage_die_type;
-                        weapon_info.damage_bonus = nat.attacks[attack_index].damage_bonus;
-                    }
-                }
-
-                let mut weapon_entity : Option<Entity> = None;
-                for (weaponentity,wielded,melee) in (&entities, &equipped_items, &meleeweapons).join() {
-                    if wielded.owner == entity && wielded.slot == EquipmentSlot::Melee {
-                        weapon_info = melee.clone();
-                        weapon_entity = Some(weaponentity);
-                    }
-                }
-
-                for (wielded, melee) in (&equipped_items, &meleeweapons).join() {
-                    if wielded.owner == entity && wielded.slot == EquipmentSlot::Melee {
-                        weapon_info = melee.clone();
-                    }
-                }
-
-                let natural_roll = crate::rng::roll_dice(1, 20);
-                let attribute_hit_bonus = if weapon_info.attribute == WeaponAttribute::Might 
-                    { attacker_attributes.might.bonus }
-                    else { attacker_attributes.quickness.bonus };
-                let skill_hit_bonus = skill_bonus(Skill::Melee, &*attacker_skills);
-                let weapon_hit_bonus = weapon_info.hit_bonus;
-                let mut status_hit_bonus = 0;
-                if let Some(hc) = hunger_clock.get(entity) {
-                    if hc.state == HungerState::WellFed {
-                        status_hit_bonus += 1;
-                    }
-                }
-                let modified_hit_roll = natural_roll + attribute_hit_bonus + skill_hit_bonus
-                    + weapon_hit_bonus + status_hit_bonus;
-
-                let mut armor_item_bonus_f = 0.0;
-                for (wielded,armor) in (&equipped_items, &wearables).join() {
-                    if wielded.owner == wants_melee.target {
-                        armor_item_bonus_f += armor.armor_class;
-                    }
-                }
-
-                let base_armor_class = match natural.get(wants_melee.target) {
-                    None => 10,
-                    Some(nat) => nat.armor_class.unwrap_or(10)
+                let defender = Defender{
+                    attributes: target_attributes,
+                    skills: target_skills,
+                    natural_armor: natural.get(wants_melee.target).and_then(|nat| nat.armor_class),
+                    item_armor: combat::worn_armor(wants_melee.target, &equipped_items, &wearables),
+                    unaware: awareness.get(wants_melee.target).is_some_and(|a| a.state == AwarenessState::Asleep || a.state == AwarenessState::Unaware)
                 };
-                let armor_quickness_bonus = target_attributes.quickness.bonus;
-                let armor_skill_bonus = skill_bonus(Skill::Defense, &*target_skills);
-                let armor_item_bonus = armor_item_bonus_f as i32;
-                let armor_class = base_armor_class + armor_quickness_bonus + armor_skill_bonus + armor_item_bonus;
-
-                if natural_roll != 1 && (natural_roll == 20 || modified_hit_roll > armor_class) {
-                    let base_damage = crate::rng::roll_dice(weapon_info.damage_n_dice, weapon_info.damage_die_type);
-                    let attr_damage_bonus = attacker_attributes.might.bonus;
-                    let skill_damage_bonus = skill_bonus(Skill::Melee, &*attacker_skills);
-                    let weapon_damage_bonus = weapon_info.damage_bonus;
-
-                    let damage = i32::max(0, base_damage + attr_damage_bonus + skill_hit_bonus +
-                        skill_damage_bonus + weapon_damage_bonus);
-                    add_effect(
-                        Some(entity),
-                        EffectType::Damage{ amount: damage },
-                        Targets::Single{ target: wants_melee.target }
-                    );
-                    crate::gamelog::Logger::new()
-                        .npc_name(&name.name)
-                        .append("trafia:")
-                        .npc_name(&target_name.name)
-                        .append("za")
-                        .damage(damage)
-                        .append("HP.")
-                        .log();
-
-                    if let Some(chance) = &weapon_info.proc_chance {
-                        if crate::rng::roll_dice(1, 100) <= (chance * 100.0) as i32 {
-                            if let Some(target) = &weapon_info.proc_target {
-                                let effect_target = if target == "Self" {
-                                    Targets::Single{ target: entity }
-                                } else {
-                                    Targets::Single{ target: wants_melee.target }
-                                };
-                                add_effect(
-                                    Some(entity),
-                                    EffectType::ItemUse{ item: weapon_entity.unwrap() },
-                                    effect_target
-                                )
-                            }
-                        }
-                    }
-                } else if natural_roll == 1 {
-                    crate::gamelog::Logger::new()
-                        .npc_name(&name.name)
-                        .append("chce zaatakowac:")
-                        .npc_name(&target_name.name)
-                        .append("ale potyka sie o wlasne nogi.")
-                        .log();
-                    add_effect(
-                        None,
-                        EffectType::Particle{ glyph: rltk::to_cp437('‼'), fg: rltk::RGB::named(rltk::BLUE), bg: rltk::RGB::named(rltk::BLACK), lifespan: 200.0 },
-                        Targets::Single{ target: wants_melee.target }
-                    );
-                } else {
-                    crate::gamelog::Logger::new()
-                        .npc_name(&name.name)
-                        .append("atakuje:")
-                        .npc_name(&target_name.name)
-                        .append("ale bezskutecznie.")
-                        .log();
-                    add_effect(
-                        None,
-                        EffectType::Particle{ glyph: rltk::to_cp437('‼'), fg: rltk::RGB::named(rltk::CYAN), bg: rltk::RGB::named(rltk::BLACK), lifespan: 200.0 },
-                        Targets::Single{ target: wants_melee.target }
-                    );
-                }
+                let outcome = crate::rng::with_rng(|rng| {
+                    let profile = combat::choose_weapon(rng, entity, AttackKind::Melee, &entities, &equipped_items, &meleeweapons, &natural);
+                    combat::resolve_attack(rng, &attacker, &defender, &profile)
+                });
+                let pos = positions.get(entity).unwrap();
+                combat::apply_outcome(entity, wants_melee.target, AttackKind::Melee, &outcome, &name.name, &target_name.name, map.xy_idx(pos.x, pos.y));
             }
         }
 
         wants_melee.clear();
     }
-}
\ No newline at end of file
+}
@@ -9,10 +9,14 @@ mod ai;
 use ai::*;
 mod movement_system;
 use movement_system::MovementSystem;
+mod hazard_system;
+use hazard_system::HazardSystem;
 mod trigger_system;
 use trigger_system::TriggerSystem;
 mod melee_combat_system;
 use melee_combat_system::MeleeCombatSystem;
+mod maneuver_system;
+use maneuver_system::ManeuverSystem;
 mod ranged_combat_system;
 use ranged_combat_system::RangedCombatSystem;
 mod inventory_system;
@@ -14,19 +14,20 @@ impl<'a> System<'a> for MovementSystem {
                         WriteStorage<'a, EntityMoved>,
                         WriteStorage<'a, Viewshed>,
                         ReadExpect<'a, Entity>,
-                        WriteExpect<'a, RunState>);
+                        WriteExpect<'a, RunState>,
+                        WriteExpect<'a, rltk::Point>);
 
     fn run(&mut self, data: Self::SystemData) {
         let (map, mut position, entities, mut apply_move,
             mut apply_teleport, mut other_level, mut moved,
-            mut viewsheds, player_entity, mut runstate) = data;
+            mut viewsheds, player_entity, mut runstate, mut player_pos) = data;
 
         for (entity, teleport) in (&entities, &apply_teleport).join() {
-            if teleport.dest_depth == map.depth {
+            if teleport.dest_map == map.id() {
                 apply_move.insert(entity, ApplyMove{ dest_idx: map.xy_idx(teleport.dest_x, teleport.dest_y) })
                     .expect("Unable to insert");
             } else if entity == *player_entity {
-                *runstate = RunState::TeleportingToOtherLevel{ x: teleport.dest_x, y: teleport.dest_y, depth: teleport.dest_depth };
+                *runstate = RunState::TeleportingToOtherLevel{ x: teleport.dest_x, y: teleport.dest_y, map: teleport.dest_map };
             } else if let Some(pos) = position.get(entity) {
                 let idx = map.xy_idx(pos.x, pos.y);
                 let dest_idx = map.xy_idx(teleport.dest_x, teleport.dest_y);
@@ -34,7 +35,7 @@ impl<'a> System<'a> for MovementSystem {
                 other_level.insert(entity, OtherLevelPosition{
                     x: teleport.dest_x,
                     y: teleport.dest_y,
-                    depth: teleport.dest_depth })
+                    map: teleport.dest_map })
                     .expect("Unable to insert");
                 position.remove(entity);
             }
@@ -50,7 +51,11 @@ impl<'a> System<'a> for MovementSystem {
             if let Some(vs) = viewsheds.get_mut(entity) {
                 vs.dirty = true;
             }
-            moved.insert(entity, EntityMoved{}).expect("Unable to insert");
+            if entity == *player_entity {
+                player_pos.x = pos.x;
+                player_pos.y = pos.y;
+            }
+            moved.insert(entity, EntityMoved{ from: start_idx }).expect("Unable to insert");
         }
         apply_move.clear();
     }
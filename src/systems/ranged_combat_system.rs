@@ -1,6 +1,6 @@
 use specs::prelude::*;
-use crate::{Attributes, Skills, WantsToShoot, Name, HungerClock, HungerState, Pools, skill_bonus,
-    Skill, Equipped, Weapon, EquipmentSlot, WeaponAttribute, Wearable, NaturalAttackDefense, effects::*, Map, Position};
+use crate::{Attributes, Skills, WantsToShoot, Name, HungerClock, Pools, Equipped, Weapon, Wearable, NaturalAttackDefense, Magazine,
+    effects::*, Map, Position, Awareness, AwarenessState, combat::{self, AttackKind, Attacker, Defender}};
 use rltk::{to_cp437, RGB, Point};
 
 pub struct RangedCombatSystem {}
@@ -19,12 +19,15 @@ impl<'a> System<'a> for RangedCombatSystem {
                         ReadStorage<'a, Wearable>,
                         ReadStorage<'a, NaturalAttackDefense>,
                         ReadStorage<'a, Position>,
-                        ReadExpect<'a, Map>
+                        ReadExpect<'a, Map>,
+                        WriteStorage<'a, Magazine>,
+                        ReadExpect<'a, Entity>,
+                        ReadStorage<'a, Awareness>
                     );
 
     fn run(&mut self, data : Self::SystemData) {
         let (entities, mut wants_shoot, names, attributes, skills, hunger_clock,
-            pools, equipped_items, weapon, wearables, natural, positions, map) = data;
+            pools, equipped_items, weapon, wearables, natural, positions, map, mut magazines, player_entity, awareness) = data;
 
         for (entity, wants_shoot, name, attacker_attributes, attacker_skills, attacker_pools) in (&entities, &wants_shoot, &names, &attributes, &skills, &pools).join() {
             let target_pools = pools.get(wants_shoot.target).unwrap();
@@ -33,8 +36,28 @@ impl<'a> System<'a> for RangedCombatSystem {
             if attacker_pools.hit_points.current > 0 && target_pools.hit_points.current > 0 {
                 let target_name = names.get(wants_shoot.target).unwrap();
 
+                let profile = crate::rng::with_rng(|rng|
+                    combat::choose_weapon(rng, entity, AttackKind::Ranged, &entities, &equipped_items, &weapon, &natural)
+                );
                 let apos = positions.get(entity).unwrap();
                 let dpos = positions.get(wants_shoot.target).unwrap();
+
+                // Every shot takes one out of the magazine, and it may be found again where it landed
+                if let Some(magazine) = profile.item.and_then(|item| magazines.get_mut(item)) {
+                    if magazine.loaded < 1 {
+                        if entity == *player_entity {
+                            crate::gamelog::Logger::new().append("Bron Wieclawa nie jest nabita.").log();
+                        }
+                        continue;
+                    }
+                    magazine.loaded -= 1;
+                    add_effect(
+                        Some(entity),
+                        EffectType::SpentAmmo{ kind: magazine.ammo.clone() },
+                        Targets::Tile{ tile_idx: map.xy_idx(dpos.x, dpos.y) as i32 }
+                    );
+                }
+
                 add_effect(
                     None,
                     EffectType::ParticleProjectile{
@@ -52,136 +75,20 @@ impl<'a> System<'a> for RangedCombatSystem {
                     Targets::Tile{tile_idx : map.xy_idx(apos.x, apos.y) as i32}
                 );
 
-                let mut weapon_info = Weapon{
-                    range: None,
-                    attribute: WeaponAttribute::Might,
-                    hit_bonus : 0,
-                    damage_n_dice : 1,
-                    damage_die_type: 4,
-                    damage_bonus : 0,
-                    proc_chance : None,
-                    proc_target : None
+                let attacker = Attacker{
+                    attributes: attacker_attributes,
+                    skills: attacker_skills,
+                    well_fed: combat::is_well_fed(entity, &hunger_clock)
                 };
-
-                if let Some(nat) = natural.get(entity) {
-                    if !nat.attacks.is_empty() {
-                        let attack_index = if nat.attacks.len()==1 { 0 } else { crate::rng::roll_dice(1, nat.attacks.len() as i32) as usize -1 };
-                        weapon_info.hit_bonus = nat.attacks[attack_index].hit_bonus;
-                        weapon_info.damage_n_dice = nat.attacks[attack_index].damage_n_dice;
-                        weapon_info.damage_die_type = nat.attacks[attack_index].damage_die_type;
-                        weapon_info.damage_bonus = nat.attacks[attack_index].damage_bonus;
-                    }
-                }
-
-                let mut weapon_entity : Option<Entity> = None;
-                for (weaponentity,wielded,melee) in (&entities, &equipped_items, &weapon).join() {
-                    if wielded.owner == entity && wielded.slot == EquipmentSlot::Melee {
-                        weapon_info = melee.clone();
-                        weapon_entity = Some(weaponentity);
-                    }
-                }
-
-                let natural_roll = crate::rng::roll_dice(1, 20);
-                let attribute_hit_bonus = if weapon_info.attribute == WeaponAttribute::Might
-                    { attacker_attributes.might.bonus }
-                    else { attacker_attributes.quickness.bonus };
-                let skill_hit_bonus = skill_bonus(Skill::Melee, &*attacker_skills);
-                let weapon_hit_bonus = weapon_info.hit_bonus;
-                let mut status_hit_bonus = 0;
-                if let Some(hc) = hunger_clock.get(entity) {
-                    if hc.state == HungerState::WellFed {
-                        status_hit_bonus += 1;
-                    }
-                }
-                let modified_hit_roll = natural_roll + attribute_hit_bonus + skill_hit_bonus
-                    + weapon_hit_bonus + status_hit_bonus;
-                //println!("Natural roll: {}", natural.roll);
-                //println!("Modified hit roll: {}, modified_hit_roll");
-
-                let mut armor_item_bonus_f = 0.0;
-                for (wielded,armor) in (&equipped_items, &wearables).join() {
-                    if wielded.owner == wants_shoot.target {
-                        armor_item_bonus_f += armor.armor_class;
-                    }
-                }
-                let base_armor_class = match natural.get(wants_shoot.target) {
-                    None => 10,
-                    Some(nat) => nat.armor_class.unwrap_or(10)
+                let defender = Defender{
+                    attributes: target_attributes,
+                    skills: target_skills,
+                    natural_armor: natural.get(wants_shoot.target).and_then(|nat| nat.armor_class),
+                    item_armor: combat::worn_armor(wants_shoot.target, &equipped_items, &wearables),
+                    unaware: awareness.get(wants_shoot.target).is_some_and(|a| a.state == AwarenessState::Asleep || a.state == AwarenessState::Unaware)
                 };
-                let armor_quickness_bonus = target_attributes.quickness.bonus;
-                let armor_skill_bonus = skill_bonus(Skill::Defense, &*target_skills);
-                let armor_item_bonus = armor_item_bonus_f as i32;
-                let armor_class = base_armor_class + armor_quickness_bonus + armor_skill_bonus + armor_item_bonus;
-
-                //println!("Armor class: {}, armor_class");
-                if natural_roll != 1 && (natural_roll == 20 || modified_hit_roll > armor_class) {
-                    let base_damage = crate::rng::roll_dice(weapon_info.damage_n_dice, weapon_info.damage_die_type);
-                    let attr_damage_bonus = attacker_attributes.might.bonus;
-                    let skill_damage_bonus = skill_bonus(Skill::Melee, &*attacker_skills);
-                    let weapon_damage_bonus = weapon_info.damage_bonus;
-
-                    let damage = i32::max(0, base_damage + attr_damage_bonus + skill_damage_bonus + weapon_damage_bonus);
-
-                    /*println!("Damage: {} + {}attr + {}skill + {}weapon = {}",
-                        base_damage, attr_damage_bonus, skill_damage_bonus, weapon_damage_bonus, damage);*/
-                    add_effect(
-                        Some(entity),
-                        EffectType::Damage{ amount: damage },
-                        Targets::Single{ target: wants_shoot.target }
-                    );
-                    crate::gamelog::Logger::new()
-                        .npc_name(&name.name)
-                        .append("trafia:")
-                        .npc_name(&target_name.name)
-                        .append("za")
-                        .damage(damage)
-                        .append("HP.")
-                        .log();
-
-                    if let Some(chance) = &weapon_info.proc_chance {
-                        let roll = crate::rng::roll_dice(1, 100);
-                        //println!("Roll {}, Chance {}, roll, chance");
-                        if roll <= (chance * 100.0) as i32 {
-                            //println!("Proc");
-                            if let Some(target) = &weapon_info.proc_target {
-                                let effect_target = if target == "Self" {
-                                    Targets::Single{ target: entity }
-                                } else {
-                                    Targets::Single{ target: wants_shoot.target }
-                                };
-                                add_effect(
-                                    Some(entity),
-                                    EffectType::ItemUse{ item: weapon_entity.unwrap() },
-                                    effect_target
-                                )
-                            }
-                        }
-                    }
-                } else if natural_roll == 1 {
-                    crate::gamelog::Logger::new()
-                        .npc_name(&name.name)
-                        .append("chce strzelic w:")
-                        .npc_name(&target_name.name)
-                        .append("ale za pózno reaguje.")
-                        .log();
-                    add_effect(
-                        None,
-                        EffectType::Particle{ glyph: rltk::to_cp437('‼'), fg: rltk::RGB::named(rltk::BLUE), bg: rltk::RGB::named(rltk::BLACK), lifespan: 200.0 },
-                        Targets::Single{ target: wants_shoot.target }
-                    );
-                } else {
-                    crate::gamelog::Logger::new()
-                        .npc_name(&name.name)
-                        .append("atakuje:")
-                        .npc_name(&target_name.name)
-                        .append("ale nie trafia.")
-                        .log();
-                    add_effect(
-                        None,
-                        EffectType::Particle{ glyph: rltk::to_cp437('‼'), fg: rltk::RGB::named(rltk::CYAN), bg: rltk::RGB::named(rltk::BLACK), lifespan: 200.0 },
-                        Targets::Single{ target: wants_shoot.target }
-                    );
-                }
+                let outcome = crate::rng::with_rng(|rng| combat::resolve_attack(rng, &attacker, &defender, &profile));
+                combat::apply_outcome(entity, wants_shoot.target, AttackKind::Ranged, &outcome, &name.name, &target_name.name, map.xy_idx(apos.x, apos.y));
             }
         }
 
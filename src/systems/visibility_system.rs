@@ -1,5 +1,5 @@
 use specs::prelude::*;
-use crate::{Viewshed, Position, Map, Player, Hidden, Name, BlocksVisibility};
+use crate::{Viewshed, Position, Map, Player, Hidden, Name, BlocksVisibility, TileType, effects::{add_effect, EffectType, Targets}};
 use rltk::{field_of_view, Point};
 
 pub struct VisibilitySystem {}
@@ -29,6 +29,7 @@ impl<'a> System<'a> for VisibilitySystem {
                 viewshed.dirty = false;
                 viewshed.visible_tiles = field_of_view(Point::new(pos.x, pos.y), viewshed.range, &*map);
                 viewshed.visible_tiles.retain(|p| p.x >= 0 && p.x < map.width && p.y >= 0 && p.y < map.height );
+                viewshed.visible_tiles.sort_by_key(|p| (p.y, p.x));
 
                 let _p : Option<&Player> = player.get(ent);
                 if let Some(_p) = _p {
@@ -38,6 +39,15 @@ impl<'a> System<'a> for VisibilitySystem {
                         map.revealed_tiles[idx] = true;
                         map.visible_tiles[idx] = true;
 
+                        // A monster seen in the wall gives the secret door away
+                        if map.tiles[idx] == TileType::SecretDoor {
+                            let mut occupied = false;
+                            crate::spatial::for_each_tile_content(idx, |_| occupied = true);
+                            if occupied {
+                                add_effect(None, EffectType::RevealSecretDoor, Targets::Tile{ tile_idx: idx as i32 });
+                            }
+                        }
+
                         crate::spatial::for_each_tile_content(idx, |e| {
                             let maybe_hidden = hidden.get(e);
                             if let Some(_maybe_hidden) = maybe_hidden {
@@ -0,0 +1,110 @@
+use std::sync::Mutex;
+use specs::prelude::*;
+use rltk::Point;
+use wieclaw_rl::{headless::Simulation, raws::{RAWS, SpawnType, spawn_named_entity}, Map, Pools, Position, PlayerCommand,
+    tile_walkable, Equipped, Weapon, CursedItem, effects::{add_effect, run_effects_queue, EffectType, Targets}};
+
+// The simulation keeps its spatial index, effect queue and generator in globals, so only one runs at a time
+static SIMULATION : Mutex<()> = Mutex::new(());
+
+fn player_pos(sim : &Simulation) -> Point {
+    *sim.ecs().fetch::<Point>()
+}
+
+fn player_hp(sim : &Simulation) -> (i32, i32) {
+    let player = *sim.ecs().fetch::<Entity>();
+    let pools = sim.ecs().read_storage::<Pools>();
+    let hp = &pools.get(player).unwrap().hit_points;
+    (hp.current, hp.max)
+}
+
+fn free_neighbour(sim : &Simulation) -> (i32, i32) {
+    let pos = player_pos(sim);
+    let map = sim.ecs().fetch::<Map>();
+    for (dx, dy) in [(1, 0), (-1, 0), (0, 1), (0, -1), (1, 1), (-1, -1), (1, -1), (-1, 1)] {
+        let idx = map.xy_idx(pos.x + dx, pos.y + dy);
+        if tile_walkable(map.tiles[idx]) && !wieclaw_rl::spatial::is_blocked(idx) {
+            return (dx, dy);
+        }
+    }
+    panic!("player is walled in");
+}
+
+#[test]
+fn moving_updates_the_player_position() {
+    let _lock = SIMULATION.lock().unwrap_or_else(|e| e.into_inner());
+    let mut sim = Simulation::with_seed(Some(1));
+    let start = player_pos(&sim);
+    let (dx, dy) = free_neighbour(&sim);
+
+    sim.step(PlayerCommand::Move{ dx, dy });
+
+    let end = player_pos(&sim);
+    assert_eq!(end, Point::new(start.x + dx, start.y + dy));
+    let player = *sim.ecs().fetch::<Entity>();
+    let position = sim.ecs().read_storage::<Position>().get(player).cloned().unwrap();
+    assert_eq!((position.x, position.y), (end.x, end.y));
+}
+
+#[test]
+fn same_seed_and_script_replay_the_same_run() {
+    let _lock = SIMULATION.lock().unwrap_or_else(|e| e.into_inner());
+    let run = || {
+        let mut sim = Simulation::with_seed(Some(42));
+        let (dx, dy) = free_neighbour(&sim);
+        let script = [PlayerCommand::Move{ dx, dy }, PlayerCommand::Wait, PlayerCommand::Move{ dx: -dx, dy: -dy }, PlayerCommand::Wait];
+        for _ in 0..5 {
+            for command in script.iter() {
+                sim.step(*command);
+            }
+        }
+        (player_pos(&sim), player_hp(&sim))
+    };
+
+    let (first_pos, first_hp) = run();
+    let (second_pos, second_hp) = run();
+    assert_eq!(first_pos, second_pos);
+    assert_eq!(first_hp, second_hp);
+    assert!(first_hp.0 > 0 && first_hp.0 <= first_hp.1);
+}
+
+#[test]
+fn attacking_a_monster_hurts_it() {
+    let _lock = SIMULATION.lock().unwrap_or_else(|e| e.into_inner());
+    let mut sim = Simulation::with_seed(Some(3));
+    let player = *sim.ecs().fetch::<Entity>();
+    sim.ecs_mut().write_storage::<Pools>().get_mut(player).unwrap().god_mode = true;
+    let (dx, dy) = free_neighbour(&sim);
+    let pos = player_pos(&sim);
+    let wolf = spawn_named_entity(&RAWS.lock().unwrap(), sim.ecs_mut(), "Wilk", SpawnType::AtPosition{ x: pos.x + dx, y: pos.y + dy })
+        .expect("no wolf in the raws");
+    sim.step(PlayerCommand::Wait);
+    let max_hp = sim.ecs().read_storage::<Pools>().get(wolf).unwrap().hit_points.max;
+
+    for _ in 0..20 {
+        if !sim.ecs().is_alive(wolf) { break; }
+        sim.step(PlayerCommand::Move{ dx, dy });
+    }
+
+    let hurt = !sim.ecs().is_alive(wolf) || sim.ecs().read_storage::<Pools>().get(wolf).unwrap().hit_points.current < max_hp;
+    assert!(hurt, "twenty swings and the wolf is untouched");
+    assert_eq!(player_pos(&sim), pos, "the player should stay put while fighting");
+}
+
+#[test]
+fn a_cursed_weapon_is_chipped_instead_of_dropped() {
+    let _lock = SIMULATION.lock().unwrap_or_else(|e| e.into_inner());
+    let mut sim = Simulation::with_seed(Some(5));
+    let player = *sim.ecs().fetch::<Entity>();
+    let pick = spawn_named_entity(&RAWS.lock().unwrap(), sim.ecs_mut(), "Kilof", SpawnType::Equipped{ by: player })
+        .expect("no pick in the raws");
+    sim.ecs_mut().write_storage::<CursedItem>().insert(pick, CursedItem{}).expect("Unable to insert");
+    let bonus = sim.ecs().read_storage::<Weapon>().get(pick).unwrap().damage_bonus;
+
+    add_effect(Some(player), EffectType::DropWeapon{ item: pick }, Targets::Single{ target: player });
+    run_effects_queue(sim.ecs_mut());
+
+    assert!(sim.ecs().read_storage::<Equipped>().get(pick).is_some_and(|e| e.owner == player));
+    assert!(sim.ecs().read_storage::<Position>().get(pick).is_none());
+    assert_eq!(sim.ecs().read_storage::<Weapon>().get(pick).unwrap().damage_bonus, bonus - 1);
+}